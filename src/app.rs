@@ -7,23 +7,90 @@
 //! - Loading states with skeleton placeholders to prevent layout shift
 //! - Error handling for failed API requests and not-found routes
 
-use crate::app_server::{get_note, get_notes};
-use crate::note::Note;
+use crate::app_server::{
+    get_assets, get_backlinks, get_corpus_stats, get_graph, get_not_found_note, get_note,
+    get_notes, get_related, get_tag_index, get_tag_tree, get_tags, random_note, search_notes,
+    NoteApiError,
+};
+use crate::note::{
+    truncate_words, NoteMetadata, NoteSort, ReferenceGraph, TagNode, TocEntry, DEFAULT_API_PREFIX,
+};
+use http::StatusCode;
 use leptos::prelude::*;
 use leptos::wasm_bindgen::JsCast;
-use leptos_meta::{provide_meta_context, MetaTags, Script, Stylesheet, Title};
-use leptos_router::hooks::use_params;
+use leptos_meta::{provide_meta_context, HashedStylesheet, Meta, MetaTags, Stylesheet, Title};
+use leptos_router::hooks::{use_location, use_navigate, use_params};
 use leptos_router::params::Params;
 use leptos_router::SsrMode;
 use leptos_router::{
     components::{Route, Router, Routes, A},
     path,
 };
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 // Application-wide constants used for branding and display
 const APP_TITLE: &str = "MiniRef";
 const APP_SUBTITLE: &str = "Digital Zettelkasten";
 
+/// Number of notes loaded on the home page at a time
+const NOTES_PAGE_SIZE: usize = 24;
+
+/// Number of recently viewed notes listed in the sidebar
+const RECENT_NOTES_LIMIT: usize = 8;
+
+/// `localStorage` key the recently viewed notes are saved under
+const RECENT_NOTES_KEY: &str = "miniref.recent-notes";
+
+/// Width and height of the reference graph drawing, in SVG units
+const GRAPH_SIZE: f64 = 800.0;
+
+/// Maximum length of the description shown in link previews of a note
+const DESCRIPTION_LENGTH: usize = 160;
+
+/// Width, in pixels, of the image thumbnails in a note's attachments; twice
+/// their displayed width so they stay sharp on high-density screens
+const THUMBNAIL_WIDTH: u32 = 320;
+
+/// KaTeX stylesheet, which loads KaTeX's fonts from next to it
+#[cfg(not(feature = "self-hosted-assets"))]
+const KATEX_CSS_URL: &str = "https://cdn.jsdelivr.net/npm/katex@0.16.19/dist/katex.min.css";
+#[cfg(feature = "self-hosted-assets")]
+const KATEX_CSS_URL: &str = "/vendor/katex/katex.min.css";
+
+/// Stylesheet coloring code highlighted on the server, generated from the
+/// configured syntect theme
+const HIGHLIGHT_CSS_URL: &str = "/highlight.css";
+
+/// `Content-Security-Policy` the server sends unless configured otherwise.
+///
+/// Allows the KaTeX stylesheet and fonts loaded from jsDelivr. Scripts and
+/// styles may also be inline, for the hydration scripts and the inline styles
+/// of rendered math, and WebAssembly may be compiled.
+/// Images can come from anywhere, since notes may embed remote images.
+#[cfg(not(feature = "self-hosted-assets"))]
+pub const DEFAULT_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; \
+    script-src 'self' 'unsafe-inline' 'wasm-unsafe-eval'; \
+    style-src 'self' 'unsafe-inline' https://cdn.jsdelivr.net; \
+    font-src 'self' https://cdn.jsdelivr.net; \
+    img-src 'self' data: https:; \
+    object-src 'none'; \
+    base-uri 'self'; \
+    frame-ancestors 'none'";
+
+/// `Content-Security-Policy` the server sends unless configured otherwise.
+///
+/// Like the policy for assets loaded from jsDelivr, but with KaTeX served by
+/// the app itself, only the app's origin is allowed.
+#[cfg(feature = "self-hosted-assets")]
+pub const DEFAULT_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; \
+    script-src 'self' 'unsafe-inline' 'wasm-unsafe-eval'; \
+    style-src 'self' 'unsafe-inline'; \
+    img-src 'self' data: https:; \
+    object-src 'none'; \
+    base-uri 'self'; \
+    frame-ancestors 'none'";
+
 /// Skeleton loader for note cards that provides a loading placeholder
 /// matching the structure and dimensions of a real note card.
 ///
@@ -73,6 +140,78 @@ fn NotePageSkeleton() -> impl IntoView {
     }
 }
 
+/// Sets the HTTP status code of the server-rendered response.
+///
+/// Only has an effect during SSR; in the browser the response has already
+/// been received.
+fn set_response_status(status: StatusCode) {
+    #[cfg(feature = "ssr")]
+    if let Some(response) = use_context::<leptos_axum::ResponseOptions>() {
+        response.set_status(status);
+    }
+    #[cfg(not(feature = "ssr"))]
+    let _ = status;
+}
+
+/// Error page with a link back to the notes list.
+///
+/// # Props
+/// * `status` - HTTP status code the server responds with when rendering this page
+/// * `title` - Heading describing the error
+/// * `message` - Optional details shown below the heading
+#[component]
+fn ErrorPage(
+    status: StatusCode,
+    #[prop(into)] title: String,
+    #[prop(optional, into)] message: Option<String>,
+) -> impl IntoView {
+    // Lets crawlers and clients tell a missing note from a rendered one
+    set_response_status(status);
+
+    view! {
+        <div class="error-page">
+            <h1>{title}</h1>
+            {message.map(|message| view! { <p>{message}</p> })}
+            <A href="/">"← Back to notes"</A>
+        </div>
+    }
+}
+
+/// Not-found page, showing the content of the note configured for it.
+///
+/// Falls back to a plain "Note not found" [`ErrorPage`] when that note doesn't
+/// exist or can't be loaded. Either way the server responds with a 404.
+#[component]
+fn NotFoundPage() -> impl IntoView {
+    let custom = Resource::new(
+        || (),
+        |_| async { get_not_found_note().await.ok().flatten() },
+    );
+
+    view! {
+        <Suspense fallback=|| ()>
+            {move || custom.get().map(|note| match note {
+                Some(note) => {
+                    set_response_status(StatusCode::NOT_FOUND);
+                    view! {
+                        <Title text=note.title.clone()/>
+                        <div class=format!("note-full not-found {}", note.layout.css_class())>
+                            <h1 class="note-title">{note.title}</h1>
+                            <div class="note-content" inner_html=note.content/>
+                            <A href="/">"← Back to notes"</A>
+                        </div>
+                    }
+                    .into_any()
+                }
+                None => view! {
+                    <ErrorPage status=StatusCode::NOT_FOUND title="404" message="Note not found"/>
+                }
+                .into_any(),
+            })}
+        </Suspense>
+    }
+}
+
 /// Card component for displaying a note preview in the notes grid.
 ///
 /// # Props
@@ -81,18 +220,25 @@ fn NotePageSkeleton() -> impl IntoView {
 /// Displays:
 /// - Note ID
 /// - Title (linked to full note view)
+/// - Excerpt of the content
 /// - Tags
 /// - References to other notes
+///
+/// # Props
+/// * `selected` - Whether the card is selected with keyboard navigation
 #[component]
-fn NoteCard(note: Note) -> impl IntoView {
+fn NoteCard(note: NoteMetadata, #[prop(optional, into)] selected: Signal<bool>) -> impl IntoView {
     view! {
-        <article class="note">
+        <article class="note" class:selected=selected>
             <div class="note-header">
                 <span class="note-id">{note.id.clone()}</span>
             </div>
             <h2 class="note-title">
                 <A href=format!("/{}", note.id)>{note.title}</A>
             </h2>
+            {(!note.excerpt.is_empty()).then(|| view! {
+                <p class="note-excerpt">{note.excerpt}</p>
+            })}
             <div class="tags">
                 {note.tags.into_iter().map(|tag| {
                     view! { <span class="tag">{tag}</span> }
@@ -111,6 +257,364 @@ fn NoteCard(note: Note) -> impl IntoView {
     }
 }
 
+/// Renders table of contents entries as a nested list of anchor links.
+///
+/// Each entry's children are the entries that follow it with a deeper heading
+/// level, up to the next entry at the same or a shallower level.
+fn toc_list(entries: &[TocEntry]) -> AnyView {
+    let mut items = Vec::new();
+    let mut i = 0;
+    while i < entries.len() {
+        let entry = &entries[i];
+        let end = entries[i + 1..]
+            .iter()
+            .position(|next| next.level <= entry.level)
+            .map(|offset| i + 1 + offset)
+            .unwrap_or(entries.len());
+        let children = &entries[i + 1..end];
+        let nested = (!children.is_empty()).then(|| toc_list(children));
+
+        items.push(view! {
+            <li>
+                <a href=format!("#{}", entry.slug)>{entry.text.clone()}</a>
+                {nested}
+            </li>
+        });
+        i = end;
+    }
+    view! { <ul>{items}</ul> }.into_any()
+}
+
+/// Table of contents for a note, generated from its headings.
+///
+/// # Props
+/// * `entries` - The note's TOC entries in document order
+///
+/// Renders nothing when the note has no headings.
+#[component]
+fn TableOfContents(entries: Vec<TocEntry>) -> impl IntoView {
+    (!entries.is_empty()).then(|| {
+        view! {
+            <nav class="toc">
+                <h3>"Contents"</h3>
+                {toc_list(&entries)}
+            </nav>
+        }
+    })
+}
+
+/// Search box component that queries notes as the user types.
+///
+/// Displays a list of matching notes (title matches first) below the input,
+/// each linking to the full note view.
+#[component]
+fn SearchBox() -> impl IntoView {
+    let (query, set_query) = signal(String::new());
+
+    // Re-run the search whenever the query changes
+    let results = Resource::new(
+        move || query.get(),
+        |query: String| async move {
+            if query.trim().is_empty() {
+                Vec::new()
+            } else {
+                search_notes(query).await.unwrap_or_default()
+            }
+        },
+    );
+
+    view! {
+        <div class="search">
+            <input
+                type="search"
+                class="search-input"
+                placeholder="Search notes..."
+                prop:value=query
+                on:input=move |ev| set_query.set(event_target_value(&ev))
+            />
+            <Transition fallback=|| ()>
+                <ul class="search-results">
+                    {move || results.get().map(|results| {
+                        results.into_iter().map(|meta| {
+                            view! {
+                                <li>
+                                    <A href=format!("/{}", meta.id)>{meta.title}</A>
+                                </li>
+                            }
+                        }).collect_view()
+                    })}
+                </ul>
+            </Transition>
+        </div>
+    }
+}
+
+/// Tag cloud that toggles tags in and out of the home page filter.
+///
+/// # Props
+/// * `selected` - The tags currently filtering the notes grid
+///
+/// Tags used by more notes are drawn larger; selected tags are highlighted.
+#[component]
+fn TagCloud(selected: RwSignal<Vec<String>>) -> impl IntoView {
+    let tags = Resource::new(
+        || (),
+        |_| async move { get_tags().await.unwrap_or_default() },
+    );
+
+    view! {
+        <Suspense fallback=|| ()>
+            {move || tags.get().map(|tags| {
+                let max = tags.iter().map(|(_, count)| *count).max().unwrap_or(1);
+                view! {
+                    <div class="tag-cloud">
+                        {tags.into_iter().map(|(tag, count)| {
+                            let size = 0.75 + 0.5 * count as f64 / max as f64;
+                            let is_selected = {
+                                let tag = tag.clone();
+                                move || selected.with(|selected| selected.contains(&tag))
+                            };
+                            let toggle = {
+                                let tag = tag.clone();
+                                move |_| selected.update(|selected| {
+                                    if let Some(index) = selected.iter().position(|t| *t == tag) {
+                                        selected.remove(index);
+                                    } else {
+                                        selected.push(tag.clone());
+                                    }
+                                })
+                            };
+                            view! {
+                                <button
+                                    class="tag"
+                                    class:selected=is_selected
+                                    style=format!("font-size: {:.2}rem", size)
+                                    title=format!("{} notes", count)
+                                    on:click=toggle
+                                >
+                                    {tag}
+                                </button>
+                            }
+                        }).collect_view()}
+                    </div>
+                }
+            })}
+        </Suspense>
+    }
+}
+
+/// Returns the key pressed for a keyboard shortcut, if the event is one.
+///
+/// Key presses with a modifier held, or while typing in a text field (like
+/// the search box), aren't shortcuts.
+fn shortcut_key(event: &web_sys::KeyboardEvent) -> Option<String> {
+    if event.ctrl_key() || event.meta_key() || event.alt_key() {
+        return None;
+    }
+    let typing = event
+        .target()
+        .and_then(|target| target.dyn_into::<web_sys::HtmlElement>().ok())
+        .is_some_and(|element| {
+            matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+                || element.is_content_editable()
+        });
+    (!typing).then(|| event.key())
+}
+
+/// Sidebar link that opens a randomly chosen note.
+///
+/// Does nothing if there are no notes or the request fails.
+#[component]
+fn RandomNoteLink() -> impl IntoView {
+    let navigate = use_navigate();
+    let open_random = move |_| {
+        let navigate = navigate.clone();
+        leptos::task::spawn_local(async move {
+            if let Ok(note) = random_note().await {
+                navigate(&format!("/{}", note.id), Default::default());
+            }
+        });
+    };
+
+    view! {
+        <button class="random-note" on:click=open_random>
+            "Random note"
+        </button>
+    }
+}
+
+/// Footer summarizing the published notes: how many there are, their tags,
+/// references, and orphans, and their average length.
+///
+/// Renders nothing while loading or if the request fails.
+#[component]
+fn CorpusStatsPanel() -> impl IntoView {
+    let stats = Resource::new(|| (), |_| async move { get_corpus_stats().await.ok() });
+
+    view! {
+        <Suspense fallback=|| ()>
+            {move || stats.get().flatten().filter(|stats| stats.total_notes > 0).map(|stats| view! {
+                <footer class="corpus-stats">
+                    <span>{stats.total_notes} " notes"</span>
+                    <span>{stats.total_tags} " tags"</span>
+                    <span>{stats.total_references} " references"</span>
+                    <span>{stats.orphan_notes} " orphans"</span>
+                    <span>{format!("{:.0}", stats.average_word_count)} " words on average"</span>
+                </footer>
+            })}
+        </Suspense>
+    }
+}
+
+/// Collapsible tree of tags nested on `/`, shown in the sidebar.
+///
+/// Each tag links to its tag page and shows how many notes carry it or a tag
+/// nested under it.
+#[component]
+fn TagTree() -> impl IntoView {
+    let tree = Resource::new(
+        || (),
+        |_| async move { get_tag_tree().await.unwrap_or_default() },
+    );
+
+    view! {
+        <Suspense fallback=|| ()>
+            {move || tree.get().filter(|root| !root.children.is_empty()).map(|root| view! {
+                <div class="tag-tree">
+                    <h3>"Tags"</h3>
+                    {tag_tree_view(root.children)}
+                </div>
+            })}
+        </Suspense>
+    }
+}
+
+/// Renders a level of the tag hierarchy as a list, with tags that have
+/// nested tags collapsible.
+fn tag_tree_view(nodes: Vec<TagNode>) -> AnyView {
+    view! {
+        <ul>
+            {nodes.into_iter().map(|node| {
+                let label = view! {
+                    <span class="tag-name" title=node.path.clone()>
+                        <A href=format!("/tags/{}", node.path)>{node.name}</A>
+                    </span>
+                    <span class="tag-count">{node.count}</span>
+                };
+                if node.children.is_empty() {
+                    view! { <li>{label}</li> }.into_any()
+                } else {
+                    view! {
+                        <li>
+                            <details>
+                                <summary>{label}</summary>
+                                {tag_tree_view(node.children)}
+                            </details>
+                        </li>
+                    }
+                    .into_any()
+                }
+            }).collect_view()}
+        </ul>
+    }
+    .into_any()
+}
+
+/// A note in the sidebar's list of recently viewed notes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct RecentNote {
+    id: String,
+    title: String,
+}
+
+/// The recently viewed notes, newest first, shared by the sidebar and the
+/// note page through context.
+///
+/// The list is kept in the browser's `localStorage`, so it's only filled in
+/// on the client; the server renders it empty.
+#[derive(Clone, Copy)]
+struct RecentNotes(RwSignal<Vec<RecentNote>>);
+
+impl RecentNotes {
+    /// Reads the saved list, empty if nothing valid was saved
+    fn load() -> Vec<RecentNote> {
+        window()
+            .local_storage()
+            .ok()
+            .flatten()
+            .and_then(|storage| storage.get_item(RECENT_NOTES_KEY).ok().flatten())
+            .and_then(|saved| serde_json::from_str(&saved).ok())
+            .unwrap_or_default()
+    }
+
+    /// Moves a note to the top of the list and saves it.
+    ///
+    /// Starts from the saved list rather than the signal, which may not be
+    /// loaded yet. Failing to save, as with storage disabled, only keeps the
+    /// list from outliving the page.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the viewed note
+    /// * `title` - Title of the viewed note
+    fn remember(self, id: &str, title: &str) {
+        let mut notes = Self::load();
+        notes.retain(|note| note.id != id);
+        notes.insert(
+            0,
+            RecentNote {
+                id: id.to_string(),
+                title: title.to_string(),
+            },
+        );
+        notes.truncate(RECENT_NOTES_LIMIT);
+
+        if let (Ok(Some(storage)), Ok(saved)) =
+            (window().local_storage(), serde_json::to_string(&notes))
+        {
+            let _ = storage.set_item(RECENT_NOTES_KEY, &saved);
+        }
+        self.0.set(notes);
+    }
+}
+
+/// Sidebar list of the recently viewed notes, with the open note marked
+/// `active`.
+///
+/// Renders nothing until a note has been viewed, which includes the server
+/// render.
+#[component]
+fn RecentNotesList() -> impl IntoView {
+    let recent = expect_context::<RecentNotes>();
+    let pathname = use_location().pathname;
+
+    // The path is percent-encoded, while note links use the plain ID. Only
+    // runs on the client, since the list is empty on the server.
+    let is_open = move |id: &str| {
+        js_sys::decode_uri_component(&pathname.get())
+            .ok()
+            .and_then(|path| path.as_string())
+            .is_some_and(|path| path.strip_prefix('/') == Some(id))
+    };
+
+    view! {
+        <Show when=move || !recent.0.read().is_empty()>
+            <div class="recent-notes">
+                <h3>"Recent"</h3>
+                <ul>
+                    {move || recent.0.get().into_iter().map(|note| {
+                        let id = note.id.clone();
+                        view! {
+                            <li class:active=move || is_open(&id)>
+                                <A href=format!("/{}", note.id)>{note.title}</A>
+                            </li>
+                        }
+                    }).collect_view()}
+                </ul>
+            </div>
+        </Show>
+    }
+}
+
 /// The application shell component that provides the basic HTML structure
 /// and loads necessary scripts and styles for the application.
 ///
@@ -125,7 +629,9 @@ pub fn shell(options: LeptosOptions) -> impl IntoView {
                 <meta charset="utf-8"/>
                 <meta name="viewport" content="width=device-width, initial-scale=1"/>
                 <AutoReload options=options.clone()/>
-                <HydrationScripts options/>
+                <HydrationScripts options=options.clone()/>
+                // The build names the app stylesheet by its content hash
+                <HashedStylesheet options id="leptos"/>
                 <MetaTags/>
             </head>
             <body>
@@ -139,29 +645,24 @@ pub fn shell(options: LeptosOptions) -> impl IntoView {
 ///
 /// This component:
 /// - Provides meta context for document head management
-/// - Loads required stylesheets (Leptos, KaTeX, highlight.js)
+/// - Provides the recently viewed notes, loaded from `localStorage` once hydrated
+/// - Loads required stylesheets (KaTeX, code highlighting)
 /// - Sets up the router with main layout structure
 /// - Handles 404 cases with a fallback route
 #[component]
 pub fn App() -> impl IntoView {
     provide_meta_context();
 
+    // Effects only run on the client, after hydration, so the first render
+    // matches the server's empty list
+    let recent = RecentNotes(RwSignal::new(Vec::new()));
+    provide_context(recent);
+    Effect::new(move |_| recent.0.set(RecentNotes::load()));
+
     view! {
         // Load required stylesheets
-        <Stylesheet id="leptos" href="/pkg/miniref.css"/>
-        <Stylesheet
-            id="katex"
-            href="https://cdn.jsdelivr.net/npm/katex@0.16.19/dist/katex.min.css"
-        />
-        <Stylesheet
-            id="hljs"
-            href="https://cdn.jsdelivr.net/gh/highlightjs/cdn-release@latest/build/styles/base16/ocean.min.css"
-        />
-        // Load syntax highlighting script
-        <Script
-            src="https://cdn.jsdelivr.net/gh/highlightjs/cdn-release@latest/build/highlight.min.js"
-            defer="defer"
-        />
+        <Stylesheet id="katex" href=KATEX_CSS_URL/>
+        <Stylesheet id="highlight" href=HIGHLIGHT_CSS_URL/>
 
         <Title text=APP_TITLE/>
 
@@ -171,16 +672,21 @@ pub fn App() -> impl IntoView {
                     <div class="sigil"></div>
                     <div class="nav-links">
                         <A href="/">"Notes"</A>
+                        <A href="/graph">"Graph"</A>
+                        <RandomNoteLink/>
                     </div>
+                    <RecentNotesList/>
+                    <TagTree/>
                 </nav>
                 <Routes fallback=|| view! {
-                    <div class="error-page">
-                        <h1>"404"</h1>
-                        <p>"Note not found"</p>
-                    </div>
+                    <NotFoundPage/>
                 }>
                     <Route path=path!("/") view=HomePage/>
-                    <Route path=path!("/:note_id") view=NotePage ssr=SsrMode::Async />
+                    <Route path=path!("/graph") view=GraphPage/>
+                    // Wildcard so nested tags like `project/miniref` resolve
+                    <Route path=path!("/tags/*tag") view=TagPage/>
+                    // Wildcard so nested note IDs like `projects/foo` resolve; keep this last
+                    <Route path=path!("/*note_id") view=NotePage ssr=SsrMode::Async />
                 </Routes>
             </main>
         </Router>
@@ -190,14 +696,79 @@ pub fn App() -> impl IntoView {
 /// Home page component that displays a grid of all available notes.
 ///
 /// Features:
-/// - Fetches all notes using a Resource
+/// - Fetches notes a page at a time using a Resource
+/// - Provides a search box for full-text search
+/// - Filters notes by the tags selected in a tag cloud
 /// - Shows skeleton loading state while loading
 /// - Handles errors with user-friendly messages
-/// - Displays notes in a responsive grid layout
+/// - Displays notes in a responsive grid layout with a "Load more" button
+/// - Moves a selection between notes with `j`/`k` and opens it with `Enter`
 #[component]
 fn HomePage() -> impl IntoView {
-    // Create a resource to fetch all notes
-    let notes = Resource::new(|| (), |_| async move { get_notes().await });
+    // Number of notes to show; grows by a page each time "Load more" is clicked
+    let (limit, set_limit) = signal(NOTES_PAGE_SIZE);
+
+    // Tags a note must have to be shown
+    let selected_tags = RwSignal::new(Vec::<String>::new());
+
+    // Create a resource to fetch the first `limit` notes matching the selected tags
+    let notes = Resource::new(
+        move || (limit.get(), selected_tags.get()),
+        |(limit, tags)| async move { get_notes(NoteSort::Id, 0, Some(limit), tags).await },
+    );
+
+    // Position of the note selected with the keyboard among the listed notes
+    let selected = RwSignal::new(None::<usize>);
+    let listed_ids = Memo::new(move |_| {
+        notes
+            .get()
+            .and_then(|r| r.ok())
+            .map(|list| {
+                list.notes
+                    .into_iter()
+                    .map(|note| note.id)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    });
+
+    // `j`/`k` move the selection down/up, and `Enter` opens the selected note.
+    // The listener is removed when the page is left.
+    let navigate = use_navigate();
+    let listener = window_event_listener(leptos::ev::keydown, move |event| {
+        let Some(key) = shortcut_key(&event) else {
+            return;
+        };
+        let count = listed_ids.with_untracked(Vec::len);
+        let current = selected.get_untracked();
+        let next = match key.as_str() {
+            "j" if count > 0 => current.map_or(0, |i| (i + 1).min(count - 1)),
+            "k" if count > 0 => current.map_or(0, |i| i.saturating_sub(1)),
+            "Enter" => {
+                let id = current.and_then(|i| listed_ids.with_untracked(|ids| ids.get(i).cloned()));
+                if let Some(id) = id {
+                    event.prevent_default();
+                    navigate(&format!("/{}", id), Default::default());
+                }
+                return;
+            }
+            _ => return,
+        };
+        selected.set(Some(next));
+
+        // Focus the selected card's link, which scrolls it into view
+        request_animation_frame(|| {
+            if let Some(link) = document()
+                .query_selector(".notes-grid .note.selected .note-title a")
+                .ok()
+                .flatten()
+                .and_then(|link| link.dyn_into::<web_sys::HtmlElement>().ok())
+            {
+                let _ = link.focus();
+            }
+        });
+    });
+    on_cleanup(move || listener.remove());
 
     view! {
         <div class="folio">
@@ -206,7 +777,11 @@ fn HomePage() -> impl IntoView {
                 <p class="subtitle">{APP_SUBTITLE}</p>
             </header>
 
-            <Suspense
+            <SearchBox/>
+            <TagCloud selected=selected_tags/>
+
+            // Transition keeps the loaded notes visible while the next page loads
+            <Transition
                 fallback=move || view! {
                     <div class="notes-grid">
                         // Display multiple skeleton cards while loading
@@ -232,14 +807,180 @@ fn HomePage() -> impl IntoView {
                 >
                     {move || notes.get()
                         .and_then(|r| r.ok())
-                        .map(|notes| view! {
-                            <div class="notes-grid">
-                                {notes.into_iter().map(|note| view! {
-                                    <NoteCard note/>
-                                }).collect_view()}
-                            </div>
+                        .map(|list| {
+                            let has_more = list.notes.len() < list.total;
+                            view! {
+                                <div class="notes-grid">
+                                    {list.notes.into_iter().enumerate().map(|(i, note)| view! {
+                                        <NoteCard note selected=Signal::derive(move || selected.get() == Some(i))/>
+                                    }).collect_view()}
+                                </div>
+                                {has_more.then(|| view! {
+                                    <button
+                                        class="load-more"
+                                        on:click=move |_| set_limit.update(|limit| *limit += NOTES_PAGE_SIZE)
+                                    >
+                                        "Load more"
+                                    </button>
+                                })}
+                            }
                         })}
                 </Show>
+            </Transition>
+
+            <CorpusStatsPanel/>
+        </div>
+    }
+}
+
+/// Draws the reference graph as an SVG with notes laid out evenly on a circle.
+///
+/// Each reference is drawn as a line between its notes, and each note's
+/// label links to the full note view.
+fn graph_view(graph: &ReferenceGraph) -> AnyView {
+    let center = GRAPH_SIZE / 2.0;
+    let radius = center - 80.0;
+    let count = graph.nodes.len().max(1) as f64;
+
+    let positions: HashMap<&str, (f64, f64)> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| {
+            let angle = std::f64::consts::TAU * i as f64 / count;
+            (
+                node.id.as_str(),
+                (center + radius * angle.cos(), center + radius * angle.sin()),
+            )
+        })
+        .collect();
+
+    let edges = graph
+        .edges
+        .iter()
+        .filter_map(|edge| {
+            let (x1, y1) = *positions.get(edge.source.as_str())?;
+            let (x2, y2) = *positions.get(edge.target.as_str())?;
+            Some(view! { <line class="graph-edge" x1=x1 y1=y1 x2=x2 y2=y2/> })
+        })
+        .collect_view();
+
+    let nodes = graph
+        .nodes
+        .iter()
+        .map(|node| {
+            let (x, y) = positions[node.id.as_str()];
+            view! {
+                <g class="graph-node">
+                    <a href=format!("/{}", node.id)>
+                        <circle cx=x cy=y r="6"/>
+                        <text x=x y={y - 12.0} text-anchor="middle">{node.title.clone()}</text>
+                    </a>
+                </g>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <svg class="graph" viewBox=format!("0 0 {0} {0}", GRAPH_SIZE)>
+            {edges}
+            {nodes}
+        </svg>
+    }
+    .into_any()
+}
+
+/// Graph page component that visualizes references between notes.
+///
+/// Features:
+/// - Fetches the reference graph using a Resource
+/// - Draws every note and the references between them
+/// - Handles errors with user-friendly messages
+#[component]
+fn GraphPage() -> impl IntoView {
+    let graph = Resource::new(|| (), |_| async move { get_graph().await });
+
+    view! {
+        <div class="folio">
+            <header class="header">
+                <h1>"Graph"</h1>
+                <p class="subtitle">"References between notes"</p>
+            </header>
+
+            <Suspense fallback=move || view! { <div class="loading">"Loading graph..."</div> }>
+                {move || graph.get().map(|result| match result {
+                    Ok(graph) => graph_view(&graph),
+                    Err(e) => view! {
+                        <div class="error">
+                            <p>"Error loading graph: " {e.to_string()}</p>
+                        </div>
+                    }
+                    .into_any(),
+                })}
+            </Suspense>
+        </div>
+    }
+}
+
+/// Route parameters for the tag page
+#[derive(Debug, Clone, Params, PartialEq)]
+struct TagParams {
+    tag: String,
+}
+
+/// Tag page component that lists the notes carrying a tag.
+///
+/// Features:
+/// - Fetches the tag index using a Resource
+/// - Matches the tag case-insensitively, like tags are deduplicated
+/// - Links to every note with the tag
+/// - Handles unknown tags and errors with user-friendly messages
+#[component]
+fn TagPage() -> impl IntoView {
+    let params = use_params::<TagParams>();
+    let tag = move || {
+        params
+            .read()
+            .as_ref()
+            .map(|params| params.tag.clone())
+            .unwrap_or_default()
+    };
+
+    let index = Resource::new(|| (), |_| async move { get_tag_index().await });
+
+    view! {
+        <div class="folio">
+            <header class="header">
+                <h1>{tag}</h1>
+                <p class="subtitle">"Notes with this tag"</p>
+            </header>
+
+            <Suspense fallback=move || view! { <div class="loading">"Loading notes..."</div> }>
+                {move || index.get().map(|result| match result {
+                    Ok(entries) => {
+                        let tag = tag().to_lowercase();
+                        match entries.into_iter().find(|entry| entry.tag.to_lowercase() == tag) {
+                            Some(entry) => view! {
+                                <ul class="tag-notes">
+                                    {entry.note_ids.into_iter().map(|id| view! {
+                                        <li><A href=format!("/{}", id)>{id.clone()}</A></li>
+                                    }).collect_view()}
+                                </ul>
+                            }
+                            .into_any(),
+                            None => view! {
+                                <p class="tag-notes-empty">"No notes have this tag."</p>
+                            }
+                            .into_any(),
+                        }
+                    }
+                    Err(e) => view! {
+                        <div class="error">
+                            <p>"Error loading tags: " {e.to_string()}</p>
+                        </div>
+                    }
+                    .into_any(),
+                })}
             </Suspense>
         </div>
     }
@@ -257,12 +998,16 @@ struct NoteParams {
 /// - Fetches specific note data based on URL parameter
 /// - Shows skeleton loading state
 /// - Handles 404 and other errors
-/// - Applies syntax highlighting to code blocks
 /// - Displays full note content with:
-///   * Title and ID
+///   * Title, ID, and last modified date
 ///   * Tags
+///   * Table of contents
 ///   * Rendered content (including math and code)
 ///   * References to other notes
+///   * Backlinks from notes that reference this one
+///   * Related notes that share tags with this one
+///   * Attachments, with thumbnails for images
+/// - Goes back to the previous page with `Esc`
 #[component]
 fn NotePage() -> impl IntoView {
     let params = use_params::<NoteParams>();
@@ -278,41 +1023,105 @@ fn NotePage() -> impl IntoView {
         },
         move |id: Option<String>| async move {
             match id {
-                Some(id) => {
-                    let result = get_note(id).await;
-                    match result {
-                        Ok(note) => Ok(note),
-                        Err(e) => {
-                            if e.to_string().contains("404") {
-                                Err("Note not found".to_string())
-                            } else {
-                                Err(e.to_string())
-                            }
-                        }
-                    }
-                }
-                None => Err("Invalid note ID".to_string()),
+                Some(id) => get_note(id).await.map_err(NoteApiError::from),
+                // A note ID that can't be parsed can't match any note
+                None => Err(NoteApiError::NotFound),
             }
         },
     );
 
-    let content_ref = NodeRef::new();
+    // Create a resource to fetch the notes linking to this one
+    let backlinks = Resource::new(
+        move || {
+            params
+                .read()
+                .as_ref()
+                .ok()
+                .map(|params| params.note_id.clone())
+        },
+        move |id: Option<String>| async move {
+            match id {
+                Some(id) => get_backlinks(id).await.unwrap_or_default(),
+                None => Vec::new(),
+            }
+        },
+    );
 
-    // Effect that watches the note resource and runs highlighting when it changes
+    // Create a resource to fetch notes sharing tags with this one
+    let related = Resource::new(
+        move || {
+            params
+                .read()
+                .as_ref()
+                .ok()
+                .map(|params| params.note_id.clone())
+        },
+        move |id: Option<String>| async move {
+            match id {
+                Some(id) => get_related(id).await.unwrap_or_default(),
+                None => Vec::new(),
+            }
+        },
+    );
+
+    // Create a resource to fetch the files attached to this note
+    let assets = Resource::new(
+        move || {
+            params
+                .read()
+                .as_ref()
+                .ok()
+                .map(|params| params.note_id.clone())
+        },
+        move |id: Option<String>| async move {
+            match id {
+                Some(id) => get_assets(id).await.unwrap_or_default(),
+                None => Vec::new(),
+            }
+        },
+    );
+
+    // `Esc` goes back, like the browser's back button. The listener is removed
+    // when the page is left.
+    let listener = window_event_listener(leptos::ev::keydown, |event| {
+        if shortcut_key(&event).as_deref() == Some("Escape") {
+            if let Ok(history) = window().history() {
+                let _ = history.back();
+            }
+        }
+    });
+    on_cleanup(move || listener.remove());
+
+    // Add each note to the sidebar's recently viewed notes once it has loaded
+    let recent = expect_context::<RecentNotes>();
+    Effect::new(move |_| {
+        if let Some(Ok(note)) = note.get() {
+            recent.remember(&note.id, &note.title);
+        }
+    });
+
+    // Effect that watches the note resource and, once a note has loaded, scrolls
+    // to the heading named by the URL fragment, which didn't exist yet when the
+    // page was navigated to. Code is highlighted on the server already.
     Effect::new(move |_| {
         // Get the current state of our note resource
         if let Some(Ok(_)) = note.get() {
-            // Give the DOM time to update with new content before highlighting
+            // Give the DOM time to update with the new content
             request_animation_frame(move || {
                 let window = web_sys::window().unwrap();
-                if let Some(hljs) = js_sys::Reflect::get(&window, &"hljs".into())
-                    .ok()
-                    .and_then(|hljs| hljs.dyn_into::<js_sys::Object>().ok())
+                let fragment = window.location().hash().unwrap_or_default();
+                if let Some(slug) = fragment
+                    .strip_prefix('#')
+                    .filter(|slug| !slug.is_empty())
+                    .and_then(|slug| js_sys::decode_uri_component(slug).ok())
+                    .and_then(|slug| slug.as_string())
                 {
-                    let _ = js_sys::Reflect::get(&hljs, &"highlightAll".into())
-                        .ok()
-                        .and_then(|highlight_all| highlight_all.dyn_into::<js_sys::Function>().ok())
-                        .map(|f| f.call0(&hljs));
+                    if let Some(heading) = window
+                        .document()
+                        .and_then(|document| document.get_element_by_id(&slug))
+                    {
+                        heading.scroll_into_view();
+                    }
                 }
             });
         }
@@ -328,56 +1137,169 @@ fn NotePage() -> impl IntoView {
                 // Handle errors during note loading or rendering
                 <ErrorBoundary
                     fallback=|errors| view! {
-                        <div class="error-page">
-                            <h1>"Error"</h1>
-                            <p>{move || errors.get()
+                        <ErrorPage
+                            status=StatusCode::INTERNAL_SERVER_ERROR
+                            title="Error"
+                            message=errors.get_untracked()
                                 .into_iter()
                                 .map(|(_, e)| e.to_string())
                                 .collect::<Vec<_>>()
-                                .join(", ")}</p>
-                            <A href="/">"← Back to notes"</A>
-                        </div>
+                                .join(", ")
+                        />
                     }
                 >
-                    // Show note content if we have a valid note, otherwise display not found
+                    // Show note content if we have a valid note, otherwise display
+                    // not found or the error that stopped it from loading
                     <Show
-                        when=move || note.get().map(|n| n.is_ok()).unwrap_or(false)
-                        fallback=move || view! {
-                            <div class="error-page">
-                                <h1>"Note not found"</h1>
-                                <A href="/">"← Back to notes"</A>
-                            </div>
+                        when=move || matches!(note.get(), Some(Ok(_)))
+                        fallback=move || match note.get() {
+                            Some(Err(NoteApiError::NotFound)) | Some(Ok(_)) | None => view! {
+                                <NotFoundPage/>
+                            }
+                            .into_any(),
+                            Some(Err(NoteApiError::Network(_))) => view! {
+                                <ErrorPage
+                                    status=StatusCode::BAD_GATEWAY
+                                    title="Offline"
+                                    message="The notes server couldn't be reached. Check your connection and try again."
+                                />
+                            }
+                            .into_any(),
+                            Some(Err(error)) => view! {
+                                <ErrorPage
+                                    status=StatusCode::INTERNAL_SERVER_ERROR
+                                    title="Error"
+                                    message=format!("Error loading note: {}", error)
+                                />
+                            }
+                            .into_any(),
                         }
                     >
-                        <div class="note-full">
-                            {move || note.get().and_then(|n| n.ok()).map(|note| view! {
-                                // Note header with ID and title
-                                <header class="note-header">
-                                    <span class="note-id">{note.id}</span>
-                                    <h1 class="note-title">{note.title}</h1>
-                                </header>
-
-                                // Note tags
-                                <div class="tags">
-                                    {note.tags.into_iter().map(|tag| {
-                                        view! { <span class="tag">{tag}</span> }
-                                    }).collect_view()}
-                                </div>
+                        // The layout class lets notes opt into wide or slide styling
+                        <div class=move || {
+                            let layout = note
+                                .get()
+                                .and_then(|n| n.ok())
+                                .map(|note| note.layout)
+                                .unwrap_or_default();
+                            format!("note-full {}", layout.css_class())
+                        }>
+                            {move || note.get().and_then(|n| n.ok()).map(|note| {
+                                // Summary for link previews; a note without text is described by its title
+                                let description = if note.excerpt.is_empty() {
+                                    note.title.clone()
+                                } else {
+                                    truncate_words(&note.excerpt, DESCRIPTION_LENGTH)
+                                };
+                                // Asset URLs use the canonical ID, which an alias in the URL may not be
+                                let note_id = note.id.clone();
+                                let json_ld = note.json_ld();
 
-                                // Main note content - uses node_ref for syntax highlighting
-                                <div class="note-content" node_ref=content_ref inner_html=note.content/>
-
-                                // References to other notes
-                                <div class="references">
-                                    <h3>"References"</h3>
-                                    {note.references.into_iter().map(|ref_id| {
-                                        view! {
-                                            <A href=format!("/{}", ref_id)>
-                                                <span class="reference">{"→ "}{ref_id}</span>
-                                            </A>
-                                        }
-                                    }).collect_view()}
-                                </div>
+                                view! {
+                                    // Page title and link preview metadata
+                                    <Title text=note.title.clone()/>
+                                    <Meta name="description" content=description.clone()/>
+                                    <Meta property="og:title" content=note.title.clone()/>
+                                    <Meta property="og:description" content=description/>
+                                    <Meta property="og:type" content="article"/>
+                                    // Structured data for search engines
+                                    {json_ld.map(|json_ld| view! {
+                                        <script type="application/ld+json" inner_html=json_ld></script>
+                                    })}
+
+                                    // Note header with ID and title
+                                    <header class="note-header">
+                                        <span class="note-id">{note.id}</span>
+                                        <h1 class="note-title">{note.title}</h1>
+                                        {note.modified.map(|modified| view! {
+                                            <time class="note-date" datetime=modified.to_rfc3339()>
+                                                "Updated " {modified.format("%Y-%m-%d").to_string()}
+                                            </time>
+                                        })}
+                                        {(note.reading_time_minutes > 0).then(|| view! {
+                                            <span class="note-reading-time">
+                                                {note.reading_time_minutes} " min read"
+                                            </span>
+                                        })}
+                                    </header>
+
+                                    // Note tags
+                                    <div class="tags">
+                                        {note.tags.into_iter().map(|tag| {
+                                            view! { <span class="tag">{tag}</span> }
+                                        }).collect_view()}
+                                    </div>
+
+                                    // Table of contents generated from the note's headings
+                                    <TableOfContents entries=note.toc/>
+
+                                    // Main note content, with code highlighted on the server
+                                    <div class="note-content" inner_html=note.content/>
+
+                                    // References to other notes
+                                    <div class="references">
+                                        <h3>"References"</h3>
+                                        {note.references.into_iter().map(|ref_id| {
+                                            view! {
+                                                <A href=format!("/{}", ref_id)>
+                                                    <span class="reference">{"→ "}{ref_id}</span>
+                                                </A>
+                                            }
+                                        }).collect_view()}
+                                    </div>
+
+                                    // Notes that reference this one
+                                    <div class="references backlinks">
+                                        <h3>"Referenced by"</h3>
+                                        {move || backlinks.get().map(|backlinks| {
+                                            backlinks.into_iter().map(|meta| {
+                                                view! {
+                                                    <A href=format!("/{}", meta.id)>
+                                                        <span class="reference">{"← "}{meta.title}</span>
+                                                    </A>
+                                                }
+                                            }).collect_view()
+                                        })}
+                                    </div>
+
+                                    // Notes sharing tags with this one, hidden if there are none
+                                    {move || related.get().filter(|related| !related.is_empty()).map(|related| view! {
+                                        <div class="references related">
+                                            <h3>"Related"</h3>
+                                            {related.into_iter().map(|meta| {
+                                                view! {
+                                                    <A href=format!("/{}", meta.id)>
+                                                        <span class="reference">{"~ "}{meta.title}</span>
+                                                    </A>
+                                                }
+                                            }).collect_view()}
+                                        </div>
+                                    })}
+
+                                    // Files attached to this note, hidden if there are none
+                                    {move || assets.get().filter(|assets| !assets.is_empty()).map(|assets| view! {
+                                        <div class="references attachments">
+                                            <h3>"Attachments"</h3>
+                                            // The app shows the default collection
+                                            {assets.into_iter().map(|asset| {
+                                                let url = asset.url(DEFAULT_API_PREFIX, &note_id);
+                                                view! {
+                                                    <a class="attachment" href=url download=asset.name.clone()>
+                                                        {asset.is_image().then(|| view! {
+                                                            <img
+                                                                class="attachment-thumbnail"
+                                                                src=asset.thumbnail_url(DEFAULT_API_PREFIX, &note_id, THUMBNAIL_WIDTH)
+                                                                alt=asset.name.clone()
+                                                                loading="lazy"
+                                                            />
+                                                        })}
+                                                        <span class="reference">{asset.name}</span>
+                                                    </a>
+                                                }
+                                            }).collect_view()}
+                                        </div>
+                                    })}
+                                }
                             })}
                         </div>
                     </Show>
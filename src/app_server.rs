@@ -4,42 +4,246 @@
 //! the client-side UI and the backend API. These functions are automatically
 //! transformed by Leptos into client-side functions that make API requests.
 
-use crate::note::Note;
+use crate::note::{
+    ApiErrorBody, Asset, CorpusStats, Note, NoteList, NoteMetadata, NoteSort, ReferenceGraph,
+    TagIndexEntry, TagNode,
+};
 use leptos::prelude::ServerFnError;
 use leptos::server;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+#[cfg(feature = "ssr")]
+use {leptos::prelude::get_configuration, std::net::SocketAddr, std::sync::OnceLock};
 
-/// Fetches all available notes from the API.
+/// Errors returned by the note API server functions.
+///
+/// Sent to the client as [`ServerFnError::WrappedServerError`], so the UI can
+/// match on the kind of failure rather than inspecting error messages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoteApiError {
+    /// The requested note doesn't exist
+    NotFound,
+    /// The API couldn't be reached
+    Network(String),
+    /// The API response couldn't be decoded
+    Decode(String),
+    /// The API returned an error status, or the request couldn't be built
+    Server(String),
+}
+
+impl fmt::Display for NoteApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoteApiError::NotFound => write!(f, "Note not found"),
+            NoteApiError::Network(message) => write!(f, "Network error: {}", message),
+            NoteApiError::Decode(message) => write!(f, "Invalid response: {}", message),
+            NoteApiError::Server(message) => write!(f, "Server error: {}", message),
+        }
+    }
+}
+
+/// Parses the [`Display`](fmt::Display) form, which is how server functions
+/// send custom errors from the server to the client.
+impl FromStr for NoteApiError {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(if s == "Note not found" {
+            NoteApiError::NotFound
+        } else if let Some(message) = s.strip_prefix("Network error: ") {
+            NoteApiError::Network(message.to_string())
+        } else if let Some(message) = s.strip_prefix("Invalid response: ") {
+            NoteApiError::Decode(message.to_string())
+        } else {
+            let message = s.strip_prefix("Server error: ").unwrap_or(s);
+            NoteApiError::Server(message.to_string())
+        })
+    }
+}
+
+/// Flattens a server function error, so transport failures between the
+/// client and server are reported like failures reaching the API.
+impl From<ServerFnError<NoteApiError>> for NoteApiError {
+    fn from(error: ServerFnError<NoteApiError>) -> Self {
+        match error {
+            ServerFnError::WrappedServerError(error) => error,
+            ServerFnError::Request(message) => NoteApiError::Network(message),
+            ServerFnError::Deserialization(message) => NoteApiError::Decode(message),
+            other => NoteApiError::Server(other.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl From<reqwest::Error> for NoteApiError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_decode() {
+            NoteApiError::Decode(error.to_string())
+        } else if error.is_status() || error.is_builder() {
+            NoteApiError::Server(error.to_string())
+        } else {
+            NoteApiError::Network(error.to_string())
+        }
+    }
+}
+
+/// Base URL of the REST API that server functions call during SSR
+#[cfg(feature = "ssr")]
+static API_BASE: OnceLock<String> = OnceLock::new();
+
+/// Sets the base URL of the REST API (e.g. `http://127.0.0.1:3000/api`).
+///
+/// Server functions always run on the server, so this only affects the
+/// server-to-API requests; in the browser, server function calls go to the
+/// same origin the page was loaded from. Should be called once at startup;
+/// later calls are ignored.
+///
+/// # Arguments
+/// * `base` - The API base URL, with or without a trailing `/`
+#[cfg(feature = "ssr")]
+pub fn set_api_base(base: &str) {
+    let _ = API_BASE.set(base.trim_end_matches('/').to_string());
+}
+
+/// Returns the REST API base URL of a server listening on `addr`.
+///
+/// A wildcard bind address (e.g. `0.0.0.0`) isn't connectable, so it's
+/// replaced with loopback on the same port.
+///
+/// # Arguments
+/// * `addr` - The address the server binds to
+#[cfg(feature = "ssr")]
+pub fn api_base_for(addr: SocketAddr) -> String {
+    let addr = if addr.ip().is_unspecified() {
+        SocketAddr::new(std::net::Ipv4Addr::LOCALHOST.into(), addr.port())
+    } else {
+        addr
+    };
+    format!("http://{}/api", addr)
+}
+
+/// Returns the API base URL set with [`set_api_base`], or else the one derived
+/// from the Leptos site address, which is also the address the server binds to.
+#[cfg(feature = "ssr")]
+fn api_base() -> Result<&'static str, NoteApiError> {
+    if let Some(base) = API_BASE.get() {
+        return Ok(base);
+    }
+    let conf = get_configuration(None).map_err(|e| NoteApiError::Server(e.to_string()))?;
+    Ok(API_BASE.get_or_init(|| api_base_for(conf.leptos_options.site_addr)))
+}
+
+/// ID of the note rendered in place of pages that don't exist, unless
+/// changed with [`set_not_found_note`]
+pub const DEFAULT_NOT_FOUND_NOTE: &str = "404";
+
+/// ID of the note shown on not-found pages; empty when disabled
+#[cfg(feature = "ssr")]
+static NOT_FOUND_NOTE: OnceLock<String> = OnceLock::new();
+
+/// Sets the ID of the note whose content is shown on not-found pages.
+///
+/// Should be called once at startup; later calls are ignored.
+///
+/// # Arguments
+/// * `id` - The note ID, or an empty string to always show the built-in message
+#[cfg(feature = "ssr")]
+pub fn set_not_found_note(id: &str) {
+    let _ = NOT_FOUND_NOTE.set(id.to_string());
+}
+
+/// Builds the URL of an API endpoint relative to the configured base URL.
+///
+/// # Arguments
+/// * `path` - Endpoint path below the base URL (e.g. `notes`)
+#[cfg(feature = "ssr")]
+fn api_url(path: &str) -> Result<reqwest::Url, NoteApiError> {
+    reqwest::Url::parse(&format!("{}/{}", api_base()?, path))
+        .map_err(|e| NoteApiError::Server(e.to_string()))
+}
+
+/// Builds the API URL for a note, percent-encoding the ID as a single path segment.
+///
+/// Note IDs may contain `/` (e.g. `projects/foo`), which is encoded as `%2F`
+/// so the ID is matched by the `:id` route parameter.
+///
+/// # Arguments
+/// * `id` - The unique identifier of the note
+/// * `rest` - Additional path segments to append after the ID
+#[cfg(feature = "ssr")]
+fn note_api_url(id: &str, rest: &[&str]) -> Result<reqwest::Url, NoteApiError> {
+    let mut url = api_url("notes")?;
+    url.path_segments_mut()
+        .map_err(|_| NoteApiError::Server("Invalid API URL".to_string()))?
+        .push(id)
+        .extend(rest);
+    Ok(url)
+}
+
+/// Fetches a page of notes from the API.
 ///
 /// This function is marked with the #[server] attribute, which means Leptos will:
 /// 1. Run this implementation on the server during SSR
 /// 2. Generate a client-side version that makes the API request
 ///
+/// # Arguments
+/// * `sort` - Order of the notes
+/// * `offset` - Number of notes to skip
+/// * `limit` - Maximum number of notes to return, or None for all remaining notes
+/// * `tags` - Only return notes that have all of these tags
+///
 /// # Returns
-/// - `Ok(Vec<Note>)` - A list of all notes if successful
-/// - `Err(ServerFnError)` - If any step of the request fails:
-///   - Network errors during the request
-///   - Non-200 status codes from the API
-///   - JSON deserialization errors
+/// - `Ok(NoteList)` - The requested page of notes and the total note count
+/// - `Err(ServerFnError)` - If any step of the request fails, wrapping:
+///   - [`NoteApiError::Network`] for network errors during the request
+///   - [`NoteApiError::Server`] for non-200 status codes from the API
+///   - [`NoteApiError::Decode`] for JSON deserialization errors
 #[server(GetNotes)]
-pub async fn get_notes() -> Result<Vec<Note>, ServerFnError<String>> {
+pub async fn get_notes(
+    sort: NoteSort,
+    offset: usize,
+    limit: Option<usize>,
+    tags: Vec<String>,
+) -> Result<NoteList, ServerFnError<NoteApiError>> {
     // Create a reusable HTTP client
     let client = reqwest::Client::new();
 
+    // Build the paging query; each call appends to the query string
+    let mut request = client
+        .get(api_url("notes")?)
+        .query(&[("sort", sort)])
+        .query(&[("offset", offset)]);
+    if let Some(limit) = limit {
+        request = request.query(&[("limit", limit)]);
+    }
+    for tag in &tags {
+        request = request.query(&[("tag", tag)]);
+    }
+
     // Make the request to the notes API endpoint
-    let response = client
-        .get("http://127.0.0.1:3000/api/notes")
+    let response = request
         .send()
         .await
-        .map_err(|e| ServerFnError::<String>::ServerError(e.to_string()))?
+        .map_err(NoteApiError::from)?
         // Ensure we got a successful status code
         .error_for_status()
-        .map_err(|e| ServerFnError::<String>::ServerError(e.to_string()))?;
+        .map_err(NoteApiError::from)?;
 
-    // Parse the JSON response into our Note type
-    response
-        .json()
-        .await
-        .map_err(|e| ServerFnError::<String>::ServerError(e.to_string()))
+    // The total count is sent in a header so the body stays a plain list
+    let total = response
+        .headers()
+        .get("x-total-count")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+
+    // The list endpoint returns note metadata unless whole notes are requested
+    let notes: Vec<NoteMetadata> = response.json().await.map_err(NoteApiError::from)?;
+
+    Ok(NoteList {
+        total: total.unwrap_or(offset + notes.len()),
+        notes,
+    })
 }
 
 /// Fetches a specific note by ID from the API.
@@ -53,37 +257,294 @@ pub async fn get_notes() -> Result<Vec<Note>, ServerFnError<String>> {
 ///
 /// # Returns
 /// - `Ok(Note)` - The requested note if found
-/// - `Err(ServerFnError)` - If any step of the request fails:
-///   - Network errors during the request
-///   - Non-200 status codes from the API (including 404)
-///   - JSON deserialization errors
+/// - `Err(ServerFnError)` - If any step of the request fails, wrapping:
+///   - [`NoteApiError::NotFound`] if the API reports that no note has this ID
+///   - [`NoteApiError::Network`] for network errors during the request
+///   - [`NoteApiError::Server`] for other non-200 status codes from the API
+///   - [`NoteApiError::Decode`] for JSON deserialization errors
 #[server(GetNote)]
-pub async fn get_note(id: String) -> Result<Note, ServerFnError<String>> {
+pub async fn get_note(id: String) -> Result<Note, ServerFnError<NoteApiError>> {
     let client = reqwest::Client::new();
 
     // Make the request to the specific note's API endpoint
     let response = client
-        .get(format!("http://127.0.0.1:3000/api/notes/{}", id))
+        .get(note_api_url(&id, &[])?)
         .send()
         .await
-        .map_err(|e| ServerFnError::<String>::ServerError(e.to_string()))?;
+        .map_err(NoteApiError::from)?;
 
-    // Check the status code before trying to parse the response
+    // Check the status code before trying to parse the response. Only the
+    // structured not-found body means the note is missing; any other 404
+    // (e.g. a misrouted request) is reported as a server error.
     if response.status() == reqwest::StatusCode::NOT_FOUND {
-        return Err(ServerFnError::ServerError("Note not found".to_string()));
+        let error = match response.json::<ApiErrorBody>().await {
+            Ok(ApiErrorBody::NoteNotFound { .. }) => NoteApiError::NotFound,
+            Err(_) => NoteApiError::Server(reqwest::StatusCode::NOT_FOUND.to_string()),
+        };
+        return Err(error.into());
     }
 
     // Handle other error status codes
-    if !response.status().is_success() {
-        return Err(ServerFnError::ServerError(format!(
-            "API error: {}",
-            response.status()
-        )));
-    }
+    let response = response.error_for_status().map_err(NoteApiError::from)?;
 
     // Parse the JSON response into our Note type
-    response
-        .json()
+    Ok(response.json().await.map_err(NoteApiError::from)?)
+}
+
+/// Fetches the note configured to be shown on not-found pages.
+///
+/// # Returns
+/// - `Ok(Some(Note))` - The note set with [`set_not_found_note`], or else the
+///   one with the ID [`DEFAULT_NOT_FOUND_NOTE`]
+/// - `Ok(None)` - If no such note exists, or the page is disabled
+/// - `Err(ServerFnError)` - If the request fails or the response can't be decoded
+#[server(GetNotFoundNote)]
+pub async fn get_not_found_note() -> Result<Option<Note>, ServerFnError<NoteApiError>> {
+    let id = NOT_FOUND_NOTE
+        .get()
+        .map_or(DEFAULT_NOT_FOUND_NOTE, String::as_str);
+    if id.is_empty() {
+        return Ok(None);
+    }
+    match get_note(id.to_string()).await {
+        Ok(note) => Ok(Some(note)),
+        Err(ServerFnError::WrappedServerError(NoteApiError::NotFound)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Picks a random note.
+///
+/// # Returns
+/// - `Ok(NoteMetadata)` - Metadata of the chosen note
+/// - `Err(ServerFnError)` - If there are no notes, the request fails, or the
+///   response can't be decoded
+#[server(RandomNote)]
+pub async fn random_note() -> Result<NoteMetadata, ServerFnError<NoteApiError>> {
+    let client = reqwest::Client::new();
+
+    // Make the request to the random note endpoint, asking for JSON rather
+    // than the redirect browsers get
+    let response = client
+        .get(api_url("notes/random")?)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .send()
+        .await
+        .map_err(NoteApiError::from)?
+        .error_for_status()
+        .map_err(NoteApiError::from)?;
+
+    // Parse the JSON response into note metadata
+    Ok(response.json().await.map_err(NoteApiError::from)?)
+}
+
+/// Fetches metadata for all notes that reference the given note.
+///
+/// # Arguments
+/// * `id` - The unique identifier of the referenced note
+///
+/// # Returns
+/// - `Ok(Vec<NoteMetadata>)` - Notes linking to `id` (empty if none)
+/// - `Err(ServerFnError)` - If the request fails or the response can't be decoded
+#[server(GetBacklinks)]
+pub async fn get_backlinks(id: String) -> Result<Vec<NoteMetadata>, ServerFnError<NoteApiError>> {
+    let client = reqwest::Client::new();
+
+    // Make the request to the note's backlinks endpoint
+    let response = client
+        .get(note_api_url(&id, &["backlinks"])?)
+        .send()
+        .await
+        .map_err(NoteApiError::from)?
+        .error_for_status()
+        .map_err(NoteApiError::from)?;
+
+    // Parse the JSON response into note metadata
+    Ok(response.json().await.map_err(NoteApiError::from)?)
+}
+
+/// Fetches the files attached to a note.
+///
+/// # Arguments
+/// * `id` - The unique identifier of the note
+///
+/// # Returns
+/// - `Ok(Vec<Asset>)` - The note's assets (empty if it has none)
+/// - `Err(ServerFnError)` - If the request fails or the response can't be decoded
+#[server(GetAssets)]
+pub async fn get_assets(id: String) -> Result<Vec<Asset>, ServerFnError<NoteApiError>> {
+    let client = reqwest::Client::new();
+
+    // Make the request to the note's assets endpoint
+    let response = client
+        .get(note_api_url(&id, &["assets"])?)
+        .send()
+        .await
+        .map_err(NoteApiError::from)?
+        .error_for_status()
+        .map_err(NoteApiError::from)?;
+
+    // Parse the JSON response into asset descriptions
+    Ok(response.json().await.map_err(NoteApiError::from)?)
+}
+
+/// Fetches notes that share tags with the given note.
+///
+/// # Arguments
+/// * `id` - The unique identifier of the note
+///
+/// # Returns
+/// - `Ok(Vec<NoteMetadata>)` - Related notes, most shared tags first (empty if none)
+/// - `Err(ServerFnError)` - If the request fails or the response can't be decoded
+#[server(GetRelated)]
+pub async fn get_related(id: String) -> Result<Vec<NoteMetadata>, ServerFnError<NoteApiError>> {
+    let client = reqwest::Client::new();
+
+    // Make the request to the note's related notes endpoint
+    let response = client
+        .get(note_api_url(&id, &["related"])?)
+        .send()
+        .await
+        .map_err(NoteApiError::from)?
+        .error_for_status()
+        .map_err(NoteApiError::from)?;
+
+    // Parse the JSON response into note metadata
+    Ok(response.json().await.map_err(NoteApiError::from)?)
+}
+
+/// Fetches every tag along with the number of notes that use it.
+///
+/// # Returns
+/// - `Ok(Vec<(String, usize)>)` - Tags and note counts, ordered by tag
+/// - `Err(ServerFnError)` - If the request fails or the response can't be decoded
+#[server(GetTags)]
+pub async fn get_tags() -> Result<Vec<(String, usize)>, ServerFnError<NoteApiError>> {
+    let client = reqwest::Client::new();
+
+    // Make the request to the tags endpoint
+    let response = client
+        .get(api_url("tags")?)
+        .send()
+        .await
+        .map_err(NoteApiError::from)?
+        .error_for_status()
+        .map_err(NoteApiError::from)?;
+
+    // Parse the JSON response into tag counts
+    Ok(response.json().await.map_err(NoteApiError::from)?)
+}
+
+/// Fetches the tag hierarchy, with tags nested on `/`.
+///
+/// # Returns
+/// - `Ok(TagNode)` - The root of the hierarchy with note counts at each level
+/// - `Err(ServerFnError)` - If the request fails or the response can't be decoded
+#[server(GetTagTree)]
+pub async fn get_tag_tree() -> Result<TagNode, ServerFnError<NoteApiError>> {
+    let client = reqwest::Client::new();
+
+    // Make the request to the tag tree endpoint
+    let response = client
+        .get(api_url("tags/tree")?)
+        .send()
+        .await
+        .map_err(NoteApiError::from)?
+        .error_for_status()
+        .map_err(NoteApiError::from)?;
+
+    // Parse the JSON response into the tag hierarchy
+    Ok(response.json().await.map_err(NoteApiError::from)?)
+}
+
+/// Fetches every tag along with the IDs of the notes that carry it.
+///
+/// # Returns
+/// - `Ok(Vec<TagIndexEntry>)` - Tags and their note IDs, ordered by tag
+/// - `Err(ServerFnError)` - If the request fails or the response can't be decoded
+#[server(GetTagIndex)]
+pub async fn get_tag_index() -> Result<Vec<TagIndexEntry>, ServerFnError<NoteApiError>> {
+    let client = reqwest::Client::new();
+
+    // Make the request to the tag index endpoint
+    let response = client
+        .get(api_url("tags/index")?)
+        .send()
         .await
-        .map_err(|e| ServerFnError::<String>::ServerError(e.to_string()))
+        .map_err(NoteApiError::from)?
+        .error_for_status()
+        .map_err(NoteApiError::from)?;
+
+    // Parse the JSON response into the tag index
+    Ok(response.json().await.map_err(NoteApiError::from)?)
+}
+
+/// Searches notes by title, tags, and body text.
+///
+/// # Arguments
+/// * `query` - Text to search for
+///
+/// # Returns
+/// - `Ok(Vec<NoteMetadata>)` - Matching notes, title matches first
+/// - `Err(ServerFnError)` - If the request fails or the response can't be decoded
+#[server(SearchNotes)]
+pub async fn search_notes(query: String) -> Result<Vec<NoteMetadata>, ServerFnError<NoteApiError>> {
+    let client = reqwest::Client::new();
+
+    // Make the request to the search endpoint, letting reqwest encode the query
+    let response = client
+        .get(api_url("search")?)
+        .query(&[("q", query)])
+        .send()
+        .await
+        .map_err(NoteApiError::from)?
+        .error_for_status()
+        .map_err(NoteApiError::from)?;
+
+    // Parse the JSON response into note metadata
+    Ok(response.json().await.map_err(NoteApiError::from)?)
+}
+
+/// Fetches aggregate numbers describing the published notes.
+///
+/// # Returns
+/// - `Ok(CorpusStats)` - Note, tag, reference, and orphan counts and the average word count
+/// - `Err(ServerFnError)` - If the request fails or the response can't be decoded
+#[server(GetCorpusStats)]
+pub async fn get_corpus_stats() -> Result<CorpusStats, ServerFnError<NoteApiError>> {
+    let client = reqwest::Client::new();
+
+    // Make the request to the stats endpoint
+    let response = client
+        .get(api_url("stats")?)
+        .send()
+        .await
+        .map_err(NoteApiError::from)?
+        .error_for_status()
+        .map_err(NoteApiError::from)?;
+
+    // Parse the JSON response into the statistics
+    Ok(response.json().await.map_err(NoteApiError::from)?)
+}
+
+/// Fetches the reference graph of all notes.
+///
+/// # Returns
+/// - `Ok(ReferenceGraph)` - Every note as a node and every resolvable reference as an edge
+/// - `Err(ServerFnError)` - If the request fails or the response can't be decoded
+#[server(GetGraph)]
+pub async fn get_graph() -> Result<ReferenceGraph, ServerFnError<NoteApiError>> {
+    let client = reqwest::Client::new();
+
+    // Make the request to the graph endpoint
+    let response = client
+        .get(api_url("graph")?)
+        .send()
+        .await
+        .map_err(NoteApiError::from)?
+        .error_for_status()
+        .map_err(NoteApiError::from)?;
+
+    // Parse the JSON response into the graph
+    Ok(response.json().await.map_err(NoteApiError::from)?)
 }
@@ -3,49 +3,375 @@
 
 // Import server-side dependencies when the "ssr" feature is enabled
 #[cfg(feature = "ssr")]
-use axum::extract::{Path, State};
+use axum::body::Body;
+#[cfg(feature = "ssr")]
+use axum::extract::{Path, Query, State};
+#[cfg(feature = "ssr")]
+use axum::response::{Html, IntoResponse, Response};
 #[cfg(feature = "ssr")]
 use axum::Json;
 #[cfg(feature = "ssr")]
+use http::header;
+#[cfg(feature = "ssr")]
 use http::StatusCode;
 #[cfg(feature = "ssr")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ssr")]
+use std::collections::HashMap;
+#[cfg(feature = "ssr")]
+use std::io::{Seek, SeekFrom};
+#[cfg(feature = "ssr")]
 use std::sync::Arc;
 
 // Import our Note-related types for the server
 #[cfg(feature = "ssr")]
-use miniref::note::{Note, NoteStore};
+use miniref::note::{
+    encode_path_segment, AliasConflict, ApiErrorBody, Asset, BrokenRef, CacheStats,
+    CollectionStore, CorpusStats, CreateNoteRequest, DuplicateId, MathDelimiters, Note,
+    NoteMetadata, NoteSort, NoteStore, NoteVersion, ParseFailure, ReferenceGraph,
+    RenameNoteRequest, TagIndexEntry, TagNode, TemplateNoteRequest, UnlinkedMention,
+    UpdateNoteRequest, DEFAULT_API_PREFIX, DEFAULT_CACHE_CAPACITY, DEFAULT_EMBED_DEPTH,
+    DEFAULT_THEME,
+};
 
 /// Server entry point - sets up and runs the web server with both API and SSR routes
 #[cfg(feature = "ssr")]
 #[tokio::main]
 async fn main() {
     use axum::{routing::get, Router};
-    use leptos::logging::log;
     use leptos::prelude::*;
     use leptos_axum::{generate_route_list, LeptosRoutes};
     use miniref::app::*;
+    use miniref::app_server::{
+        api_base_for, set_api_base, set_not_found_note, DEFAULT_NOT_FOUND_NOTE,
+    };
+    use tower::Layer;
+    use tower_http::compression::{
+        predicate::{DefaultPredicate, NotForContentType, Predicate},
+        CompressionLayer,
+    };
+    use tower_http::cors::{AllowOrigin, CorsLayer};
+    use tower_http::services::ServeDir;
+    use tower_http::set_header::SetResponseHeaderLayer;
+    use tower_http::trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer};
+    use tracing::{error, info, warn, Level};
+    use tracing_subscriber::EnvFilter;
+
+    // Log to stdout, filtered by RUST_LOG (e.g. `miniref=debug`), info and up by default
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
+
+    // Notes directory: `--notes-dir <path>`, then MINIREF_NOTES_DIR, then ./notes
+    let notes_dir = notes_dir_arg(std::env::args().skip(1))
+        .or_else(|| std::env::var("MINIREF_NOTES_DIR").ok())
+        .unwrap_or_else(|| DEFAULT_NOTES_DIR.to_string());
+
+    // Syntax highlighting theme of the code highlighting stylesheet
+    let theme = std::env::var("MINIREF_THEME").unwrap_or_else(|_| DEFAULT_THEME.to_string());
+
+    // Optional directory of extra `.sublime-syntax` and `.tmTheme` files
+    let syntax_dir = std::env::var("MINIREF_SYNTAX_DIR").ok();
+
+    // LaTeX macros shared by all notes: MINIREF_KATEX_MACROS, else macros.yaml in the notes directory
+    let macros_file = std::env::var("MINIREF_KATEX_MACROS").ok().or_else(|| {
+        let default = std::path::Path::new(&notes_dir).join(DEFAULT_MACROS_FILE);
+        default
+            .is_file()
+            .then(|| default.to_string_lossy().into_owned())
+    });
+
+    // Maximum number of processed notes to keep in memory
+    let cache_capacity = std::env::var("MINIREF_CACHE_CAPACITY")
+        .ok()
+        .and_then(|capacity| capacity.parse().ok())
+        .unwrap_or(DEFAULT_CACHE_CAPACITY);
 
-    // Load application configuration
-    let conf = get_configuration(None).unwrap();
+    // How deeply notes embedded with `![[id]]` may embed other notes
+    let embed_depth = std::env::var("MINIREF_EMBED_DEPTH")
+        .ok()
+        .and_then(|depth| depth.parse().ok())
+        .unwrap_or(DEFAULT_EMBED_DEPTH);
+
+    // Whether tags keep their original casing instead of being lowercased
+    let preserve_tag_case = std::env::var("MINIREF_PRESERVE_TAG_CASE")
+        .map(|value| matches!(value.as_str(), "1" | "true"))
+        .unwrap_or(false);
+
+    // Whether notes carry their markdown source alongside the rendered HTML
+    let include_source = std::env::var("MINIREF_INCLUDE_SOURCE")
+        .map(|value| matches!(value.as_str(), "1" | "true"))
+        .unwrap_or(false);
+
+    // Whether updating a note keeps its previous version in `{id}.history`
+    let keep_history = std::env::var("MINIREF_KEEP_HISTORY")
+        .map(|value| matches!(value.as_str(), "1" | "true"))
+        .unwrap_or(false);
+
+    // Whether prose gets typographic quotes, dashes, and ellipses
+    let smart_typography = std::env::var("MINIREF_SMART_TYPOGRAPHY")
+        .map(|value| matches!(value.as_str(), "1" | "true"))
+        .unwrap_or(false);
+
+    // Math delimiters: a comma-separated list of `dollars` and `brackets`
+    let math_delimiters = std::env::var("MINIREF_MATH_DELIMITERS")
+        .map(|value| parse_math_delimiters(&value))
+        .unwrap_or_default();
+
+    // Opens a notes directory as a store with the configured settings
+    let open_store = |dir: &str| {
+        NoteStore::new(dir)
+            .and_then(|store| match &syntax_dir {
+                Some(dir) => store.with_syntax_dir(dir),
+                None => Ok(store),
+            })
+            .and_then(|store| store.with_theme(&theme))
+            .and_then(|store| match &macros_file {
+                Some(path) => store.with_katex_macros_file(path),
+                None => Ok(store),
+            })
+            .map(|store| store.with_cache_capacity(Some(cache_capacity)))
+            .map(|store| store.with_preserve_tag_case(preserve_tag_case))
+            .map(|store| store.with_note_source(include_source))
+            .map(|store| store.with_history(keep_history))
+            .map(|store| store.with_smart_typography(smart_typography))
+            .map(|store| store.with_math_delimiters(math_delimiters))
+            .map(|store| store.with_embed_depth(embed_depth))
+    };
+
+    // Initialize the note store which provides access to our notes directory
+    let note_store = match open_store(&notes_dir) {
+        Ok(store) => Arc::new(store),
+        Err(e) => {
+            error!("Failed to set up the note store for {}: {}", notes_dir, e);
+            std::process::exit(1);
+        }
+    };
+
+    // `miniref check` validates the notes and exits instead of serving them
+    if std::env::args().nth(1).as_deref() == Some("check") {
+        match run_check(&note_store) {
+            Ok(0) => std::process::exit(0),
+            Ok(_) => std::process::exit(1),
+            Err(e) => {
+                error!("Failed to check the notes in {}: {}", notes_dir, e);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    // Load application configuration. The site address (`site-addr` in
+    // Cargo.toml, or LEPTOS_SITE_ADDR) is the one place the port is set: the
+    // server binds to it and server functions reach the API through it.
+    let conf = match get_configuration(None) {
+        Ok(conf) => conf,
+        Err(e) => {
+            error!(
+                "Invalid Leptos configuration (check LEPTOS_SITE_ADDR): {}",
+                e
+            );
+            std::process::exit(1);
+        }
+    };
     let addr = conf.leptos_options.site_addr;
     let leptos_options = conf.leptos_options;
 
-    // Initialize the note store which provides access to our notes directory
-    let note_store = Arc::new(NoteStore::new("./notes").expect("Failed to init store"));
+    // Base URL server functions use to reach the REST API during SSR
+    let api_base = std::env::var("MINIREF_API_BASE").unwrap_or_else(|_| api_base_for(addr));
+    set_api_base(&api_base);
+
+    // Content-Security-Policy sent with every response; an empty value sends none
+    let content_security_policy = std::env::var("MINIREF_CSP")
+        .unwrap_or_else(|_| DEFAULT_CONTENT_SECURITY_POLICY.to_string());
+    let content_security_policy = match content_security_policy.trim() {
+        "" => None,
+        policy => match http::HeaderValue::from_str(policy) {
+            Ok(policy) => Some(policy),
+            Err(e) => {
+                error!("Invalid MINIREF_CSP: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    // Origins other sites may read the API from: a comma-separated list, or `*`
+    // for any. Unset or empty keeps the API same-origin only.
+    let cors_origins = std::env::var("MINIREF_CORS_ORIGINS").unwrap_or_default();
+    let cors_origins = match cors_origins.trim() {
+        "" => None,
+        "*" => Some(AllowOrigin::any()),
+        origins => match origins
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .map(http::HeaderValue::from_str)
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(origins) => Some(AllowOrigin::list(origins)),
+            Err(e) => {
+                error!("Invalid MINIREF_CORS_ORIGINS: {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    // Further collections served under /api/{name}: a comma-separated list of
+    // `name=directory`. Each has its own notes and IDs.
+    let mut collections = CollectionStore::new();
+    for entry in std::env::var("MINIREF_COLLECTIONS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let Some((name, dir)) = entry.split_once('=') else {
+            error!(
+                "Invalid MINIREF_COLLECTIONS entry {:?}, expected name=directory",
+                entry
+            );
+            std::process::exit(1);
+        };
+        let (name, dir) = (name.trim(), dir.trim());
+        if RESERVED_COLLECTION_NAMES.contains(&name) {
+            error!(
+                "Invalid MINIREF_COLLECTIONS: {:?} is taken by an API route",
+                name
+            );
+            std::process::exit(1);
+        }
+        let store = match open_store(dir) {
+            Ok(store) => store.with_api_prefix(&format!("{}/{}", DEFAULT_API_PREFIX, name)),
+            Err(e) => {
+                error!("Failed to set up the note store for {}: {}", dir, e);
+                std::process::exit(1);
+            }
+        };
+        if let Err(e) = collections.insert(name, Arc::new(store)) {
+            error!("Invalid MINIREF_COLLECTIONS: {}", e);
+            std::process::exit(1);
+        }
+    }
+    let stores: Vec<Arc<NoteStore>> = std::iter::once(Arc::clone(&note_store))
+        .chain(collections.iter().map(|(_, store)| Arc::clone(store)))
+        .collect();
+
+    // Note rendered on not-found pages; an empty value keeps the built-in message
+    let not_found_note = std::env::var("MINIREF_NOT_FOUND_NOTE")
+        .unwrap_or_else(|_| DEFAULT_NOT_FOUND_NOTE.to_string());
+    set_not_found_note(&not_found_note);
+
+    // Watch the notes directories so edits invalidate the cache immediately
+    for store in &stores {
+        if let Err(e) = store.watch() {
+            warn!(
+                "Failed to watch notes directory {}, falling back to modification time checks: {}",
+                store.root_path().display(),
+                e
+            );
+        }
+    }
+
+    // Parse every note ahead of the first request, unless disabled
+    let warm_cache = std::env::var("MINIREF_WARM_CACHE")
+        .map(|value| !matches!(value.as_str(), "0" | "false"))
+        .unwrap_or(true);
+    if warm_cache {
+        // Runs in the background so the server can start listening right away
+        for store in &stores {
+            let store = Arc::clone(store);
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = store.warm_cache() {
+                    warn!("Failed to warm the note cache: {}", e);
+                }
+            });
+        }
+    }
+
+    // Re-parse changed notes on an interval, for systems without file watching
+    let refresh_interval = std::env::var("MINIREF_REFRESH_INTERVAL")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|&seconds| seconds > 0);
+    // Kept alive until the server stops, which stops the refresh
+    let _refresh: Vec<_> = refresh_interval
+        .map(|seconds| {
+            stores
+                .iter()
+                .map(|store| store.start_refresh(std::time::Duration::from_secs(seconds)))
+                .collect()
+        })
+        .unwrap_or_default();
 
     // Generate routes from our Leptos App component
     let routes = generate_route_list(App);
 
-    // Create a router for our REST API endpoints
-    let api_router = Router::new()
-        .route("/notes", get(list_notes_handler)) // GET /api/notes - List all notes
-        .route("/notes/:id", get(get_note_handler)) // GET /api/notes/:id - Get a specific note
-        .with_state(note_store);
+    // The API of the default collection, with the other collections nested
+    // under their names
+    let mut api_router = api_routes().with_state(Arc::clone(&note_store));
+    for (name, store) in collections.iter() {
+        api_router = api_router.nest(
+            &format!("/{}", name),
+            api_routes().with_state(Arc::clone(store)),
+        );
+    }
+    let api_router = api_router
+        // Compress API responses for clients that accept gzip or brotli. Only
+        // the API is compressed, so SSR pages and static files are left alone;
+        // images and zip archives are already compressed.
+        .layer(CompressionLayer::new().compress_when(
+            DefaultPredicate::new().and(NotForContentType::const_new("application/zip")),
+        ))
+        // Run each API request in a span with its method and URI, and log the
+        // response status and latency
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
+                .on_response(DefaultOnResponse::new().level(Level::INFO)),
+        );
+
+    // Let the configured origins read the API, answering their preflight requests
+    let api_router = match cors_origins {
+        Some(origins) => api_router.layer(
+            CorsLayer::new()
+                .allow_origin(origins)
+                .allow_methods([http::Method::GET, http::Method::HEAD])
+                .allow_headers([header::ACCEPT, header::IF_NONE_MATCH])
+                .expose_headers([
+                    header::ETAG,
+                    header::CONTENT_LOCATION,
+                    http::HeaderName::from_static(TOTAL_COUNT_HEADER),
+                ]),
+        ),
+        None => api_router,
+    };
+
+    // The compiled JS, WASM and CSS are named by their content hash, so
+    // browsers can keep them for good; missing files aren't cached
+    let pkg_dir =
+        std::path::Path::new(&*leptos_options.site_root).join(&*leptos_options.site_pkg_dir);
+    let pkg_service = SetResponseHeaderLayer::overriding(
+        header::CACHE_CONTROL,
+        |response: &http::Response<_>| {
+            response
+                .status()
+                .is_success()
+                .then(|| http::HeaderValue::from_static(PKG_CACHE_CONTROL))
+        },
+    )
+    .layer(ServeDir::new(pkg_dir));
 
     // Create the main application router that handles both API and SSR routes
     let app = Router::new()
         // Nest our API routes under /api
         .nest("/api", api_router)
+        .route(
+            "/highlight.css",
+            get(highlight_css_handler).with_state(Arc::clone(&note_store)),
+        ) // GET /highlight.css - Code highlighting colors of the configured theme
+        .route("/sitemap.xml", get(sitemap_handler).with_state(note_store)) // GET /sitemap.xml - Home page and note URLs for search engines
+        .nest_service(&format!("/{}", leptos_options.site_pkg_dir), pkg_service) // GET /pkg/* - Compiled JS, WASM and CSS
         // Add routes for server-side rendered pages
         .leptos_routes(&leptos_options, routes, {
             let leptos_options = leptos_options.clone();
@@ -55,36 +381,1325 @@ async fn main() {
         .fallback(leptos_axum::file_and_error_handler(shell))
         .with_state(leptos_options);
 
+    // Restrict what pages can load and run, since notes can contain raw HTML
+    let app = match content_security_policy {
+        Some(policy) => app.layer(SetResponseHeaderLayer::if_not_present(
+            header::CONTENT_SECURITY_POLICY,
+            policy,
+        )),
+        None => app,
+    };
+
     // Start the server
-    log!("listening on http://{}", &addr);
-    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, app.into_make_service())
+    let listener = match tokio::net::TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Failed to bind to {}: {}", addr, e);
+            std::process::exit(1);
+        }
+    };
+    info!("listening on http://{}", &addr);
+    if let Err(e) = axum::serve(listener, app.into_make_service()).await {
+        error!("Server error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// `Cache-Control` of the compiled JS, WASM and CSS, which are named by their content hash
+#[cfg(feature = "ssr")]
+const PKG_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// File in the notes directory that KaTeX macros are read from, if it exists
+#[cfg(feature = "ssr")]
+const DEFAULT_MACROS_FILE: &str = "macros.yaml";
+
+/// Notes directory used when none is given on the command line or in the environment
+#[cfg(feature = "ssr")]
+const DEFAULT_NOTES_DIR: &str = "./notes";
+
+/// Reads the notes directory from a `--notes-dir <path>` or `--notes-dir=<path>` argument
+///
+/// # Arguments
+/// * `args` - Command line arguments, excluding the program name
+///
+/// # Returns
+/// * `Option<String>` - The notes directory, if given
+#[cfg(feature = "ssr")]
+fn notes_dir_arg(mut args: impl Iterator<Item = String>) -> Option<String> {
+    while let Some(arg) = args.next() {
+        if arg == "--notes-dir" {
+            return args.next();
+        }
+        if let Some(path) = arg.strip_prefix("--notes-dir=") {
+            return Some(path.to_string());
+        }
+    }
+    None
+}
+
+/// Reads the enabled math delimiters from a `MINIREF_MATH_DELIMITERS` value
+///
+/// # Arguments
+/// * `value` - Comma-separated list of `dollars` and `brackets`
+///
+/// # Returns
+/// * `MathDelimiters` - The listed delimiters, all off for an empty list;
+///   unknown names are logged and ignored
+#[cfg(feature = "ssr")]
+fn parse_math_delimiters(value: &str) -> MathDelimiters {
+    let mut delimiters = MathDelimiters {
+        dollars: false,
+        brackets: false,
+    };
+    for name in value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        match name {
+            "dollars" => delimiters.dollars = true,
+            "brackets" => delimiters.brackets = true,
+            other => tracing::warn!(
+                "Ignoring unknown math delimiter in MINIREF_MATH_DELIMITERS: {}",
+                other
+            ),
+        }
+    }
+    delimiters
+}
+
+/// Validates every note for `miniref check`, without starting the server
+///
+/// Prints a line for each note that fails to parse, each broken reference, and
+/// each ID claimed by more than one file, followed by a summary of the counts.
+///
+/// # Arguments
+/// * `store` - The note store to validate
+///
+/// # Returns
+/// * `Result<usize, std::io::Error>` - Number of problems found, or an IO error
+///   if the notes directory can't be read
+#[cfg(feature = "ssr")]
+fn run_check(store: &NoteStore) -> std::io::Result<usize> {
+    let parse_errors = store.parse_errors()?;
+    for failure in &parse_errors {
+        println!("parse error: {}: {}", failure.id, failure.error);
+    }
+
+    let broken_refs = store.validate_references()?;
+    for broken in &broken_refs {
+        println!("broken reference: {} -> {}", broken.from, broken.to);
+    }
+
+    let duplicates = store.find_duplicate_ids()?;
+    for (id, paths) in &duplicates {
+        let files: Vec<String> = paths
+            .iter()
+            .map(|path| relative_display(store.root_path(), path))
+            .collect();
+        println!("duplicate id: {} ({})", id, files.join(", "));
+    }
+
+    let problems = parse_errors.len() + broken_refs.len() + duplicates.len();
+    println!(
+        "{} problems: {} parse errors, {} broken references, {} duplicate ids",
+        problems,
+        parse_errors.len(),
+        broken_refs.len(),
+        duplicates.len()
+    );
+    Ok(problems)
+}
+
+/// Displays a path relative to the notes directory, or in full if it's outside it
+#[cfg(feature = "ssr")]
+fn relative_display(root: &std::path::Path, path: &std::path::Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// Error response returned by API handlers: a status code and a message body
+#[cfg(feature = "ssr")]
+type ApiError = (StatusCode, String);
+
+/// Maps an IO error from the note store to a 500 response with the error message
+#[cfg(feature = "ssr")]
+fn internal_error(error: std::io::Error) -> ApiError {
+    (StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+}
+
+/// Maps an IO error from a note write operation to the matching HTTP status code
+#[cfg(feature = "ssr")]
+fn io_error_response(error: std::io::Error) -> ApiError {
+    let status = match error.kind() {
+        std::io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        std::io::ErrorKind::AlreadyExists => StatusCode::CONFLICT,
+        std::io::ErrorKind::InvalidInput => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, error.to_string())
+}
+
+/// Reads a note back after a write so the response contains the rendered content
+#[cfg(feature = "ssr")]
+fn read_back_note(store: &NoteStore, id: &str) -> Result<Note, ApiError> {
+    store.get_note(id).map_err(internal_error)?.ok_or_else(|| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Note {} was written but could not be found", id),
+        )
+    })
+}
+
+/// Body of the health and readiness responses
+#[cfg(feature = "ssr")]
+#[derive(Serialize)]
+struct HealthStatus {
+    /// `ok`, or `unavailable` if the server can't serve notes
+    status: &'static str,
+    /// Why the server is unavailable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// API handler for the liveness check
+///
+/// Always returns 200 OK with `{"status": "ok"}` without touching the notes
+#[cfg(feature = "ssr")]
+async fn health_handler() -> Json<HealthStatus> {
+    Json(HealthStatus {
+        status: "ok",
+        error: None,
+    })
+}
+
+/// API handler for the readiness check
+///
+/// Returns:
+/// - 200 OK with `{"status": "ok"}` if the notes directory can be read
+/// - 503 Service Unavailable with `{"status": "unavailable", "error": ...}` otherwise
+#[cfg(feature = "ssr")]
+async fn ready_handler(State(store): State<Arc<NoteStore>>) -> (StatusCode, Json<HealthStatus>) {
+    // A single stat of the root, so the check stays cheap and skips the cache
+    let error = match tokio::fs::metadata(store.root_path()).await {
+        Ok(metadata) if metadata.is_dir() => None,
+        Ok(_) => Some("notes path is not a directory".to_string()),
+        Err(e) => Some(e.to_string()),
+    };
+
+    match error {
+        None => (
+            StatusCode::OK,
+            Json(HealthStatus {
+                status: "ok",
+                error: None,
+            }),
+        ),
+        Some(error) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(HealthStatus {
+                status: "unavailable",
+                error: Some(error),
+            }),
+        ),
+    }
+}
+
+/// Query parameters for the notes list endpoint
+#[cfg(feature = "ssr")]
+#[derive(Deserialize)]
+struct ListParams {
+    /// Order of the returned notes (`id`, `title`, or `modified`)
+    #[serde(default)]
+    sort: NoteSort,
+    /// Maximum number of notes to return; all remaining notes when absent
+    limit: Option<usize>,
+    /// Number of notes to skip
+    #[serde(default)]
+    offset: usize,
+    /// Whether drafts are listed too
+    #[serde(default)]
+    include_drafts: bool,
+    /// Whether whole notes are returned instead of their metadata
+    #[serde(default)]
+    full: bool,
+    /// Cursor of the requested page; empty for the first page. Switches to
+    /// cursor paging, see [`NoteStore::list_notes_paged`].
+    cursor: Option<String>,
+    // `tag` may be repeated, so it's read from the raw query pairs instead
+}
+
+/// Response header carrying the total number of notes before paging
+#[cfg(feature = "ssr")]
+const TOTAL_COUNT_HEADER: &str = "x-total-count";
+
+/// API handler for listing notes
+///
+/// Without query parameters, returns the metadata of every published note
+/// sorted by ID, without rendering notes that aren't cached yet. `?full=true`
+/// returns whole notes, content included, instead. Each `?tag=` parameter
+/// restricts the list to notes with that tag, so repeating it returns only
+/// notes carrying all of the given tags. Drafts are only listed with
+/// `?include_drafts=true`.
+///
+/// With `?cursor=`, pages through published notes by ID instead: the first
+/// page has an empty cursor, and each page carries the cursor of the next.
+///
+/// Returns:
+/// - 200 OK with a JSON array of the requested page of notes and the total
+///   number of matching notes in the `X-Total-Count` header, or with
+///   `{notes, next_cursor}` when paging by cursor
+/// - 400 Bad Request if `cursor` is combined with other listing options
+/// - 500 Internal Server Error if the notes directory can't be read
+#[cfg(feature = "ssr")]
+async fn list_notes_handler(
+    State(store): State<Arc<NoteStore>>,
+    Query(params): Query<ListParams>,
+    Query(pairs): Query<Vec<(String, String)>>,
+) -> Result<Response, ApiError> {
+    // Tags are matched case-insensitively, like they're deduplicated
+    let tags: Vec<String> = pairs
+        .iter()
+        .filter(|(key, _)| key == "tag")
+        .map(|(_, value)| value.trim().to_lowercase())
+        .collect();
+    let has_tags = |note_tags: &[String]| {
+        tags.iter()
+            .all(|tag| note_tags.iter().any(|t| t.to_lowercase() == *tag))
+    };
+
+    if let Some(cursor) = params.cursor {
+        // Cursors are note IDs, so they only make sense in ID order
+        if params.sort != NoteSort::Id
+            || params.offset > 0
+            || params.include_drafts
+            || params.full
+            || !tags.is_empty()
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "cursor can't be combined with sort, offset, tag, include_drafts, or full"
+                    .to_string(),
+            ));
+        }
+        let cursor = (!cursor.is_empty()).then_some(cursor);
+        let limit = params.limit.unwrap_or(usize::MAX);
+        let page = tokio::task::spawn_blocking(move || store.list_notes_paged(cursor, limit))
+            .await
+            .map_err(std::io::Error::other)
+            .and_then(|listed| listed)
+            .map_err(internal_error)?;
+        return Ok(Json(page).into_response());
+    }
+
+    if params.full {
+        let mut notes = if params.include_drafts {
+            store.list_all_notes_async().await
+        } else {
+            store.list_notes_async().await
+        }
+        .map_err(internal_error)?;
+        params.sort.sort_notes(&mut notes);
+        notes.retain(|note| has_tags(&note.tags));
+        return Ok(paged_response(notes, params.offset, params.limit));
+    }
+
+    let include_drafts = params.include_drafts;
+    let mut notes = tokio::task::spawn_blocking(move || store.list_metadata(include_drafts))
         .await
-        .unwrap();
+        .map_err(std::io::Error::other)
+        .and_then(|listed| listed)
+        .map_err(internal_error)?;
+    params.sort.sort_metadata(&mut notes);
+    notes.retain(|note| has_tags(&note.tags));
+    Ok(paged_response(notes, params.offset, params.limit))
+}
+
+/// First segments of the API routes, which collection names can't shadow
+#[cfg(feature = "ssr")]
+const RESERVED_COLLECTION_NAMES: [&str; 9] = [
+    "diagnostics",
+    "export",
+    "graph",
+    "health",
+    "notes",
+    "ready",
+    "search",
+    "stats",
+    "tags",
+];
+
+/// Builds the REST API routes of one note collection
+///
+/// The routes are served under `/api` for the default collection and under
+/// `/api/{name}` for each collection in `MINIREF_COLLECTIONS`.
+#[cfg(feature = "ssr")]
+fn api_routes() -> axum::Router<Arc<NoteStore>> {
+    use axum::routing::{get, post};
+
+    axum::Router::new()
+        .route("/health", get(health_handler)) // GET /api/health - Liveness check
+        .route("/ready", get(ready_handler)) // GET /api/ready - Readiness check of the notes directory
+        .route("/notes", get(list_notes_handler).post(create_note_handler)) // GET /api/notes?sort=&limit=&offset=&tag=&include_drafts=&full=&cursor= - List notes, POST /api/notes?template= - Create a note
+        .route(
+            "/notes/:id",
+            get(get_note_handler)
+                .put(update_note_handler)
+                .delete(delete_note_handler),
+        ) // GET/PUT/DELETE /api/notes/:id - Get, replace, or delete a specific note
+        .route("/notes/batch", post(batch_get_notes_handler)) // POST /api/notes/batch - Get several notes by ID
+        .route("/notes/random", get(random_note_handler)) // GET /api/notes/random - A random note, or a redirect to it for browsers
+        .route("/notes/:id/rename", post(rename_note_handler)) // POST /api/notes/:id/rename - Rename a note and update links to it
+        .route("/notes/:id/raw", get(get_note_raw_handler)) // GET /api/notes/:id/raw - Unrendered markdown source of a note
+        .route("/notes/:id/history", get(get_note_history_handler)) // GET /api/notes/:id/history - Saved previous versions of a note
+        .route("/notes/:id/meta", get(get_note_metadata_handler)) // GET /api/notes/:id/meta - Metadata of a note, without rendering it
+        .route("/notes/:id/backlinks", get(get_backlinks_handler)) // GET /api/notes/:id/backlinks - Notes referencing a note
+        .route("/notes/:id/unlinked", get(unlinked_mentions_handler)) // GET /api/notes/:id/unlinked - Mentions of a note's title that don't link to it
+        .route("/notes/:id/related", get(get_related_handler)) // GET /api/notes/:id/related?limit= - Notes sharing tags with a note
+        .route("/notes/:id/assets", get(list_assets_handler)) // GET /api/notes/:id/assets - Files attached to a note
+        .route("/notes/:id/assets/:name", get(get_asset_handler)) // GET /api/notes/:id/assets/:name - Serve a note asset
+        .route(
+            "/notes/:id/assets/:name/thumbnail",
+            get(asset_thumbnail_handler),
+        ) // GET /api/notes/:id/assets/:name/thumbnail?w= - Downscaled image asset
+        .route("/tags", get(list_tags_handler)) // GET /api/tags - All tags with note counts
+        .route("/tags/tree", get(tag_tree_handler)) // GET /api/tags/tree - Tags nested on `/` with note counts
+        .route("/tags/index", get(tag_index_handler)) // GET /api/tags/index - IDs of the notes carrying each tag
+        .route("/export", get(export_handler)) // GET /api/export - Zip archive of all notes and assets
+        .route("/search", get(search_notes_handler)) // GET /api/search?q=... - Full-text search
+        .route("/graph", get(graph_handler)) // GET /api/graph - Reference network as nodes and edges
+        .route("/stats", get(stats_handler)) // GET /api/stats - Note, tag, reference, and orphan counts
+        .route("/diagnostics/broken-refs", get(broken_refs_handler)) // GET /api/diagnostics/broken-refs - References to missing notes
+        .route("/diagnostics/parse-errors", get(parse_errors_handler)) // GET /api/diagnostics/parse-errors - Notes that fail to parse
+        .route("/diagnostics/cache", get(cache_stats_handler)) // GET /api/diagnostics/cache - Cache size and hit/miss counts
+        .route("/diagnostics/duplicate-ids", get(duplicate_ids_handler)) // GET /api/diagnostics/duplicate-ids - IDs claimed by more than one file
+        .route("/diagnostics/cycles", get(reference_cycles_handler)) // GET /api/diagnostics/cycles - Groups of notes referencing each other in a cycle
+        .route("/diagnostics/alias-conflicts", get(alias_conflicts_handler)) // GET /api/diagnostics/alias-conflicts - Aliases that don't resolve
+}
+
+/// Builds a list response from one page of `items`, with the number of items
+/// before paging in the `X-Total-Count` header
+#[cfg(feature = "ssr")]
+fn paged_response<T: Serialize>(items: Vec<T>, offset: usize, limit: Option<usize>) -> Response {
+    let total = items.len();
+    let page: Vec<T> = items
+        .into_iter()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+
+    ([(TOTAL_COUNT_HEADER, total.to_string())], Json(page)).into_response()
+}
+
+/// Query parameters for the note creation endpoint
+#[cfg(feature = "ssr")]
+#[derive(Deserialize)]
+struct CreateParams {
+    /// Template in the notes directory's `.templates` directory to create the note from
+    template: Option<String>,
 }
 
-/// API handler for listing all notes
+/// API handler for creating a new note
+///
+/// The body is a [`CreateNoteRequest`], or a [`TemplateNoteRequest`] when a
+/// `template` is given, in which case the note is the named template with its
+/// placeholders filled in.
 ///
-/// Returns a JSON array of all notes in the store
+/// Returns:
+/// - 201 Created with the new note JSON
+/// - 400 Bad Request if the ID is invalid or a template placeholder has no value
+/// - 404 Not Found if the template doesn't exist
+/// - 409 Conflict if a note with the ID already exists
+/// - 422 Unprocessable Entity if the body doesn't match the request type
+/// - 500 Internal Server Error if the note can't be written or read back
 #[cfg(feature = "ssr")]
-async fn list_notes_handler(State(store): State<Arc<NoteStore>>) -> Json<Vec<Note>> {
-    Json(store.list_notes().expect("Failed to load notes"))
+async fn create_note_handler(
+    State(store): State<Arc<NoteStore>>,
+    Query(params): Query<CreateParams>,
+    Json(request): Json<serde_json::Value>,
+) -> Result<(StatusCode, Json<Note>), ApiError> {
+    let invalid_body = |e: serde_json::Error| (StatusCode::UNPROCESSABLE_ENTITY, e.to_string());
+
+    let id = match params.template {
+        Some(template) => {
+            let request: TemplateNoteRequest =
+                serde_json::from_value(request).map_err(invalid_body)?;
+            let mut vars = request.vars;
+            vars.insert("id".to_string(), request.id);
+            store
+                .create_from_template(&template, vars)
+                .map_err(io_error_response)?
+        }
+        None => {
+            let request: CreateNoteRequest =
+                serde_json::from_value(request).map_err(invalid_body)?;
+            let note = Note {
+                id: request.id,
+                title: request.title,
+                tags: request.tags,
+                references: request.references,
+                aliases: request.aliases,
+                math: request.math,
+                draft: request.draft,
+                layout: request.layout,
+                ..Default::default()
+            };
+            store
+                .create_note(&note, &request.body)
+                .map_err(io_error_response)?;
+            note.id
+        }
+    };
+
+    let created = read_back_note(&store, &id)?;
+    Ok((StatusCode::CREATED, Json(created)))
 }
 
 /// API handler for getting a specific note by ID
 ///
+/// The representation follows the `Accept` header: the note JSON by default,
+/// the markdown source for `text/markdown`, or the rendered content fragment
+/// for `text/html`. Every note response carries an `ETag` derived from
+/// [`Note::hash`] and the representation.
+///
 /// Returns:
-/// - 200 OK with note JSON if found
-/// - 404 Not Found if note doesn't exist
+/// - 200 OK with the note in the accepted format if found. If the ID is an
+///   alias of the note or differs in case, the `Content-Location` header
+///   points at the note's canonical URL.
+/// - 304 Not Modified if the `If-None-Match` header matches the note's ETag
+/// - 404 Not Found with `{"error": "note_not_found", "id": ...}` if note doesn't exist
+/// - 500 Internal Server Error if the note can't be read or parsed, with the reason
 #[cfg(feature = "ssr")]
 async fn get_note_handler(
     State(store): State<Arc<NoteStore>>,
     Path(note_id): Path<String>,
-) -> Result<Json<Note>, StatusCode> {
-    match store.get_note(&note_id).expect("Failed to load note") {
-        Some(note) => Ok(Json(note)),
-        None => Err(StatusCode::NOT_FOUND),
+    headers: http::HeaderMap,
+) -> Result<Response, Response> {
+    let Some(note) = store
+        .get_note_async(&note_id)
+        .await
+        .map_err(|e| internal_error(e).into_response())?
+    else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiErrorBody::NoteNotFound { id: note_id }),
+        )
+            .into_response());
+    };
+
+    let format = NoteFormat::from_accept(&headers);
+    let etag = match format {
+        NoteFormat::Json => format!("\"{}\"", note.hash),
+        NoteFormat::Markdown => format!("\"{}-md\"", note.hash),
+        NoteFormat::Html => format!("\"{}-html\"", note.hash),
+    };
+    let vary = (header::VARY, header::ACCEPT.as_str());
+    if etag_matches(&headers, &etag) {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)], [vary]).into_response());
     }
+
+    let mut response = match format {
+        NoteFormat::Json => ([(header::ETAG, etag)], [vary], Json(&note)).into_response(),
+        NoteFormat::Markdown => {
+            let Some(source) = store
+                .get_note_raw(&note.id)
+                .map_err(|e| internal_error(e).into_response())?
+            else {
+                return Err((
+                    StatusCode::NOT_FOUND,
+                    Json(ApiErrorBody::NoteNotFound { id: note_id }),
+                )
+                    .into_response());
+            };
+            (
+                [
+                    (header::ETAG, etag),
+                    (
+                        header::CONTENT_TYPE,
+                        "text/markdown; charset=utf-8".to_string(),
+                    ),
+                ],
+                [vary],
+                source,
+            )
+                .into_response()
+        }
+        NoteFormat::Html => {
+            ([(header::ETAG, etag)], [vary], Html(note.content.clone())).into_response()
+        }
+    };
+    if note.id != note_id {
+        let location = format!(
+            "{}/notes/{}",
+            store.api_prefix(),
+            encode_path_segment(&note.id)
+        );
+        if let Ok(location) = http::HeaderValue::from_str(&location) {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_LOCATION, location);
+        }
+    }
+    Ok(response)
+}
+
+/// Representations [`get_note_handler`] can answer with
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NoteFormat {
+    /// The [`Note`] as JSON
+    Json,
+    /// The note file as written, frontmatter included
+    Markdown,
+    /// The rendered content fragment
+    Html,
+}
+
+#[cfg(feature = "ssr")]
+impl NoteFormat {
+    /// Picks the format the `Accept` header prefers
+    ///
+    /// Media ranges are ranked by their `q` parameter, with earlier ones
+    /// winning ties. Falls back to JSON when the header is missing or names
+    /// none of the formats.
+    ///
+    /// # Arguments
+    /// * `headers` - Headers of the request
+    ///
+    /// # Returns
+    /// * `NoteFormat` - The preferred format
+    fn from_accept(headers: &http::HeaderMap) -> Self {
+        let mut best: Option<(Self, f32)> = None;
+        let ranges = headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','));
+        for range in ranges {
+            let mut params = range.split(';').map(str::trim);
+            let format = match params
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase()
+                .as_str()
+            {
+                "application/json" | "*/*" | "application/*" => Self::Json,
+                "text/markdown" => Self::Markdown,
+                "text/html" => Self::Html,
+                _ => continue,
+            };
+            let quality = params
+                .filter_map(|param| param.strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality > 0.0 && !best.is_some_and(|(_, best)| quality <= best) {
+                best = Some((format, quality));
+            }
+        }
+        best.map_or(Self::Json, |(format, _)| format)
+    }
+}
+
+/// API handler for listing the previous versions of a note
+///
+/// Versions are only saved while `MINIREF_KEEP_HISTORY` is on.
+///
+/// Returns:
+/// - 200 OK with a JSON array of `{version, saved, size}`, newest first
+/// - 404 Not Found with `{"error": "note_not_found", "id": ...}` if note doesn't exist
+/// - 500 Internal Server Error if the history directory can't be read
+#[cfg(feature = "ssr")]
+async fn get_note_history_handler(
+    State(store): State<Arc<NoteStore>>,
+    Path(note_id): Path<String>,
+) -> Result<Json<Vec<NoteVersion>>, Response> {
+    if !store.note_exists(&note_id) {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiErrorBody::NoteNotFound { id: note_id }),
+        )
+            .into_response());
+    }
+    store
+        .get_note_history(&note_id)
+        .map(Json)
+        .map_err(|e| internal_error(e).into_response())
+}
+
+/// API handler for getting a note's metadata without rendering its content
+///
+/// Returns:
+/// - 200 OK with `{id, title, excerpt, tags, references, created, modified}`;
+///   `excerpt` is empty unless the note was already rendered
+/// - 404 Not Found with `{"error": "note_not_found", "id": ...}` if note doesn't exist
+/// - 500 Internal Server Error if the note can't be read or parsed, with the reason
+#[cfg(feature = "ssr")]
+async fn get_note_metadata_handler(
+    State(store): State<Arc<NoteStore>>,
+    Path(note_id): Path<String>,
+) -> Result<Json<NoteMetadata>, Response> {
+    let metadata = tokio::task::spawn_blocking({
+        let note_id = note_id.clone();
+        move || store.get_note_metadata(&note_id)
+    })
+    .await
+    .map_err(std::io::Error::other)
+    .and_then(|metadata| metadata)
+    .map_err(|e| internal_error(e).into_response())?;
+
+    match metadata {
+        Some(metadata) => Ok(Json(metadata)),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiErrorBody::NoteNotFound { id: note_id }),
+        )
+            .into_response()),
+    }
+}
+
+/// Checks whether a request's `If-None-Match` header matches an entity tag
+///
+/// # Arguments
+/// * `headers` - Request headers
+/// * `etag` - The current entity tag, quoted
+///
+/// # Returns
+/// * `bool` - Whether any listed tag, compared weakly, or `*` matches
+#[cfg(feature = "ssr")]
+fn etag_matches(headers: &http::HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// API handler for getting several notes at once
+///
+/// Takes a JSON array of note IDs.
+///
+/// Returns:
+/// - 200 OK with a JSON object mapping each requested ID to its note, or
+///   `null` if it doesn't exist or can't be parsed
+#[cfg(feature = "ssr")]
+async fn batch_get_notes_handler(
+    State(store): State<Arc<NoteStore>>,
+    Json(ids): Json<Vec<String>>,
+) -> Json<HashMap<String, Option<Note>>> {
+    Json(store.get_notes_by_ids(&ids))
+}
+
+/// API handler for picking a random note
+///
+/// Returns:
+/// - 302 Found redirecting to the note's page if the request accepts HTML,
+///   as when followed from a browser link
+/// - 200 OK with the note's metadata JSON otherwise
+/// - 404 Not Found if there are no notes
+/// - 500 Internal Server Error if the notes can't be read
+#[cfg(feature = "ssr")]
+async fn random_note_handler(
+    State(store): State<Arc<NoteStore>>,
+    headers: http::HeaderMap,
+) -> Result<Response, ApiError> {
+    let note = store
+        .random_note()
+        .map_err(internal_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                "There are no notes to pick from".to_string(),
+            )
+        })?;
+
+    let wants_html = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"));
+    if wants_html {
+        return Ok((
+            StatusCode::FOUND,
+            [(header::LOCATION, note_page_path(&note.id))],
+        )
+            .into_response());
+    }
+    Ok(Json(note).into_response())
+}
+
+/// Builds the path of a note's page, percent-encoding each segment of its ID
+///
+/// # Arguments
+/// * `id` - ID of the note, possibly nested (e.g. `projects/foo`)
+///
+/// # Returns
+/// * `String` - The page path, e.g. `/projects/foo`
+#[cfg(feature = "ssr")]
+fn note_page_path(id: &str) -> String {
+    let segments: Vec<String> = id.split('/').map(encode_path_segment).collect();
+    format!("/{}", segments.join("/"))
+}
+
+/// API handler for getting the markdown source of a note
+///
+/// Returns:
+/// - 200 OK with the note file as `text/markdown`, frontmatter included
+/// - 404 Not Found with `{"error": "note_not_found", "id": ...}` if note doesn't exist
+/// - 500 Internal Server Error if the file can't be read
+#[cfg(feature = "ssr")]
+async fn get_note_raw_handler(
+    State(store): State<Arc<NoteStore>>,
+    Path(note_id): Path<String>,
+) -> Result<Response, Response> {
+    match store
+        .get_note_raw(&note_id)
+        .map_err(|e| internal_error(e).into_response())?
+    {
+        Some(content) => Ok((
+            [(header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+            content,
+        )
+            .into_response()),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiErrorBody::NoteNotFound { id: note_id }),
+        )
+            .into_response()),
+    }
+}
+
+/// API handler for replacing an existing note
+///
+/// Returns:
+/// - 200 OK with the updated note JSON
+/// - 404 Not Found if the note doesn't exist
+/// - 500 Internal Server Error if the note can't be written or read back
+#[cfg(feature = "ssr")]
+async fn update_note_handler(
+    State(store): State<Arc<NoteStore>>,
+    Path(note_id): Path<String>,
+    Json(request): Json<UpdateNoteRequest>,
+) -> Result<Json<Note>, ApiError> {
+    let note = Note {
+        id: note_id,
+        title: request.title,
+        tags: request.tags,
+        references: request.references,
+        aliases: request.aliases,
+        math: request.math,
+        draft: request.draft,
+        layout: request.layout,
+        ..Default::default()
+    };
+    store
+        .update_note(&note, &request.body)
+        .map_err(io_error_response)?;
+
+    read_back_note(&store, &note.id).map(Json)
+}
+
+/// API handler for renaming a note and updating the notes linking to it
+///
+/// Returns:
+/// - 200 OK with the renamed note JSON
+/// - 400 Bad Request if the new ID is invalid
+/// - 404 Not Found if the note doesn't exist
+/// - 409 Conflict if a note with the new ID already exists
+/// - 500 Internal Server Error if the files can't be moved or rewritten
+#[cfg(feature = "ssr")]
+async fn rename_note_handler(
+    State(store): State<Arc<NoteStore>>,
+    Path(note_id): Path<String>,
+    Json(request): Json<RenameNoteRequest>,
+) -> Result<Json<Note>, ApiError> {
+    store
+        .rename_note(&note_id, &request.id)
+        .map_err(io_error_response)?;
+
+    read_back_note(&store, &request.id).map(Json)
+}
+
+/// API handler for deleting a note and its assets
+///
+/// Returns:
+/// - 204 No Content on success
+/// - 404 Not Found if the note doesn't exist
+/// - 500 Internal Server Error if the files can't be removed
+#[cfg(feature = "ssr")]
+async fn delete_note_handler(
+    State(store): State<Arc<NoteStore>>,
+    Path(note_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    store.delete_note(&note_id).map_err(io_error_response)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// API handler for listing the files attached to a note
+///
+/// Returns:
+/// - 200 OK with a JSON array of the note's assets, empty if it has none
+/// - 404 Not Found with `{"error": "note_not_found", "id": ...}` if note doesn't exist
+/// - 500 Internal Server Error if the note can't be read
+#[cfg(feature = "ssr")]
+async fn list_assets_handler(
+    State(store): State<Arc<NoteStore>>,
+    Path(note_id): Path<String>,
+) -> Result<Json<Vec<Asset>>, Response> {
+    match store
+        .get_note_async(&note_id)
+        .await
+        .map_err(|e| internal_error(e).into_response())?
+    {
+        Some(note) => Ok(Json(note.assets)),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiErrorBody::NoteNotFound { id: note_id }),
+        )
+            .into_response()),
+    }
+}
+
+/// API handler for serving a file from a note's `.assets` directory
+///
+/// Returns:
+/// - 200 OK with the file contents and a guessed `Content-Type`
+/// - 404 Not Found if the note or asset doesn't exist
+/// - 500 Internal Server Error if the file can't be read
+#[cfg(feature = "ssr")]
+async fn get_asset_handler(
+    State(store): State<Arc<NoteStore>>,
+    Path((note_id, name)): Path<(String, String)>,
+) -> Result<impl IntoResponse, ApiError> {
+    let path = store.asset_path(&note_id, &name).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Asset not found: {}/{}", note_id, name),
+        )
+    })?;
+    let bytes = std::fs::read(&path).map_err(internal_error)?;
+    let mime_type = mime_guess::from_path(&path).first_or_octet_stream();
+
+    Ok((
+        [(header::CONTENT_TYPE, mime_type.essence_str().to_string())],
+        bytes,
+    ))
+}
+
+/// Query parameters for the asset thumbnail endpoint
+#[cfg(feature = "ssr")]
+#[derive(Deserialize)]
+struct ThumbnailParams {
+    /// Width of the thumbnail in pixels, capped at `MAX_THUMBNAIL_WIDTH`
+    #[serde(default = "default_thumbnail_width")]
+    w: u32,
+}
+
+/// Thumbnail width used when the request doesn't give one
+#[cfg(feature = "ssr")]
+fn default_thumbnail_width() -> u32 {
+    200
+}
+
+/// API handler for serving a downscaled PNG copy of an image asset
+///
+/// Returns:
+/// - 200 OK with the thumbnail as `image/png`
+/// - 404 Not Found if the note or asset doesn't exist
+/// - 415 Unsupported Media Type if the asset isn't an image that can be decoded
+/// - 500 Internal Server Error if the thumbnail can't be generated or read
+#[cfg(feature = "ssr")]
+async fn asset_thumbnail_handler(
+    State(store): State<Arc<NoteStore>>,
+    Path((note_id, name)): Path<(String, String)>,
+    Query(params): Query<ThumbnailParams>,
+) -> Result<impl IntoResponse, ApiError> {
+    // Decoding and resizing images is CPU-bound
+    let thumbnail = tokio::task::spawn_blocking({
+        let (note_id, name) = (note_id.clone(), name.clone());
+        move || store.asset_thumbnail(&note_id, &name, params.w)
+    })
+    .await
+    .map_err(std::io::Error::other)
+    .and_then(|thumbnail| thumbnail)
+    .map_err(|e| match e.kind() {
+        std::io::ErrorKind::Unsupported => (StatusCode::UNSUPPORTED_MEDIA_TYPE, e.to_string()),
+        _ => internal_error(e),
+    })?;
+    let path = thumbnail.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("Asset not found: {}/{}", note_id, name),
+        )
+    })?;
+    let bytes = tokio::fs::read(&path).await.map_err(internal_error)?;
+
+    Ok(([(header::CONTENT_TYPE, "image/png")], bytes))
+}
+
+/// API handler for listing the notes that reference a given note
+///
+/// Returns a JSON array of note metadata, empty if nothing links to the note,
+/// or 500 Internal Server Error if the notes can't be read
+#[cfg(feature = "ssr")]
+async fn get_backlinks_handler(
+    State(store): State<Arc<NoteStore>>,
+    Path(note_id): Path<String>,
+) -> Result<Json<Vec<NoteMetadata>>, ApiError> {
+    store
+        .get_backlinks(&note_id)
+        .map(Json)
+        .map_err(internal_error)
+}
+
+/// Number of related notes returned when no limit is given
+#[cfg(feature = "ssr")]
+const DEFAULT_RELATED_LIMIT: usize = 5;
+
+/// Query parameters for the related notes endpoint
+#[cfg(feature = "ssr")]
+#[derive(Deserialize)]
+struct RelatedParams {
+    /// Maximum number of notes to return
+    limit: Option<usize>,
+}
+
+/// API handler for suggesting notes that share tags with a given note
+///
+/// Returns a JSON array of note metadata, most shared tags first, empty if
+/// no other note shares a tag, or 500 Internal Server Error if the notes
+/// can't be read
+#[cfg(feature = "ssr")]
+async fn get_related_handler(
+    State(store): State<Arc<NoteStore>>,
+    Path(note_id): Path<String>,
+    Query(params): Query<RelatedParams>,
+) -> Result<Json<Vec<NoteMetadata>>, ApiError> {
+    store
+        .related_notes(&note_id, params.limit.unwrap_or(DEFAULT_RELATED_LIMIT))
+        .map(Json)
+        .map_err(internal_error)
+}
+
+/// API handler for finding mentions of a note's title that don't link to it
+///
+/// Returns:
+/// - 200 OK with a JSON array of `{note_id, snippet}`, empty if there are none
+/// - 404 Not Found with `{"error": "note_not_found", "id": ...}` if note doesn't exist
+/// - 500 Internal Server Error if the notes can't be read
+#[cfg(feature = "ssr")]
+async fn unlinked_mentions_handler(
+    State(store): State<Arc<NoteStore>>,
+    Path(note_id): Path<String>,
+) -> Result<Json<Vec<UnlinkedMention>>, Response> {
+    // Reads and searches every note file
+    let mentions = tokio::task::spawn_blocking({
+        let note_id = note_id.clone();
+        move || {
+            let exists = store.get_note_metadata(&note_id)?.is_some();
+            exists
+                .then(|| store.unlinked_mentions(&note_id))
+                .transpose()
+        }
+    })
+    .await
+    .map_err(std::io::Error::other)
+    .and_then(|mentions| mentions)
+    .map_err(|e| internal_error(e).into_response())?;
+
+    match mentions {
+        Some(mentions) => Ok(Json(mentions)),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiErrorBody::NoteNotFound { id: note_id }),
+        )
+            .into_response()),
+    }
+}
+
+/// Handler for the stylesheet coloring highlighted code in notes
+///
+/// Returns:
+/// - 200 OK with CSS for the configured syntect theme as `text/css`
+/// - 500 Internal Server Error if the theme can't be converted to CSS
+#[cfg(feature = "ssr")]
+async fn highlight_css_handler(
+    State(store): State<Arc<NoteStore>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let css = store.highlight_css().map_err(internal_error)?;
+    Ok(([(header::CONTENT_TYPE, "text/css; charset=utf-8")], css))
+}
+
+/// Handler for the sitemap of the home page and every note
+///
+/// URLs are made absolute using the request's `Host` header, and the scheme
+/// from `X-Forwarded-Proto` when behind a proxy.
+///
+/// Returns:
+/// - 200 OK with the sitemap as `application/xml`, each note's `<lastmod>`
+///   taken from its file modification time and left out where that's unknown
+/// - 500 Internal Server Error if the notes can't be read
+#[cfg(feature = "ssr")]
+async fn sitemap_handler(
+    State(store): State<Arc<NoteStore>>,
+    headers: http::HeaderMap,
+) -> Result<impl IntoResponse, ApiError> {
+    let entries = tokio::task::spawn_blocking(move || store.sitemap_entries())
+        .await
+        .map_err(std::io::Error::other)
+        .and_then(|listed| listed)
+        .map_err(internal_error)?;
+
+    let header_value = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    let scheme = header_value("x-forwarded-proto").unwrap_or("http");
+    let host = header_value("host").unwrap_or("localhost");
+    let base = html_escape::encode_text(&format!("{}://{}", scheme, host)).into_owned();
+
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    xml.push_str(&format!("  <url><loc>{}/</loc></url>\n", base));
+    for (id, modified) in entries {
+        let path = note_page_path(&id);
+        let lastmod = modified
+            .map(|modified| {
+                let modified = chrono::DateTime::<chrono::Utc>::from(modified)
+                    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+                format!("<lastmod>{}</lastmod>", modified)
+            })
+            .unwrap_or_default();
+        xml.push_str(&format!(
+            "  <url><loc>{}{}</loc>{}</url>\n",
+            base, path, lastmod
+        ));
+    }
+    xml.push_str("</urlset>\n");
+
+    Ok(([(header::CONTENT_TYPE, "application/xml")], xml))
+}
+
+/// Writes a zip export of the notes to a temporary file, ready to be read back.
+///
+/// Zip archives can't be written to a plain stream, so the archive is spooled
+/// to disk instead of memory. Where the platform allows it, the file is
+/// unlinked right away and disappears once the returned handle is closed.
+#[cfg(feature = "ssr")]
+fn spool_export(store: &NoteStore) -> std::io::Result<std::fs::File> {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default();
+    let path = std::env::temp_dir().join(format!(
+        "miniref-export-{}-{}.zip",
+        std::process::id(),
+        nanos
+    ));
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    let _ = std::fs::remove_file(&path);
+
+    let mut file = store.export_to_writer(file)?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(file)
+}
+
+/// API handler for exporting every note and its assets as a zip archive
+///
+/// Returns the archive as an `application/zip` attachment, streamed from a
+/// temporary file, or 500 Internal Server Error if the notes can't be read
+#[cfg(feature = "ssr")]
+async fn export_handler(State(store): State<Arc<NoteStore>>) -> Result<Response, ApiError> {
+    let file = tokio::task::spawn_blocking(move || spool_export(&store))
+        .await
+        .map_err(|e| internal_error(std::io::Error::other(e)))?
+        .map_err(internal_error)?;
+
+    let stream = tokio_util::io::ReaderStream::new(tokio::fs::File::from_std(file));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"miniref-notes.zip\"",
+            ),
+        ],
+        Body::from_stream(stream),
+    )
+        .into_response())
+}
+
+/// API handler for listing tags
+///
+/// Returns a JSON array of `[tag, count]` pairs ordered by tag,
+/// or 500 Internal Server Error if the notes can't be read
+#[cfg(feature = "ssr")]
+async fn list_tags_handler(
+    State(store): State<Arc<NoteStore>>,
+) -> Result<Json<Vec<(String, usize)>>, ApiError> {
+    store.list_tags().map(Json).map_err(internal_error)
+}
+
+/// API handler for the notes carrying each tag
+///
+/// Unlike the tag list, which only counts notes, returns which notes carry
+/// each tag.
+///
+/// Returns:
+/// - 200 OK with a JSON array of `{tag, note_ids}` ordered by tag, with the
+///   note IDs sorted
+/// - 500 Internal Server Error if the notes can't be read
+#[cfg(feature = "ssr")]
+async fn tag_index_handler(
+    State(store): State<Arc<NoteStore>>,
+) -> Result<Json<Vec<TagIndexEntry>>, ApiError> {
+    let mut entries: Vec<TagIndexEntry> = store
+        .tag_index()
+        .map_err(internal_error)?
+        .into_iter()
+        .map(|(tag, note_ids)| TagIndexEntry { tag, note_ids })
+        .collect();
+    entries.sort_by(|a, b| a.tag.to_lowercase().cmp(&b.tag.to_lowercase()));
+    Ok(Json(entries))
+}
+
+/// API handler for the tag hierarchy
+///
+/// Returns the root [`TagNode`] as JSON, with tags nested on `/` and note
+/// counts at each level, or 500 Internal Server Error if the notes can't be read
+#[cfg(feature = "ssr")]
+async fn tag_tree_handler(State(store): State<Arc<NoteStore>>) -> Result<Json<TagNode>, ApiError> {
+    store.tag_tree().map(Json).map_err(internal_error)
+}
+
+/// Query parameters for the search endpoint
+#[cfg(feature = "ssr")]
+#[derive(Deserialize)]
+struct SearchParams {
+    /// The search query
+    #[serde(default)]
+    q: String,
+}
+
+/// API handler for searching notes
+///
+/// Returns a JSON array of note metadata, with title matches ranked before
+/// tag and body matches, or 500 Internal Server Error if the notes can't be read
+#[cfg(feature = "ssr")]
+async fn search_notes_handler(
+    State(store): State<Arc<NoteStore>>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<Vec<NoteMetadata>>, ApiError> {
+    store
+        .search_notes(&params.q)
+        .map(Json)
+        .map_err(internal_error)
+}
+
+/// API handler for the reference graph
+///
+/// Returns `{nodes: [{id, title}], edges: [{source, target}]}` as JSON,
+/// or 500 Internal Server Error if the notes can't be read
+#[cfg(feature = "ssr")]
+async fn graph_handler(
+    State(store): State<Arc<NoteStore>>,
+) -> Result<Json<ReferenceGraph>, ApiError> {
+    store.reference_graph().map(Json).map_err(internal_error)
+}
+
+/// API handler for the corpus statistics
+///
+/// Returns `{total_notes, total_tags, total_references, orphan_notes,
+/// average_word_count}` as JSON, or 500 Internal Server Error if the notes
+/// can't be read
+#[cfg(feature = "ssr")]
+async fn stats_handler(State(store): State<Arc<NoteStore>>) -> Result<Json<CorpusStats>, ApiError> {
+    store.corpus_stats().map(Json).map_err(internal_error)
+}
+
+/// API handler for listing references to notes that don't exist
+///
+/// Returns a JSON array of `{from, to}` pairs, empty if every reference
+/// resolves, or 500 Internal Server Error if the notes can't be read
+#[cfg(feature = "ssr")]
+async fn broken_refs_handler(
+    State(store): State<Arc<NoteStore>>,
+) -> Result<Json<Vec<BrokenRef>>, ApiError> {
+    store
+        .validate_references()
+        .map(Json)
+        .map_err(internal_error)
+}
+
+/// API handler for listing note files that fail to parse
+///
+/// Returns a JSON array of `{id, error}` objects, empty if every note parses,
+/// or 500 Internal Server Error if the notes directory can't be read
+#[cfg(feature = "ssr")]
+async fn parse_errors_handler(
+    State(store): State<Arc<NoteStore>>,
+) -> Result<Json<Vec<ParseFailure>>, ApiError> {
+    store.parse_errors().map(Json).map_err(internal_error)
+}
+
+/// API handler for listing note IDs claimed by more than one file
+///
+/// Returns a JSON array of `{id, files}` objects with file paths relative to
+/// the notes directory, empty if every ID is unique, or 500 Internal Server
+/// Error if the notes can't be read
+#[cfg(feature = "ssr")]
+async fn duplicate_ids_handler(
+    State(store): State<Arc<NoteStore>>,
+) -> Result<Json<Vec<DuplicateId>>, ApiError> {
+    let duplicates = store.find_duplicate_ids().map_err(internal_error)?;
+    Ok(Json(
+        duplicates
+            .into_iter()
+            .map(|(id, paths)| DuplicateId {
+                id,
+                files: paths
+                    .iter()
+                    .map(|path| relative_display(store.root_path(), path))
+                    .collect(),
+            })
+            .collect(),
+    ))
+}
+
+/// API handler for listing reference cycles
+///
+/// Returns a JSON array of cycles, each an array of the IDs of the notes in
+/// it, empty if there are none, or 500 Internal Server Error if the notes
+/// can't be read
+#[cfg(feature = "ssr")]
+async fn reference_cycles_handler(
+    State(store): State<Arc<NoteStore>>,
+) -> Result<Json<Vec<Vec<String>>>, ApiError> {
+    store.reference_cycles().map(Json).map_err(internal_error)
+}
+
+/// API handler for listing aliases that don't resolve to a single note
+///
+/// Returns a JSON array of `{alias, ids}` objects, empty if every alias
+/// resolves, or 500 Internal Server Error if the notes can't be read
+#[cfg(feature = "ssr")]
+async fn alias_conflicts_handler(
+    State(store): State<Arc<NoteStore>>,
+) -> Result<Json<Vec<AliasConflict>>, ApiError> {
+    store.alias_conflicts().map(Json).map_err(internal_error)
+}
+
+/// API handler for note cache statistics
+///
+/// Returns `{entries, hits, misses}` for the note cache
+#[cfg(feature = "ssr")]
+async fn cache_stats_handler(State(store): State<Arc<NoteStore>>) -> Json<CacheStats> {
+    Json(store.cache_stats())
 }
 
 /// Client-side entry point (disabled when using SSR)
@@ -97,3 +1712,127 @@ pub fn main() {
     // unless we want this to work with e.g., Trunk for pure client-side testing
     // see lib.rs for hydration function instead
 }
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use miniref::note::NoteListPage;
+
+    /// Creates a store over an empty directory holding the given notes, replacing
+    /// a directory left by an earlier run
+    fn store(name: &str, notes: &[(&str, &str)]) -> Arc<NoteStore> {
+        let dir = std::env::temp_dir().join(format!("miniref-{}-{}", name, std::process::id()));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+        for (id, content) in notes {
+            std::fs::write(dir.join(format!("{}.md", id)), content).unwrap();
+        }
+        Arc::new(NoteStore::new(&dir).unwrap())
+    }
+
+    #[test]
+    fn math_delimiters_are_read_from_a_comma_separated_list() {
+        assert_eq!(
+            parse_math_delimiters("brackets, dollars"),
+            MathDelimiters {
+                dollars: true,
+                brackets: true,
+            }
+        );
+        assert_eq!(
+            parse_math_delimiters("brackets"),
+            MathDelimiters {
+                dollars: false,
+                brackets: true,
+            }
+        );
+        // Unknown names are skipped, leaving math off when nothing else is listed
+        assert_eq!(
+            parse_math_delimiters(" ,latex"),
+            MathDelimiters {
+                dollars: false,
+                brackets: false,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn rename_maps_store_errors_to_status_codes() {
+        let store = store(
+            "rename-handler",
+            &[
+                ("a", "---\ntitle: A\n---\nSee [[b]].\n"),
+                ("b", "---\ntitle: B\nreferences: [a]\n---\nSee [[a]].\n"),
+            ],
+        );
+        let rename = |from: &str, to: &str| {
+            rename_note_handler(
+                State(store.clone()),
+                Path(from.to_string()),
+                Json(RenameNoteRequest { id: to.to_string() }),
+            )
+        };
+
+        let missing = rename("missing", "c").await.unwrap_err();
+        assert_eq!(missing.0, StatusCode::NOT_FOUND);
+        let taken = rename("a", "b").await.unwrap_err();
+        assert_eq!(taken.0, StatusCode::CONFLICT);
+
+        let Json(renamed) = rename("a", "c").await.unwrap();
+        assert_eq!(renamed.id, "c");
+        assert!(!store.note_exists("a"));
+        assert_eq!(
+            store.get_note_raw("b").unwrap().unwrap(),
+            "---\ntitle: B\nreferences: [c]\n---\nSee [[c]].\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn cursor_listing_pages_by_id_and_rejects_other_options() {
+        let store = store(
+            "cursor-handler",
+            &[
+                ("a", "---\ntitle: A\n---\n"),
+                ("b", "---\ntitle: B\n---\n"),
+                ("c", "---\ntitle: C\n---\n"),
+            ],
+        );
+        let list = |sort: NoteSort, cursor: &str, pairs: Vec<(String, String)>| {
+            let params = ListParams {
+                sort,
+                limit: Some(2),
+                offset: 0,
+                include_drafts: false,
+                full: false,
+                cursor: Some(cursor.to_string()),
+            };
+            list_notes_handler(State(store.clone()), Query(params), Query(pairs))
+        };
+
+        let sorted = list(NoteSort::Title, "", vec![]).await.unwrap_err();
+        assert_eq!(sorted.0, StatusCode::BAD_REQUEST);
+        let tagged = list(NoteSort::Id, "", vec![("tag".into(), "x".into())])
+            .await
+            .unwrap_err();
+        assert_eq!(tagged.0, StatusCode::BAD_REQUEST);
+
+        let mut ids = Vec::new();
+        let mut cursor = String::new();
+        loop {
+            let response = list(NoteSort::Id, &cursor, vec![]).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let page: NoteListPage = serde_json::from_slice(&body).unwrap();
+            ids.extend(page.notes.into_iter().map(|note| note.id));
+            match page.next_cursor {
+                Some(next) => cursor = next,
+                None => break,
+            }
+        }
+        assert_eq!(ids, ["a", "b", "c"]);
+    }
+}
@@ -2,49 +2,499 @@
 //!
 //! This module provides the core functionality for storing, processing, and retrieving notes.
 //! It includes:
-//! - File-based note storage with YAML frontmatter
+//! - Note storage behind a pluggable backend, files with YAML, TOML, or JSON
+//!   frontmatter by default
 //! - Markdown processing with syntax highlighting
+//! - Wikilink (`[[note-id]]`) parsing and rewriting
 //! - LaTeX math rendering (both inline and display)
 //! - Asset management for note attachments
-//! - In-memory caching with file modification tracking
+//! - In-memory LRU caching with file modification tracking or live file watching
 
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[cfg(feature = "ssr")]
 use {
-    gray_matter::{engine::YAML, Matter}, // For YAML frontmatter parsing
-    katex::{render_with_opts, Opts},     // For LaTeX math rendering
+    gray_matter::{
+        engine::{Engine, JSON, TOML, YAML},
+        Matter, Pod,
+    }, // For frontmatter parsing
+    ignore::gitignore::{Gitignore, GitignoreBuilder}, // For `.minirefignore` patterns
+    image::{ImageError, ImageFormat},                 // For asset thumbnails
+    katex::{render_with_opts, Opts},                  // For LaTeX math rendering
     markdown::{to_html_with_options, Options as MarkdownOptions}, // For Markdown processing
-    parking_lot::RwLock,
-    regex::Regex, // For pattern matching
-    std::collections::HashMap,
-    std::path::{Path, PathBuf}, // For filesystem operations
-    std::time::SystemTime,
-    syntect::{highlighting::ThemeSet, html::highlighted_html_for_string, parsing::SyntaxSet}, // For syntax highlighting
+    notify::{RecommendedWatcher, RecursiveMode, Watcher}, // For live cache invalidation
+    parking_lot::{Mutex, RwLock},
+    rand::seq::SliceRandom, // For picking random notes
+    regex::Regex,           // For pattern matching
+    std::collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    std::hash::{DefaultHasher, Hash, Hasher},
+    std::io::{Seek, Write},
+    std::path::{Component, Path, PathBuf}, // For filesystem operations
+    std::sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    std::sync::{Arc, Weak},
+    std::time::{Duration, SystemTime},
+    syntect::{
+        highlighting::ThemeSet,
+        html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator},
+        parsing::SyntaxSet,
+        util::LinesWithEndings,
+    }, // For syntax highlighting
+    tracing::{debug, info, warn},
 };
 
+/// Name of the syntect theme used for syntax highlighting when none is configured
+pub const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+/// Maximum number of processed notes kept in the cache when none is configured
+pub const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
+/// Approximate maximum length, in characters, of a note's [`Note::excerpt`]
+pub const EXCERPT_LENGTH: usize = 200;
+
+/// Reading speed used to estimate [`Note::reading_time_minutes`]
+pub const WORDS_PER_MINUTE: usize = 220;
+
+/// How deeply notes embedded with `![[id]]` may embed other notes by default
+pub const DEFAULT_EMBED_DEPTH: usize = 2;
+
+/// Path the REST API of a note store is served under by default
+pub const DEFAULT_API_PREFIX: &str = "/api";
+
+/// Number of characters kept on each side of a mention in an [`UnlinkedMention`] snippet
+pub const MENTION_CONTEXT: usize = 60;
+
+/// Largest thumbnail width, in pixels, that can be requested for an image asset
+pub const MAX_THUMBNAIL_WIDTH: u32 = 1024;
+
 /// Represents a complete note with all its metadata and content.
 ///
 /// This struct is used both for storing notes and transmitting them between
 /// the server and client. It includes all note data including content and assets.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Note {
-    /// Unique identifier for the note
+    /// Unique identifier for the note. For notes read from disk this is the
+    /// file's path relative to the notes directory, without `.md` (e.g.
+    /// `projects/foo`); a different `id` in the frontmatter is ignored.
+    #[serde(default)]
     pub id: String,
     /// Display title of the note
+    #[serde(default)]
     pub title: String,
     /// Main content of the note (rendered HTML)
     #[serde(default)]
     pub content: String,
+    /// Short plain-text summary of the content, without markup or math
+    #[serde(default)]
+    pub excerpt: String,
+    /// Number of words in the content, not counting code blocks and math
+    #[serde(default)]
+    pub word_count: usize,
+    /// Estimated minutes needed to read the content, rounded up
+    #[serde(default)]
+    pub reading_time_minutes: u32,
     /// List of tags associated with the note
     #[serde(default)]
     pub tags: Vec<String>,
     /// List of IDs of other notes this note references
     #[serde(default)]
     pub references: Vec<String>,
+    /// Other IDs the note can be requested by, such as IDs it was renamed from
+    #[serde(default)]
+    pub aliases: Vec<String>,
     /// List of files/attachments associated with this note
     #[serde(default)]
     pub assets: Vec<Asset>,
+    /// Table of contents generated from the note's headings
+    #[serde(default)]
+    pub toc: Vec<TocEntry>,
+    /// When the note was created (frontmatter `created`, else file creation time)
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub created: Option<DateTime<Utc>>,
+    /// When the note was last modified (frontmatter `modified`, else file modification time)
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub modified: Option<DateTime<Utc>>,
+    /// Whether `$...$` and `$$...$$` are rendered as math (frontmatter `math`, default true)
+    #[serde(default = "default_math")]
+    pub math: bool,
+    /// Whether the note is unfinished (frontmatter `draft`). Drafts are left out
+    /// of listings but can still be fetched directly.
+    #[serde(default)]
+    pub draft: bool,
+    /// Page layout the note is displayed with (frontmatter `layout`)
+    #[serde(default, deserialize_with = "deserialize_layout")]
+    pub layout: NoteLayout,
+    /// Fingerprint of the note file and its rendering, used as the note's ETag.
+    /// Changes whenever the note's API representation does.
+    #[serde(default)]
+    pub hash: String,
+    /// Markdown body as written, before rendering. Only set when the store is
+    /// configured with [`NoteStore::with_note_source`], and left out of the
+    /// serialized note otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl Note {
+    /// Describes the note as a schema.org `Article` in JSON-LD, for search engines.
+    ///
+    /// The description has the note's title as `name` and `headline`, its
+    /// timestamps as `datePublished` and `dateModified`, and its tags as
+    /// `keywords`; missing timestamps and tags are left out. `</` is escaped
+    /// so the JSON can be embedded in a `<script>` element as is.
+    ///
+    /// # Returns
+    /// * `Option<String>` - The JSON-LD document, or None if the note has no title
+    pub fn json_ld(&self) -> Option<String> {
+        if self.title.trim().is_empty() {
+            return None;
+        }
+
+        let mut data = serde_json::json!({
+            "@context": "https://schema.org",
+            "@type": "Article",
+            "name": self.title,
+            "headline": self.title,
+        });
+        if let Some(created) = self.created {
+            data["datePublished"] = created.to_rfc3339().into();
+        }
+        if let Some(modified) = self.modified {
+            data["dateModified"] = modified.to_rfc3339().into();
+        }
+        if !self.tags.is_empty() {
+            data["keywords"] = self.tags.join(", ").into();
+        }
+        Some(data.to_string().replace("</", "<\\/"))
+    }
+}
+
+/// Math rendering is enabled unless a note opts out
+fn default_math() -> bool {
+    true
+}
+
+impl Default for Note {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            title: String::new(),
+            content: String::new(),
+            excerpt: String::new(),
+            word_count: 0,
+            reading_time_minutes: 0,
+            tags: Vec::new(),
+            references: Vec::new(),
+            aliases: Vec::new(),
+            assets: Vec::new(),
+            toc: Vec::new(),
+            created: None,
+            modified: None,
+            math: default_math(),
+            draft: false,
+            layout: NoteLayout::default(),
+            hash: String::new(),
+            source: None,
+        }
+    }
+}
+
+/// Parses a timestamp from an RFC 3339 string or a plain `YYYY-MM-DD` date.
+///
+/// Plain dates are interpreted as midnight UTC. Returns None if the value
+/// matches neither format.
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|timestamp| timestamp.and_utc())
+        })
+}
+
+/// Leniently deserializes an optional timestamp, treating unparseable values as missing
+/// so a malformed frontmatter date doesn't cause the whole note to be rejected.
+fn deserialize_timestamp<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.as_deref().and_then(parse_timestamp))
+}
+
+/// Page layout a note is displayed with, chosen by its frontmatter `layout` field.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteLayout {
+    /// The regular reading column
+    #[default]
+    Default,
+    /// Full page width, for wide tables and diagrams
+    Wide,
+    /// Larger type with `---`-separated sections spaced a screen apart
+    Slides,
+}
+
+impl NoteLayout {
+    /// CSS class applied to the note's container for this layout.
+    pub fn css_class(self) -> &'static str {
+        match self {
+            NoteLayout::Default => "layout-default",
+            NoteLayout::Wide => "layout-wide",
+            NoteLayout::Slides => "layout-slides",
+        }
+    }
+
+    /// Whether this is the default layout, for skipping it when writing frontmatter
+    pub fn is_default(&self) -> bool {
+        *self == NoteLayout::Default
+    }
+}
+
+/// Leniently deserializes a layout, treating unknown values as the default layout
+/// so a typo in the frontmatter doesn't cause the whole note to be rejected.
+fn deserialize_layout<'de, D>(deserializer: D) -> Result<NoteLayout, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(match value.as_deref().map(str::trim) {
+        Some("wide") => NoteLayout::Wide,
+        Some("slides") => NoteLayout::Slides,
+        _ => NoteLayout::Default,
+    })
+}
+
+/// A single heading in a note's table of contents.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TocEntry {
+    /// Heading level (1 for `<h1>` through 6 for `<h6>`)
+    pub level: u8,
+    /// Plain-text heading content
+    pub text: String,
+    /// Slug used as the heading's `id` attribute and link fragment
+    pub slug: String,
+}
+
+/// Converts heading text into a URL-friendly slug.
+///
+/// Text is lowercased, alphanumeric characters are kept, whitespace, `-` and `_`
+/// collapse into single dashes, and everything else is dropped. For example,
+/// `"Monads in Rust!"` becomes `"monads-in-rust"`.
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if (c.is_whitespace() || c == '-' || c == '_')
+            && !slug.is_empty()
+            && !slug.ends_with('-')
+        {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Normalizes a note's tags: trims whitespace, drops empty tags, and removes
+/// duplicates case-insensitively, keeping the first occurrence of each.
+///
+/// For example, `["rust", " Rust", "rust"]` becomes `["rust"]`.
+///
+/// # Arguments
+/// * `tags` - Tags as written in the note's frontmatter
+/// * `preserve_case` - Keep each tag's original casing instead of lowercasing it
+///
+/// # Returns
+/// * `Vec<String>` - The normalized tags in first-seen order
+pub fn normalize_tags(tags: &[String], preserve_case: bool) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    tags.iter()
+        .map(|tag| tag.trim())
+        .filter(|tag| !tag.is_empty() && seen.insert(tag.to_lowercase()))
+        .map(|tag| {
+            if preserve_case {
+                tag.to_string()
+            } else {
+                tag.to_lowercase()
+            }
+        })
+        .collect()
+}
+
+/// Converts rendered HTML into plain text.
+///
+/// Tags are removed, block-level elements are separated by a space, common
+/// character references (`&amp;`, `&#39;`, ...) are decoded, and runs of
+/// whitespace collapse into single spaces.
+///
+/// # Arguments
+/// * `html` - Rendered HTML, such as [`Note::content`]
+///
+/// # Returns
+/// * `String` - The text content, or an empty string if there is none
+pub fn strip_tags(html: &str) -> String {
+    const BLOCK_TAGS: &[&str] = &[
+        "address",
+        "article",
+        "blockquote",
+        "br",
+        "dd",
+        "div",
+        "dl",
+        "dt",
+        "figcaption",
+        "figure",
+        "footer",
+        "h1",
+        "h2",
+        "h3",
+        "h4",
+        "h5",
+        "h6",
+        "header",
+        "hr",
+        "li",
+        "ol",
+        "p",
+        "pre",
+        "section",
+        "table",
+        "td",
+        "th",
+        "tr",
+        "ul",
+    ];
+
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&decode_entities(&rest[..start]));
+        let Some(end) = rest[start..].find('>') else {
+            // An unterminated tag swallows the rest of the input
+            rest = "";
+            break;
+        };
+        let name: String = rest[start + 1..start + end]
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if BLOCK_TAGS.contains(&name.as_str()) {
+            text.push(' ');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(&decode_entities(rest));
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Decodes the named character references emitted by the markdown renderer
+/// and syntax highlighter, plus numeric references.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..].find(';').map(|end| &rest[1..end + 1]);
+        let c = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        });
+        match (entity, c) {
+            (Some(entity), Some(c)) => {
+                decoded.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Shortens text to at most `max_chars` characters, cutting at a word
+/// boundary and appending `…` when anything was removed.
+///
+/// # Arguments
+/// * `text` - Plain text to shorten
+/// * `max_chars` - Maximum number of characters to keep, excluding the ellipsis
+///
+/// # Returns
+/// * `String` - The text unchanged if it fits, otherwise its leading words
+pub fn truncate_words(text: &str, max_chars: usize) -> String {
+    let Some((cut, _)) = text.char_indices().nth(max_chars) else {
+        return text.to_string();
+    };
+    // Back up to the last word boundary, unless the first word alone is too long
+    let kept = match text[..cut].rfind(char::is_whitespace) {
+        Some(space) if !text[cut..].starts_with(char::is_whitespace) => &text[..space],
+        _ => &text[..cut],
+    };
+    format!("{}…", kept.trim_end())
+}
+
+/// Chooses the title of a note.
+///
+/// In order of preference: the frontmatter `title`, the text of the note's
+/// first level-one heading, and the note's ID made readable by [`humanize_id`].
+/// Titles that are empty or only whitespace are skipped.
+///
+/// # Arguments
+/// * `frontmatter_title` - The `title` from the frontmatter, empty if missing
+/// * `first_heading` - Plain text of the note's first level-one heading, if any
+/// * `id` - ID of the note
+///
+/// # Returns
+/// * `String` - The title to display for the note
+pub fn note_title(frontmatter_title: &str, first_heading: Option<&str>, id: &str) -> String {
+    [frontmatter_title, first_heading.unwrap_or_default()]
+        .into_iter()
+        .map(str::trim)
+        .find(|title| !title.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| humanize_id(id))
+}
+
+/// Turns a note ID into a readable title.
+///
+/// Takes the last segment of the ID, treats dashes and underscores as spaces,
+/// and capitalizes the first letter, so `projects/my-first_note` becomes
+/// `My first note`.
+///
+/// # Arguments
+/// * `id` - ID of the note
+///
+/// # Returns
+/// * `String` - The humanized name
+pub fn humanize_id(id: &str) -> String {
+    let name = id.rsplit('/').next().unwrap_or(id).replace(['-', '_'], " ");
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
 }
 
 /// Represents a file or attachment associated with a note.
@@ -61,351 +511,5516 @@ pub struct Asset {
     pub mime_type: String,
 }
 
+impl Asset {
+    /// Returns the URL the asset is served from by the API.
+    ///
+    /// # Arguments
+    /// * `api_prefix` - Path the note's collection is served under (e.g. `/api`)
+    /// * `note_id` - ID of the note the asset belongs to
+    pub fn url(&self, api_prefix: &str, note_id: &str) -> String {
+        format!(
+            "{}/notes/{}/assets/{}",
+            api_prefix,
+            encode_path_segment(note_id),
+            encode_path_segment(&self.name)
+        )
+    }
+
+    /// Returns the URL of a downscaled copy of the asset, for image assets.
+    ///
+    /// # Arguments
+    /// * `api_prefix` - Path the note's collection is served under
+    /// * `note_id` - ID of the note the asset belongs to
+    /// * `width` - Width of the thumbnail in pixels
+    pub fn thumbnail_url(&self, api_prefix: &str, note_id: &str, width: u32) -> String {
+        format!("{}/thumbnail?w={}", self.url(api_prefix, note_id), width)
+    }
+
+    /// Returns whether the asset is an image, judging by its MIME type
+    pub fn is_image(&self) -> bool {
+        self.mime_type.starts_with("image/")
+    }
+}
+
 /// Lightweight version of Note containing only metadata.
 ///
 /// Used for operations where the full note content isn't needed,
 /// such as listing notes or displaying previews.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct NoteMetadata {
     pub id: String,
     pub title: String,
+    /// Plain-text preview of the content; empty if the note hasn't been rendered
+    #[serde(default)]
+    pub excerpt: String,
     pub tags: Vec<String>,
     pub references: Vec<String>,
+    pub created: Option<DateTime<Utc>>,
+    pub modified: Option<DateTime<Utc>>,
 }
 
-/// Cached version of a processed note along with its metadata
-#[cfg(feature = "ssr")]
-struct CachedNote {
-    /// The processed note
-    note: Note,
-    /// Last modified time of the source file when this cache entry was created
-    last_modified: SystemTime,
+/// One page of notes listed by cursor, see [`NoteStore::list_notes_paged`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct NoteListPage {
+    /// Metadata of the notes on this page, sorted by ID
+    pub notes: Vec<NoteMetadata>,
+    /// Cursor of the next page; None on the last page
+    pub next_cursor: Option<String>,
 }
 
-/// Manages the storage, processing, and caching of notes.
+/// Request body for creating a new note.
 ///
-/// The NoteStore handles all file operations and content processing,
-/// including:
-/// - File reading and writing
-/// - YAML frontmatter parsing
-/// - Markdown rendering
-/// - Syntax highlighting
-/// - LaTeX math rendering
-/// - Asset management
-/// - Caching of processed notes
-#[cfg(feature = "ssr")]
-pub struct NoteStore {
-    /// Root directory where notes are stored
-    root_path: PathBuf,
-    /// Collection of syntax definitions for code highlighting
-    syntax_set: SyntaxSet,
-    /// Collection of color themes for syntax highlighting
-    theme_set: ThemeSet,
-    /// Cache of processed notes, protected by a read-write lock
-    note_cache: RwLock<HashMap<String, CachedNote>>,
+/// The frontmatter fields are written as YAML ahead of the markdown `body`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateNoteRequest {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub references: Vec<String>,
+    /// Other IDs the note can be requested by
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Whether `$...$` and `$$...$$` are rendered as math
+    #[serde(default = "default_math")]
+    pub math: bool,
+    /// Whether the note is a draft, left out of listings
+    #[serde(default)]
+    pub draft: bool,
+    /// Page layout the note is displayed with
+    #[serde(default)]
+    pub layout: NoteLayout,
+    /// Markdown body of the note (without frontmatter)
+    #[serde(default)]
+    pub body: String,
 }
 
-#[cfg(feature = "ssr")]
-impl NoteStore {
-    /// Creates a new NoteStore at the specified path.
-    ///
-    /// # Arguments
-    /// * `path` - Directory path where notes will be stored
-    ///
-    /// # Returns
-    /// * `Result<Self, std::io::Error>` - New NoteStore instance or IO error
-    pub fn new<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
-        let root_path = path.as_ref().to_path_buf();
-        // Create the notes directory if it doesn't exist
-        std::fs::create_dir_all(&root_path)?;
+/// Request body for replacing an existing note.
+///
+/// The note ID is taken from the request path rather than the body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateNoteRequest {
+    pub title: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub references: Vec<String>,
+    /// Other IDs the note can be requested by
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Whether `$...$` and `$$...$$` are rendered as math
+    #[serde(default = "default_math")]
+    pub math: bool,
+    /// Whether the note is a draft, left out of listings
+    #[serde(default)]
+    pub draft: bool,
+    /// Page layout the note is displayed with
+    #[serde(default)]
+    pub layout: NoteLayout,
+    /// Markdown body of the note (without frontmatter)
+    #[serde(default)]
+    pub body: String,
+}
 
-        Ok(Self {
-            root_path,
-            // Load default syntax highlighting definitions
-            syntax_set: SyntaxSet::load_defaults_newlines(),
-            // Load default color themes
-            theme_set: ThemeSet::load_defaults(),
-            // Initialize empty cache
-            note_cache: RwLock::new(HashMap::new()),
-        })
-    }
+/// Request body for renaming a note.
+///
+/// The current note ID is taken from the request path.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameNoteRequest {
+    /// ID to rename the note to
+    pub id: String,
+}
 
-    /// Gets the last modified time for a file
+/// Request body for creating a note from a template.
+///
+/// The template is named by the request's `template` query parameter.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateNoteRequest {
+    /// ID of the new note, filled in for the template's `{{id}}`
+    pub id: String,
+    /// Values for the template's other `{{name}}` placeholders
+    #[serde(default)]
+    pub vars: std::collections::HashMap<String, String>,
+}
+
+/// JSON body sent with API error responses, tagged by an `error` code.
+///
+/// For example, a missing note is reported as
+/// `{"error": "note_not_found", "id": "..."}`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum ApiErrorBody {
+    /// No note exists with the requested ID
+    NoteNotFound { id: String },
+}
+
+/// A reference from one note to a note ID that doesn't exist.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BrokenRef {
+    /// ID of the note containing the reference
+    pub from: String,
+    /// The referenced ID that couldn't be resolved
+    pub to: String,
+}
+
+/// A place where a note mentions another note's title without linking to it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UnlinkedMention {
+    /// ID of the note containing the mention
+    pub note_id: String,
+    /// The text around the mention, with whitespace collapsed
+    pub snippet: String,
+}
+
+/// A note file that couldn't be parsed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ParseFailure {
+    /// ID of the note, derived from its path
+    pub id: String,
+    /// Why the note couldn't be parsed
+    pub error: String,
+}
+
+/// An alias that can't be resolved, because more than one note claims it or
+/// because it's also the ID of a note.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AliasConflict {
+    /// The contested alias
+    pub alias: String,
+    /// IDs of the notes involved, starting with the note whose ID is the alias, if any
+    pub ids: Vec<String>,
+}
+
+/// A note ID claimed by more than one file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DuplicateId {
+    /// The contested ID
+    pub id: String,
+    /// Paths of the files claiming the ID, relative to the notes directory
+    pub files: Vec<String>,
+}
+
+/// A previous version of a note, saved when the note was updated.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NoteVersion {
+    /// Name of the version's file in the note's `.history` directory, without extension
+    pub version: String,
+    /// When the version was replaced by an update
+    pub saved: DateTime<Utc>,
+    /// Size of the saved file in bytes
+    pub size: u64,
+}
+
+/// Counters describing how well the note cache is working.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of notes currently cached
+    pub entries: usize,
+    /// Lookups served from the cache since startup
+    pub hits: u64,
+    /// Lookups that had to read and parse the note file since startup
+    pub misses: u64,
+}
+
+/// Aggregate numbers describing the published notes.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct CorpusStats {
+    /// Number of published notes
+    pub total_notes: usize,
+    /// Number of distinct tags, compared case-insensitively
+    pub total_tags: usize,
+    /// Number of references listed by the notes, including wikilinks
+    pub total_references: usize,
+    /// Number of notes that neither reference another note nor are referenced
+    pub orphan_notes: usize,
+    /// Mean word count of the notes, 0 if there are none
+    pub average_word_count: f64,
+}
+
+/// A note in the reference graph.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GraphNode {
+    pub id: String,
+    pub title: String,
+}
+
+/// A reference from the `source` note to the `target` note.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+}
+
+/// A tag and the IDs of the published notes carrying it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TagIndexEntry {
+    pub tag: String,
+    /// IDs of the notes with the tag, sorted
+    pub note_ids: Vec<String>,
+}
+
+/// A tag in the tag hierarchy, where `/` separates a tag from its parent
+/// (e.g. `project/miniref` is nested under `project`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TagNode {
+    /// Last segment of the tag (e.g. `miniref`); empty for the root
+    pub name: String,
+    /// Full tag (e.g. `project/miniref`); empty for the root
+    pub path: String,
+    /// Number of notes with this tag or a tag nested under it
+    pub count: usize,
+    /// Tags nested directly under this one, ordered by name
+    pub children: Vec<TagNode>,
+}
+
+/// The network of notes and the references between them.
+///
+/// Edges only connect notes that exist; broken references are omitted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ReferenceGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Order in which notes are listed.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteSort {
+    /// Alphabetically by note ID
+    #[default]
+    Id,
+    /// Alphabetically by title, ignoring case
+    Title,
+    /// Most recently modified first; notes without a timestamp come last
+    Modified,
+}
+
+impl NoteSort {
+    /// Sorts notes in place in this order.
     ///
     /// # Arguments
-    /// * `path` - Path to the file to check
-    ///
-    /// # Returns
-    /// * `Result<SystemTime, std::io::Error>` - Last modified time or error
-    fn get_file_modified_time(path: &Path) -> std::io::Result<SystemTime> {
-        path.metadata()?.modified()
+    /// * `notes` - The notes to sort
+    pub fn sort_notes(self, notes: &mut [Note]) {
+        match self {
+            NoteSort::Id => notes.sort_by(|a, b| a.id.cmp(&b.id)),
+            NoteSort::Title => notes.sort_by(|a, b| {
+                a.title
+                    .to_lowercase()
+                    .cmp(&b.title.to_lowercase())
+                    .then_with(|| a.id.cmp(&b.id))
+            }),
+            // Option orders None first, so sort in reverse to put recent notes first
+            NoteSort::Modified => notes.sort_by_key(|note| std::cmp::Reverse(note.modified)),
+        }
     }
 
-    /// Checks if a cached note is still valid by comparing timestamps
+    /// Sorts note metadata in place in this order, like [`NoteSort::sort_notes`].
     ///
     /// # Arguments
-    /// * `id` - ID of the note to check
-    /// * `cached` - The cached note entry to validate
-    ///
-    /// # Returns
-    /// * `Result<bool, std::io::Error>` - Whether the cache is still valid
-    fn is_cache_valid(&self, id: &str, cached: &CachedNote) -> std::io::Result<bool> {
-        let path = self.root_path.join(format!("{}.md", id));
-        let current_modified = Self::get_file_modified_time(&path)?;
-        Ok(cached.last_modified >= current_modified)
+    /// * `notes` - The note metadata to sort
+    pub fn sort_metadata(self, notes: &mut [NoteMetadata]) {
+        match self {
+            NoteSort::Id => notes.sort_by(|a, b| a.id.cmp(&b.id)),
+            NoteSort::Title => notes.sort_by(|a, b| {
+                a.title
+                    .to_lowercase()
+                    .cmp(&b.title.to_lowercase())
+                    .then_with(|| a.id.cmp(&b.id))
+            }),
+            NoteSort::Modified => notes.sort_by_key(|note| std::cmp::Reverse(note.modified)),
+        }
     }
+}
 
-    /// Lists all notes in the store, using cache when possible.
-    ///
-    /// Scans the root directory for markdown files and returns a list of all valid notes.
-    /// Uses cached versions of notes when available and still valid.
-    ///
-    /// # Returns
-    /// * `Result<Vec<Note>, std::io::Error>` - List of notes or IO error
-    pub fn list_notes(&self) -> std::io::Result<Vec<Note>> {
-        let mut notes = Vec::new();
-        let mut cache = self.note_cache.write();
-
-        // Iterate through all files in the notes directory
-        for entry in std::fs::read_dir(&self.root_path)? {
-            let entry = entry?;
-            // Only process markdown files
-            if entry.path().extension().is_some_and(|ext| ext == "md") {
-                let file_name = entry.file_name();
-                let id = file_name
-                    .to_str()
-                    .and_then(|s| s.strip_suffix(".md"))
-                    .unwrap_or_default()
-                    .to_string();
-
-                // Check if we have a valid cached version
-                if let Some(cached) = cache.get(&id) {
-                    if self.is_cache_valid(&id, cached)? {
-                        notes.push(cached.note.clone());
-                        continue;
-                    }
-                }
+/// A page of notes together with the total number of notes available.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct NoteList {
+    /// The notes on this page
+    pub notes: Vec<NoteMetadata>,
+    /// Total number of notes across all pages
+    pub total: usize,
+}
 
-                // No valid cache, need to process the note
-                if let Ok(content) = std::fs::read_to_string(entry.path()) {
-                    if let Some(note) = self.parse_note(&content, Some(&entry.path())) {
-                        // Update cache with the new processed note
-                        if let Ok(modified) = Self::get_file_modified_time(&entry.path()) {
-                            cache.insert(
-                                id,
-                                CachedNote {
-                                    note: note.clone(),
-                                    last_modified: modified,
-                                },
-                            );
-                        }
-                        notes.push(note);
-                    }
-                }
-            }
+impl From<&Note> for NoteMetadata {
+    fn from(note: &Note) -> Self {
+        Self {
+            id: note.id.clone(),
+            title: note.title.clone(),
+            excerpt: note.excerpt.clone(),
+            tags: note.tags.clone(),
+            references: note.references.clone(),
+            created: note.created,
+            modified: note.modified,
         }
-        Ok(notes)
     }
+}
 
-    /// Retrieves a specific note by ID, using cache when possible.
+/// Title prefix of the links standing in for embedded notes until they're inlined
+#[cfg(feature = "ssr")]
+const EMBED_TITLE_PREFIX: &str = "miniref-embed-";
+
+/// Pattern matching `[[target]]` and `[[target|Display Text]]` wikilinks
+#[cfg(feature = "ssr")]
+const WIKILINK_PATTERN: &str = r"\[\[([^\[\]|]+?)(?:\|([^\[\]]+?))?\]\]";
+
+/// Frontmatter fields written to disk when saving a note
+#[cfg(feature = "ssr")]
+#[derive(Serialize)]
+struct Frontmatter<'a> {
+    id: &'a str,
+    title: &'a str,
+    tags: &'a [String],
+    references: &'a [String],
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    aliases: &'a [String],
+    /// Only written when math is turned off, since it's on by default
+    #[serde(skip_serializing_if = "is_true")]
+    math: bool,
+    /// Only written for drafts
+    #[serde(skip_serializing_if = "is_false")]
+    draft: bool,
+    #[serde(skip_serializing_if = "NoteLayout::is_default")]
+    layout: NoteLayout,
+    /// Only written when set, so notes saved again keep their timestamps
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modified: Option<DateTime<Utc>>,
+}
+
+/// Whether a flag is set, for skipping frontmatter fields left at a `true` default
+#[cfg(feature = "ssr")]
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+/// Whether a flag is unset, for skipping frontmatter fields left at a `false` default
+#[cfg(feature = "ssr")]
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Reasons a note file can't be turned into a [`Note`].
+///
+/// Returned by [`NoteStore::get_note`] and [`NoteStore::get_note_async`] as the
+/// inner error of an [`std::io::Error`] with kind `InvalidData`.
+#[cfg(feature = "ssr")]
+#[derive(Debug, thiserror::Error)]
+pub enum ParseError {
+    /// The file doesn't start with a frontmatter block
+    #[error("missing frontmatter")]
+    MissingFrontmatter,
+    /// The frontmatter can't be parsed
+    #[error("invalid frontmatter: {0}")]
+    InvalidFrontmatter(String),
+    /// A required field is missing or empty
+    #[error("missing required field `{0}`")]
+    MissingField(&'static str),
+    /// The markdown body couldn't be rendered
+    #[error("markdown rendering failed: {0}")]
+    Markdown(String),
+    /// Code blocks couldn't be highlighted
+    #[error("syntax highlighting failed: {0}")]
+    Highlight(String),
+    /// KaTeX couldn't be configured to render math
+    #[error("KaTeX setup failed: {0}")]
+    Katex(String),
+}
+
+#[cfg(feature = "ssr")]
+impl From<ParseError> for std::io::Error {
+    fn from(error: ParseError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// Reverse reference index: target ID -> notes referencing it
+#[cfg(feature = "ssr")]
+type BacklinkIndex = HashMap<String, Vec<NoteMetadata>>;
+
+/// Other names notes can be requested by: aliases declared in note frontmatter,
+/// resolved to the notes claiming them, and note IDs in a different case
+#[cfg(feature = "ssr")]
+#[derive(Default)]
+struct AliasIndex {
+    /// Alias -> ID of the note claiming it, for aliases that resolve unambiguously
+    targets: HashMap<String, String>,
+    /// Aliases that don't resolve, ordered by alias
+    conflicts: Vec<AliasConflict>,
+    /// Lowercased note ID -> note IDs with that spelling
+    folded_ids: HashMap<String, Vec<String>>,
+}
+
+#[cfg(feature = "ssr")]
+impl AliasIndex {
+    /// Resolves a name that isn't a note's exact ID, trying aliases first and
+    /// then a case-insensitive match against note IDs.
     ///
     /// # Arguments
-    /// * `id` - The unique identifier of the note to retrieve
+    /// * `name` - Alias or differently cased note ID
     ///
     /// # Returns
-    /// * `Result<Option<Note>, std::io::Error>` - The note if found, None if not found, or IO error
-    pub fn get_note(&self, id: &str) -> std::io::Result<Option<Note>> {
-        let path = self.root_path.join(format!("{}.md", id));
-        if !path.exists() {
-            return Ok(None);
+    /// * `Option<&str>` - The canonical note ID, or None if nothing matches or
+    ///   the name matches several IDs that differ only in case
+    fn resolve(&self, name: &str) -> Option<&str> {
+        if let Some(target) = self.targets.get(name) {
+            return Some(target);
+        }
+        match self.folded_ids.get(&name.to_lowercase())?.as_slice() {
+            [id] if id != name => Some(id),
+            _ => None,
         }
+    }
+}
 
-        // Check cache first
-        {
-            let cache = self.note_cache.read();
-            if let Some(cached) = cache.get(id) {
-                if self.is_cache_valid(id, cached)? {
-                    return Ok(Some(cached.note.clone()));
-                }
-            }
+/// Cached version of a processed note along with its metadata
+#[cfg(feature = "ssr")]
+struct CachedNote {
+    /// The processed note
+    note: Note,
+    /// Raw markdown body (without frontmatter), kept for full-text search
+    body: String,
+    /// Last modified time of the source file when this cache entry was created,
+    /// None if the backend can't tell
+    last_modified: Option<SystemTime>,
+    /// IDs of the notes embedded in the content, including nested embeds
+    embeds: Vec<String>,
+    /// When this cache entry was created, to notice embedded notes changing
+    cached_at: SystemTime,
+    /// Logical timestamp of the most recent access, used for LRU eviction
+    last_used: AtomicU64,
+}
+
+#[cfg(feature = "ssr")]
+impl CachedNote {
+    fn new(note: Note, body: String, last_modified: Option<SystemTime>) -> Self {
+        let embeds = embedded_ids(&note.content);
+        Self {
+            note,
+            body,
+            last_modified,
+            embeds,
+            cached_at: SystemTime::now(),
+            last_used: AtomicU64::new(0),
         }
+    }
 
-        // No valid cache, need to process the note
-        let content = std::fs::read_to_string(&path)?;
-        if let Some(note) = self.parse_note(&content, Some(&path)) {
-            // Update cache with the new processed note
-            if let Ok(modified) = Self::get_file_modified_time(&path) {
-                let mut cache = self.note_cache.write();
-                cache.insert(
-                    id.to_string(),
-                    CachedNote {
-                        note: note.clone(),
-                        last_modified: modified,
-                    },
-                );
+    /// Whether the note is unchanged since this entry was cached, given its
+    /// current modification time; never when either time is unknown
+    fn is_fresh(&self, modified: Option<SystemTime>) -> bool {
+        matches!((self.last_modified, modified), (Some(cached), Some(current)) if cached >= current)
+    }
+}
+
+/// Bounded cache of processed notes with least-recently-used eviction.
+///
+/// Recency is tracked with a logical clock: every access stamps the entry with
+/// a new tick and appends `(id, tick)` to an access log. Eviction pops the log
+/// from the front, skipping records whose tick no longer matches the entry
+/// (i.e. the entry was accessed again later). Both operations are O(1)
+/// amortized, and lookups only need shared access so they work under the
+/// store's read lock.
+#[cfg(feature = "ssr")]
+struct NoteCache {
+    /// Cached notes keyed by note ID
+    entries: HashMap<String, CachedNote>,
+    /// Access log in order of use, possibly containing stale records
+    order: Mutex<VecDeque<(String, u64)>>,
+    /// Logical clock used to stamp accesses
+    clock: AtomicU64,
+    /// Maximum number of entries, or None for an unbounded cache
+    capacity: Option<usize>,
+}
+
+#[cfg(feature = "ssr")]
+impl NoteCache {
+    fn new(capacity: Option<usize>) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: Mutex::new(VecDeque::new()),
+            clock: AtomicU64::new(0),
+            capacity,
+        }
+    }
+
+    /// Looks up an entry and marks it as most recently used
+    fn get(&self, id: &str) -> Option<&CachedNote> {
+        let entry = self.entries.get(id)?;
+        self.touch(id, entry);
+        Some(entry)
+    }
+
+    /// Looks up an entry without affecting its recency
+    fn peek(&self, id: &str) -> Option<&CachedNote> {
+        self.entries.get(id)
+    }
+
+    /// Inserts an entry as most recently used, evicting old entries if over capacity
+    fn insert(&mut self, id: String, entry: CachedNote) {
+        self.entries.insert(id.clone(), entry);
+        if let Some(entry) = self.entries.get(&id) {
+            self.touch(&id, entry);
+        }
+        self.evict();
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn remove(&mut self, id: &str) {
+        // Any access log records for the ID become stale and are skipped later
+        self.entries.remove(id);
+        // Notes embedding the note would keep showing its old content
+        self.entries
+            .retain(|_, entry| !entry.embeds.iter().any(|embed| embed == id));
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.get_mut().clear();
+    }
+
+    fn set_capacity(&mut self, capacity: Option<usize>) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    /// Stamps an entry with a new tick and records the access
+    fn touch(&self, id: &str, entry: &CachedNote) {
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+        entry.last_used.store(tick, Ordering::Relaxed);
+
+        let mut order = self.order.lock();
+        order.push_back((id.to_string(), tick));
+
+        // Drop stale records so the log stays proportional to the number of entries
+        if order.len() > 2 * self.entries.len() + 16 {
+            order.retain(|(id, tick)| self.is_current(id, *tick));
+        }
+    }
+
+    /// Whether an access log record refers to the latest access of a cached entry
+    fn is_current(&self, id: &str, tick: u64) -> bool {
+        self.entries
+            .get(id)
+            .is_some_and(|entry| entry.last_used.load(Ordering::Relaxed) == tick)
+    }
+
+    /// Removes least recently used entries until the cache is within capacity
+    fn evict(&mut self) {
+        let Some(capacity) = self.capacity else {
+            return;
+        };
+        while self.entries.len() > capacity {
+            let Some((id, tick)) = self.order.get_mut().pop_front() else {
+                break;
+            };
+            if self.is_current(&id, tick) {
+                self.entries.remove(&id);
             }
-            Ok(Some(note))
-        } else {
-            Ok(None)
         }
     }
+}
 
-    /// Clears the entire note cache
+/// Markdown constructs enabled when rendering notes.
+///
+/// The default matches GitHub-flavored markdown: every extension is on, and
+/// HTML written in a note is escaped rather than passed through to the page.
+/// Task lists are always on.
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkdownConfig {
+    /// Pipe tables
+    pub tables: bool,
+    /// `~~strikethrough~~` text
+    pub strikethrough: bool,
+    /// Bare URLs and email addresses turned into links
+    pub autolinks: bool,
+    /// `[^label]` footnote references and definitions
+    pub footnotes: bool,
+    /// Whether HTML in notes is rendered as-is instead of escaped. Only enable
+    /// this for trusted notes, since it lets a note inject scripts into the page
+    pub raw_html: bool,
+}
+
+#[cfg(feature = "ssr")]
+impl Default for MarkdownConfig {
+    fn default() -> Self {
+        Self {
+            tables: true,
+            strikethrough: true,
+            autolinks: true,
+            footnotes: true,
+            raw_html: false,
+        }
+    }
+}
+
+/// Delimiters that mark LaTeX math in notes.
+///
+/// Dollars are on by default. Brackets are off, since `\[` and `\(` are also
+/// how markdown escapes a literal bracket or parenthesis.
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MathDelimiters {
+    /// `$...$` for inline and `$$...$$` for display math
+    pub dollars: bool,
+    /// `\(...\)` for inline and `\[...\]` for display math
+    pub brackets: bool,
+}
+
+#[cfg(feature = "ssr")]
+impl Default for MathDelimiters {
+    fn default() -> Self {
+        Self {
+            dollars: true,
+            brackets: false,
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl MarkdownConfig {
+    /// Builds the options the markdown renderer is called with.
     ///
-    /// This forces all subsequent note requests to reprocess the source files.
-    pub fn clear_cache(&self) {
-        let mut cache = self.note_cache.write();
-        cache.clear();
+    /// # Returns
+    /// * `MarkdownOptions` - GFM options with the disabled constructs turned off
+    fn options(&self) -> MarkdownOptions {
+        let mut options = MarkdownOptions::gfm();
+        let constructs = &mut options.parse.constructs;
+        constructs.gfm_table = self.tables;
+        constructs.gfm_strikethrough = self.strikethrough;
+        constructs.gfm_autolink_literal = self.autolinks;
+        constructs.gfm_footnote_definition = self.footnotes;
+        constructs.gfm_label_start_footnote = self.footnotes;
+        options.compile.allow_dangerous_html = self.raw_html;
+        options
     }
+}
 
-    /// Removes a specific note from the cache
+/// Periodic cache refresh started by [`NoteStore::start_refresh`].
+///
+/// The refresh task is stopped when this handle is dropped, or when the store
+/// it refreshes is dropped.
+#[cfg(feature = "ssr")]
+pub struct CacheRefresh {
+    task: tokio::task::JoinHandle<()>,
+}
+
+#[cfg(feature = "ssr")]
+impl CacheRefresh {
+    /// Stops the refresh task; equivalent to dropping the handle
+    pub fn stop(self) {}
+}
+
+#[cfg(feature = "ssr")]
+impl Drop for CacheRefresh {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Storage that notes are read from and written to, keyed by note ID.
+///
+/// IDs are `/`-separated paths without extension (e.g. `projects/foo`), and
+/// each backend decides which IDs are valid: an invalid ID has no note, and
+/// writing to one fails with `ErrorKind::InvalidInput`. Content is stored as
+/// written, frontmatter included; parsing, rendering, and caching are left to
+/// [`NoteStore`].
+#[cfg(feature = "ssr")]
+pub trait NoteBackend: Send + Sync + 'static {
+    /// Lists the IDs of all stored notes, in no particular order.
+    ///
+    /// # Returns
+    /// * `Result<Vec<String>, std::io::Error>` - Note IDs, or IO error
+    fn list(&self) -> std::io::Result<Vec<String>>;
+
+    /// Reads a note's content, frontmatter included.
     ///
     /// # Arguments
-    /// * `id` - ID of the note to remove from cache
-    pub fn invalidate_cache(&self, id: &str) {
-        let mut cache = self.note_cache.write();
-        cache.remove(id);
+    /// * `id` - ID of the note to read
+    ///
+    /// # Returns
+    /// * `Result<Option<String>, std::io::Error>` - The content if the note
+    ///   exists, None if not, or IO error
+    fn read(&self, id: &str) -> std::io::Result<Option<String>>;
+
+    /// Writes a note's content, replacing the note if it exists.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note to write
+    /// * `content` - Content including frontmatter
+    /// * `create_new` - Whether to fail instead of replacing an existing note
+    ///
+    /// # Returns
+    /// * `Result<(), std::io::Error>` - Success, or an IO error. Fails with
+    ///   `ErrorKind::AlreadyExists` if `create_new` is set and the note exists,
+    ///   and `ErrorKind::InvalidInput` if the ID is invalid.
+    fn write(&self, id: &str, content: &str, create_new: bool) -> std::io::Result<()>;
+
+    /// Deletes a note.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note to delete
+    ///
+    /// # Returns
+    /// * `Result<(), std::io::Error>` - Success, or an IO error. Fails with
+    ///   `ErrorKind::NotFound` if no note with this ID exists.
+    fn delete(&self, id: &str) -> std::io::Result<()>;
+
+    /// Returns when a note was last written.
+    ///
+    /// Cached notes are re-read once this moves past the time they were read at.
+    /// A backend that can't tell when an existing note was written should fail
+    /// with `ErrorKind::Unsupported`; the store then reads the note anyway and
+    /// never trusts its cache entry.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note
+    ///
+    /// # Returns
+    /// * `Result<Option<SystemTime>, std::io::Error>` - The modification time
+    ///   if the note exists, None if not, or IO error
+    fn modified_time(&self, id: &str) -> std::io::Result<Option<SystemTime>>;
+
+    /// Moves a note to another ID.
+    ///
+    /// The default writes the content under `new_id` and then deletes `old_id`;
+    /// backends that can move notes in place should override it.
+    ///
+    /// # Arguments
+    /// * `old_id` - Current ID of the note
+    /// * `new_id` - ID to move the note to
+    ///
+    /// # Returns
+    /// * `Result<(), std::io::Error>` - Success, or an IO error. Fails with
+    ///   `ErrorKind::NotFound` if no note with `old_id` exists,
+    ///   `ErrorKind::InvalidInput` if `new_id` is invalid, and
+    ///   `ErrorKind::AlreadyExists` if it's taken.
+    fn rename(&self, old_id: &str, new_id: &str) -> std::io::Result<()> {
+        let content = self.read(old_id)?.ok_or_else(|| note_not_found(old_id))?;
+        self.write(new_id, &content, true)?;
+        self.delete(old_id)
     }
 
-    /// Parses and processes a note's raw content into a structured Note object.
+    /// Returns the file a note is stored in, if the backend keeps notes as files.
     ///
-    /// This function handles:
-    /// 1. YAML frontmatter extraction
-    /// 2. Markdown to HTML conversion
-    /// 3. Syntax highlighting for code blocks
-    /// 4. LaTeX math rendering
-    /// 5. Asset scanning
+    /// Assets, saved versions, and file timestamps live next to this file, so
+    /// notes without one have none of them. Defaults to None.
     ///
     /// # Arguments
-    /// * `content` - Raw note content including frontmatter
-    /// * `note_path` - Optional filesystem path to the note (for asset scanning)
+    /// * `id` - ID of the note
     ///
     /// # Returns
-    /// * `Option<Note>` - Parsed and processed note, or None if parsing fails
-    fn parse_note(&self, content: &str, note_path: Option<&Path>) -> Option<Note> {
-        // Parse YAML frontmatter and content
-        let matter = Matter::<YAML>::new();
-        let parsed = matter.parse_with_struct::<Note>(content)?;
-        let theme = &self.theme_set.themes["base16-ocean.dark"];
+    /// * `Option<PathBuf>` - Path of the note's file, or None if the ID is
+    ///   invalid or notes aren't stored as files
+    fn path(&self, _id: &str) -> Option<PathBuf> {
+        None
+    }
 
-        // Convert Markdown to HTML with GitHub-flavored Markdown options
-        let options = MarkdownOptions::gfm();
-        let html_output = match to_html_with_options(&parsed.content, &options) {
-            Ok(html) => html,
-            Err(_) => return None,
+    /// Returns whether notes are kept as files, with their assets and saved
+    /// versions in directories next to them.
+    ///
+    /// Saving versions, listing them, and serving assets and thumbnails need
+    /// this; without it, notes have no assets and keeping history is refused.
+    /// Defaults to false.
+    fn stores_files(&self) -> bool {
+        false
+    }
+}
+
+/// Note storage in a directory of markdown files.
+///
+/// A note's ID is the path of its `.md` file relative to the root directory,
+/// and its assets and saved versions are kept in `.assets` and `.history`
+/// directories next to the file. Files matched by the root's `.minirefignore`
+/// aren't notes.
+#[cfg(feature = "ssr")]
+pub struct FileSystemBackend {
+    /// Root directory where notes are stored
+    root_path: PathBuf,
+    /// Patterns from the root's `.minirefignore` for files that aren't notes
+    ignore: Gitignore,
+}
+
+#[cfg(feature = "ssr")]
+impl FileSystemBackend {
+    /// Opens the notes directory at the specified path, creating it if needed.
+    ///
+    /// # Arguments
+    /// * `path` - Directory path where notes will be stored
+    ///
+    /// # Returns
+    /// * `Result<Self, std::io::Error>` - New backend, or an IO error if the
+    ///   directory can't be listed or its `.minirefignore` can't be read
+    pub fn new<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let root_path = path.as_ref().to_path_buf();
+        // Create the notes directory if it doesn't exist
+        std::fs::create_dir_all(&root_path)?;
+        // Fail early if the directory can't be listed
+        std::fs::read_dir(&root_path)?;
+        let ignore = load_ignore_file(&root_path)?;
+
+        Ok(Self { root_path, ignore })
+    }
+
+    /// Returns the root directory the notes are stored in
+    pub fn root_path(&self) -> &Path {
+        &self.root_path
+    }
+
+    /// Resolves a note ID to the path of its markdown file.
+    ///
+    /// IDs are paths relative to the root directory without the `.md` extension,
+    /// using `/` as separator (e.g. `projects/foo`). Any ID that is empty,
+    /// absolute, or contains `.`/`..` components is rejected to prevent path
+    /// traversal outside the notes directory. IDs of files matched by
+    /// `.minirefignore` are rejected too, so ignored files are never notes, as
+    /// are IDs of files inside a note's `.assets` or `.history` directory.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note to resolve
+    ///
+    /// # Returns
+    /// * `Option<PathBuf>` - Path to the note file, or None if the ID is invalid
+    fn note_path(&self, id: &str) -> Option<PathBuf> {
+        if !is_valid_note_id(id) {
+            return None;
+        }
+        let path = self.root_path.join(format!("{}.md", id));
+        (!self.is_ignored(&path, false)).then_some(path)
+    }
+
+    /// Checks whether a path inside the root directory is matched by `.minirefignore`.
+    ///
+    /// # Arguments
+    /// * `path` - Path of a file or directory inside the root directory
+    /// * `is_dir` - Whether the path is a directory
+    ///
+    /// # Returns
+    /// * `bool` - Whether the path, or one of its parent directories, is ignored
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.ignore
+            .matched_path_or_any_parents(path, is_dir)
+            .is_ignore()
+    }
+
+    /// Derives a note ID from the path of its markdown file.
+    ///
+    /// # Arguments
+    /// * `path` - Path to a markdown file inside the root directory
+    ///
+    /// # Returns
+    /// * `Option<String>` - The relative, `/`-separated ID without extension
+    fn note_id_for_path(&self, path: &Path) -> Option<String> {
+        note_id_relative_to(&self.root_path, path)
+    }
+
+    /// Recursively collects all markdown files under the root directory.
+    ///
+    /// Hidden directories (starting with `.`), note asset directories (ending
+    /// in `.assets`), and note history directories (ending in `.history`) are
+    /// not descended into.
+    ///
+    /// # Returns
+    /// * `Result<Vec<(String, PathBuf)>, std::io::Error>` - Note IDs and file paths, or IO error
+    fn note_files(&self) -> std::io::Result<Vec<(String, PathBuf)>> {
+        let mut files = Vec::new();
+        let mut pending = vec![self.root_path.clone()];
+
+        while let Some(dir) = pending.pop() {
+            for entry in std::fs::read_dir(&dir)? {
+                let entry = entry?;
+                let path = entry.path();
+
+                if entry.file_type()?.is_dir() {
+                    let name = entry.file_name();
+                    let name = name.to_string_lossy();
+                    if !name.starts_with('.')
+                        && !name.ends_with(".assets")
+                        && !name.ends_with(".history")
+                        && !self.is_ignored(&path, true)
+                    {
+                        pending.push(path);
+                    }
+                } else if path.extension().is_some_and(|ext| ext == "md")
+                    && !self.is_ignored(&path, false)
+                {
+                    if let Some(id) = self.note_id_for_path(&path) {
+                        files.push((id, path));
+                    }
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// Resolves the path of a note that must already exist on disk.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note to resolve
+    ///
+    /// # Returns
+    /// * `Result<PathBuf, std::io::Error>` - Path to the note file, or a
+    ///   `NotFound` error if the ID is invalid or the file doesn't exist
+    fn existing_note_path(&self, id: &str) -> std::io::Result<PathBuf> {
+        self.note_path(id)
+            .filter(|path| path.is_file())
+            .ok_or_else(|| note_not_found(id))
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl NoteBackend for FileSystemBackend {
+    fn list(&self) -> std::io::Result<Vec<String>> {
+        Ok(self.note_files()?.into_iter().map(|(id, _)| id).collect())
+    }
+
+    fn read(&self, id: &str) -> std::io::Result<Option<String>> {
+        let Some(path) = self.note_path(id) else {
+            return Ok(None);
         };
+        match std::fs::read_to_string(&path) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
 
-        // Regular expression for finding code blocks
-        let code_block_regex =
-            match Regex::new(r#"<pre><code class="language-([^"]+)">(.*?)</code></pre>"#) {
-                Ok(re) => re,
-                Err(_) => return None,
-            };
+    fn write(&self, id: &str, content: &str, create_new: bool) -> std::io::Result<()> {
+        let path = self.note_path(id).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid note id: {:?}", id),
+            )
+        })?;
+
+        // Nested IDs may need their parent directories created
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        if create_new {
+            // `create_new` fails atomically if the file already exists
+            let mut file = std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)?;
+            file.write_all(content.as_bytes())
+        } else {
+            std::fs::write(&path, content)
+        }
+    }
+
+    /// Also removes the note's `.assets` and `.history` directories, if present.
+    fn delete(&self, id: &str) -> std::io::Result<()> {
+        let path = self.existing_note_path(id)?;
+        std::fs::remove_file(&path)?;
+
+        for extension in ["assets", HISTORY_EXTENSION] {
+            let dir = path.with_extension(extension);
+            if dir.is_dir() {
+                std::fs::remove_dir_all(&dir)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn modified_time(&self, id: &str) -> std::io::Result<Option<SystemTime>> {
+        let Some(path) = self.note_path(id) else {
+            return Ok(None);
+        };
+        match path.metadata() {
+            // Some platforms and filesystems don't record modification times,
+            // which fails with `ErrorKind::Unsupported`
+            Ok(metadata) if metadata.is_file() => metadata.modified().map(Some),
+            Ok(_) => Ok(None),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Moves the note's file along with its `.assets` and `.history` directories.
+    fn rename(&self, old_id: &str, new_id: &str) -> std::io::Result<()> {
+        let old_path = self.existing_note_path(old_id)?;
+        let new_path = self.note_path(new_id).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid note id: {:?}", new_id),
+            )
+        })?;
+        let data_dirs = ["assets", HISTORY_EXTENSION];
+        if new_path.exists()
+            || data_dirs
+                .iter()
+                .any(|extension| new_path.with_extension(extension).exists())
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("note already exists: {}", new_id),
+            ));
+        }
+
+        // Nested IDs may need their parent directories created
+        if let Some(parent) = new_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(&old_path, &new_path)?;
+        for extension in data_dirs {
+            let dir = old_path.with_extension(extension);
+            if dir.is_dir() {
+                std::fs::rename(&dir, new_path.with_extension(extension))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn path(&self, id: &str) -> Option<PathBuf> {
+        self.note_path(id)
+    }
+
+    fn stores_files(&self) -> bool {
+        true
+    }
+}
+
+/// Note storage in memory, for tests and stores built on the fly.
+///
+/// Notes are held as raw content keyed by ID and accept the same IDs as
+/// [`FileSystemBackend`], minus `.minirefignore`. Each note's modification
+/// time is when it was last written, or when the backend was created for the
+/// notes it started with. Notes have no files, so no assets or saved versions:
+/// [`NoteStore::with_history`] is ignored and exports hold only the notes.
+#[cfg(feature = "ssr")]
+pub struct MemoryBackend {
+    /// Note ID -> (raw content, modification time)
+    notes: RwLock<HashMap<String, (String, SystemTime)>>,
+}
+
+#[cfg(feature = "ssr")]
+impl MemoryBackend {
+    /// Creates a backend holding the given notes.
+    ///
+    /// Entries with an invalid ID are logged and left out.
+    ///
+    /// # Arguments
+    /// * `notes` - Raw note content, frontmatter included, keyed by note ID
+    ///
+    /// # Returns
+    /// * `Self` - New backend instance
+    pub fn new(notes: HashMap<String, String>) -> Self {
+        let now = SystemTime::now();
+        let notes = notes
+            .into_iter()
+            .filter(|(id, _)| {
+                let valid = is_valid_note_id(id);
+                if !valid {
+                    warn!("Skipping note with invalid id {:?}", id);
+                }
+                valid
+            })
+            .map(|(id, content)| (id, (content, now)))
+            .collect();
+        Self {
+            notes: RwLock::new(notes),
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl NoteBackend for MemoryBackend {
+    fn list(&self) -> std::io::Result<Vec<String>> {
+        Ok(self.notes.read().keys().cloned().collect())
+    }
+
+    fn read(&self, id: &str) -> std::io::Result<Option<String>> {
+        Ok(self
+            .notes
+            .read()
+            .get(id)
+            .map(|(content, _)| content.clone()))
+    }
+
+    fn write(&self, id: &str, content: &str, create_new: bool) -> std::io::Result<()> {
+        if !is_valid_note_id(id) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid note id: {:?}", id),
+            ));
+        }
+        let mut notes = self.notes.write();
+        if create_new && notes.contains_key(id) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("note already exists: {}", id),
+            ));
+        }
+        notes.insert(id.to_string(), (content.to_string(), SystemTime::now()));
+        Ok(())
+    }
+
+    fn delete(&self, id: &str) -> std::io::Result<()> {
+        self.notes
+            .write()
+            .remove(id)
+            .map(|_| ())
+            .ok_or_else(|| note_not_found(id))
+    }
+
+    fn modified_time(&self, id: &str) -> std::io::Result<Option<SystemTime>> {
+        Ok(self.notes.read().get(id).map(|(_, modified)| *modified))
+    }
+}
+
+/// Several note collections served side by side, keyed by collection name.
+///
+/// Each collection is a [`NoteStore`] of its own, so note IDs, caches, and
+/// indexes are per collection: `work` and `personal` can both have a note
+/// `index` without either seeing the other's.
+#[cfg(feature = "ssr")]
+pub struct CollectionStore<B: NoteBackend = FileSystemBackend> {
+    /// Stores by collection name
+    collections: BTreeMap<String, Arc<NoteStore<B>>>,
+}
+
+#[cfg(feature = "ssr")]
+impl<B: NoteBackend> Default for CollectionStore<B> {
+    fn default() -> Self {
+        Self {
+            collections: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl<B: NoteBackend> CollectionStore<B> {
+    /// Creates an empty set of collections.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a collection.
+    ///
+    /// Names become URL path segments, so they may only contain ASCII letters,
+    /// digits, `-`, and `_`.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the collection
+    /// * `store` - Store holding the collection's notes
+    ///
+    /// # Returns
+    /// * `Result<(), std::io::Error>` - Success, or an IO error. Fails with
+    ///   `ErrorKind::InvalidInput` if the name isn't valid and
+    ///   `ErrorKind::AlreadyExists` if a collection with the name exists.
+    pub fn insert(&mut self, name: &str, store: Arc<NoteStore<B>>) -> std::io::Result<()> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid collection name: {:?}", name),
+            ));
+        }
+        if self.collections.contains_key(name) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("collection already exists: {}", name),
+            ));
+        }
+        self.collections.insert(name.to_string(), store);
+        Ok(())
+    }
+
+    /// Returns the store of a collection, if there is one with this name.
+    pub fn get(&self, name: &str) -> Option<&Arc<NoteStore<B>>> {
+        self.collections.get(name)
+    }
+
+    /// Iterates over the collections and their stores, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Arc<NoteStore<B>>)> {
+        self.collections
+            .iter()
+            .map(|(name, store)| (name.as_str(), store))
+    }
+}
+
+/// Manages the storage, processing, and caching of notes.
+///
+/// Notes are read and written through a [`NoteBackend`], a directory of
+/// markdown files unless constructed with [`NoteStore::with_backend`]. The
+/// NoteStore handles all content processing, including:
+/// - YAML frontmatter parsing
+/// - Markdown rendering
+/// - Syntax highlighting
+/// - LaTeX math rendering
+/// - Asset management
+/// - Caching of processed notes
+#[cfg(feature = "ssr")]
+pub struct NoteStore<B: NoteBackend = FileSystemBackend> {
+    /// Storage the notes are read from and written to
+    backend: B,
+    /// Collection of syntax definitions for code highlighting
+    syntax_set: SyntaxSet,
+    /// Collection of color themes for syntax highlighting
+    theme_set: ThemeSet,
+    /// Name of the theme in `theme_set` used for highlighting code blocks
+    theme_name: String,
+    /// LRU cache of processed notes, protected by a read-write lock
+    /// (shared with the file watcher)
+    note_cache: Arc<RwLock<NoteCache>>,
+    /// Reverse reference index (target ID -> notes referencing it), built lazily
+    backlinks: Arc<RwLock<Option<BacklinkIndex>>>,
+    /// Alias index, built lazily alongside the backlink index
+    aliases: Arc<RwLock<Option<Arc<AliasIndex>>>>,
+    /// Active file watcher, if `watch` was called. Dropping it (e.g. when the
+    /// store is dropped) stops the watcher thread.
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    /// Whether the watcher is keeping the cache fresh, so mtime checks can be skipped
+    watching: Arc<AtomicBool>,
+    /// Whether tags keep their original casing instead of being lowercased
+    preserve_tag_case: bool,
+    /// Whether parsed notes keep their markdown body in [`Note::source`]
+    include_source: bool,
+    /// Whether updates save the previous file to the note's `.history` directory
+    keep_history: bool,
+    /// Markdown constructs enabled when rendering notes
+    markdown: MarkdownConfig,
+    /// Delimiters that mark math in notes
+    math_delimiters: MathDelimiters,
+    /// Whether straight quotes, dashes, and ellipses in prose become typographic ones
+    smart_typography: bool,
+    /// How deeply embedded notes may embed other notes
+    embed_depth: usize,
+    /// Path the store's REST API is served under, for asset URLs in rendered notes
+    api_prefix: String,
+    /// KaTeX macros (e.g. `\RR` -> `\mathbb{R}`) available to every note
+    katex_macros: HashMap<String, String>,
+    /// Number of lookups served from the cache
+    cache_hits: AtomicU64,
+    /// Number of lookups that missed the cache or found a stale entry
+    cache_misses: AtomicU64,
+}
+
+#[cfg(feature = "ssr")]
+impl NoteStore<FileSystemBackend> {
+    /// Creates a new NoteStore at the specified path.
+    ///
+    /// # Arguments
+    /// * `path` - Directory path where notes will be stored
+    ///
+    /// # Returns
+    /// * `Result<Self, std::io::Error>` - New NoteStore instance or IO error
+    pub fn new<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        Ok(Self::with_backend(FileSystemBackend::new(path)?))
+    }
+
+    /// Returns the root directory the notes are stored in
+    pub fn root_path(&self) -> &Path {
+        self.backend.root_path()
+    }
+
+    /// Starts a background watcher that invalidates cached notes as soon as
+    /// their files (or `.assets` directories) change or are removed.
+    ///
+    /// While the watcher is active, reads trust the cache without re-checking
+    /// file modification times. If the watcher reports an error, the store
+    /// falls back to modification time checks. The watcher is stopped when the
+    /// store is dropped.
+    ///
+    /// # Returns
+    /// * `Result<(), std::io::Error>` - Success, or an error if the watcher can't be started
+    pub fn watch(&self) -> std::io::Result<()> {
+        // notify reports absolute paths, so IDs are derived relative to the canonical root
+        let root = std::fs::canonicalize(self.root_path())?;
+        let cache = Arc::clone(&self.note_cache);
+        let backlinks = Arc::clone(&self.backlinks);
+        let aliases = Arc::clone(&self.aliases);
+        let watching = Arc::clone(&self.watching);
+
+        let mut watcher = notify::recommended_watcher({
+            let root = root.clone();
+            move |result: notify::Result<notify::Event>| match result {
+                Ok(event) if event.kind.is_access() => {}
+                Ok(event) => {
+                    let mut cache = cache.write();
+                    for path in &event.paths {
+                        if let Some(id) = watched_note_id(&root, path) {
+                            cache.remove(&id);
+                        }
+                    }
+                    backlinks.write().take();
+                    aliases.write().take();
+                }
+                Err(e) => {
+                    warn!(
+                        "Note watcher error, falling back to modification time checks: {}",
+                        e
+                    );
+                    watching.store(false, Ordering::Relaxed);
+                }
+            }
+        })
+        .map_err(std::io::Error::other)?;
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(std::io::Error::other)?;
+
+        // Entries cached before the watcher started may already be stale
+        self.clear_cache();
+        *self.watcher.lock() = Some(watcher);
+        self.watching.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Finds note IDs claimed by more than one file.
+    ///
+    /// A file claims the ID given by its path and, if different, the `id` in
+    /// its frontmatter. Path IDs are unique, so a duplicate is a file declaring
+    /// another note's ID, or several files declaring the same ID. Files whose
+    /// frontmatter can't be parsed only claim their path ID.
+    ///
+    /// # Returns
+    /// * `Result<Vec<(String, Vec<PathBuf>)>, std::io::Error>` - Each duplicated ID
+    ///   with the files claiming it, ordered by ID and path, or IO error
+    pub fn find_duplicate_ids(&self) -> std::io::Result<Vec<(String, Vec<PathBuf>)>> {
+        let mut claims: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+        for (id, path) in self.backend.note_files()? {
+            let declared = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| parse_frontmatter(&content).ok())
+                .map(|(data, _)| data.id)
+                .filter(|declared| !declared.is_empty() && *declared != id);
+            if let Some(declared) = declared {
+                claims.entry(declared).or_default().push(path.clone());
+            }
+            claims.entry(id).or_default().push(path);
+        }
+
+        Ok(claims
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .map(|(id, mut paths)| {
+                paths.sort();
+                (id, paths)
+            })
+            .collect())
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl NoteStore<MemoryBackend> {
+    /// Creates a NoteStore serving the given notes from memory.
+    ///
+    /// Notes are rendered and cached like on disk, which makes this useful for
+    /// testing; writes through the store change only the in-memory copy.
+    ///
+    /// # Arguments
+    /// * `notes` - Raw note content, frontmatter included, keyed by note ID
+    ///
+    /// # Returns
+    /// * `Self` - New NoteStore instance
+    pub fn in_memory(notes: HashMap<String, String>) -> Self {
+        Self::with_backend(MemoryBackend::new(notes))
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl<B: NoteBackend> NoteStore<B> {
+    /// Creates a NoteStore that reads and writes notes through a backend.
+    ///
+    /// # Arguments
+    /// * `backend` - Storage for the notes
+    ///
+    /// # Returns
+    /// * `Self` - New NoteStore instance
+    pub fn with_backend(backend: B) -> Self {
+        Self {
+            backend,
+            // Load default syntax highlighting definitions
+            syntax_set: SyntaxSet::load_defaults_newlines(),
+            // Load default color themes
+            theme_set: ThemeSet::load_defaults(),
+            theme_name: DEFAULT_THEME.to_string(),
+            // Initialize empty cache
+            note_cache: Arc::new(RwLock::new(NoteCache::new(Some(DEFAULT_CACHE_CAPACITY)))),
+            // The backlink index is built on first use
+            backlinks: Arc::new(RwLock::new(None)),
+            aliases: Arc::new(RwLock::new(None)),
+            // File watching is opt-in via `watch`
+            watcher: Mutex::new(None),
+            watching: Arc::new(AtomicBool::new(false)),
+            preserve_tag_case: false,
+            include_source: false,
+            keep_history: false,
+            markdown: MarkdownConfig::default(),
+            math_delimiters: MathDelimiters::default(),
+            smart_typography: false,
+            embed_depth: DEFAULT_EMBED_DEPTH,
+            api_prefix: DEFAULT_API_PREFIX.to_string(),
+            katex_macros: HashMap::new(),
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Selects the syntax highlighting theme by name.
+    ///
+    /// The theme must be one of syntect's default themes (e.g. `base16-ocean.dark`,
+    /// `InspiredGitHub`, `Solarized (dark)`).
+    ///
+    /// # Arguments
+    /// * `name` - Name of the theme to use
+    ///
+    /// # Returns
+    /// * `Result<Self, std::io::Error>` - The store using the theme, or an
+    ///   `ErrorKind::InvalidInput` error listing the available themes
+    pub fn with_theme(mut self, name: &str) -> std::io::Result<Self> {
+        if !self.theme_set.themes.contains_key(name) {
+            let available = self
+                .theme_set
+                .themes
+                .keys()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "unknown syntax highlighting theme {:?} (available: {})",
+                    name, available
+                ),
+            ));
+        }
+
+        // Highlighted code is only classed, so cached notes stay valid
+        self.theme_name = name.to_string();
+        Ok(self)
+    }
+
+    /// Drops every cached note, for builders whose setting changes how notes
+    /// are parsed or rendered.
+    ///
+    /// Notes cached before the change were built with the previous setting,
+    /// so they're processed again on their next read.
+    fn rendering_changed(self) -> Self {
+        self.clear_cache();
+        self
+    }
+
+    /// Sets the maximum number of processed notes kept in the cache.
+    ///
+    /// When the cache is full, the least recently used note is evicted. Passing
+    /// `None` makes the cache unbounded. Defaults to [`DEFAULT_CACHE_CAPACITY`].
+    ///
+    /// # Arguments
+    /// * `capacity` - Maximum number of cached notes, or None for no limit
+    pub fn with_cache_capacity(self, capacity: Option<usize>) -> Self {
+        self.note_cache.write().set_capacity(capacity);
+        self
+    }
+
+    /// Sets whether tags keep their original casing.
+    ///
+    /// Tags are always deduplicated case-insensitively; by default they are
+    /// also lowercased. When preserved, the first spelling of each tag is kept.
+    ///
+    /// # Arguments
+    /// * `preserve` - Keep the original casing of tags for display
+    pub fn with_preserve_tag_case(mut self, preserve: bool) -> Self {
+        self.preserve_tag_case = preserve;
+        self.rendering_changed()
+    }
+
+    /// Sets whether parsed notes keep their markdown body alongside the rendered HTML.
+    ///
+    /// Off by default, since the body roughly doubles the size of each note in
+    /// the cache and in API responses.
+    ///
+    /// # Arguments
+    /// * `include` - Populate [`Note::source`] with the markdown body
+    pub fn with_note_source(mut self, include: bool) -> Self {
+        self.include_source = include;
+        self.rendering_changed()
+    }
+
+    /// Sets whether updating a note keeps its previous version.
+    ///
+    /// Off by default, since every update then adds a file. Previous versions
+    /// are saved to a `{id}.history` directory next to the note file and listed
+    /// by [`NoteStore::get_note_history`]. Backends that don't store notes as
+    /// files can't keep versions, so the setting is logged and ignored for them.
+    ///
+    /// # Arguments
+    /// * `keep` - Save a note's file before [`NoteStore::update_note`] overwrites it
+    pub fn with_history(mut self, keep: bool) -> Self {
+        if keep && !self.backend.stores_files() {
+            warn!("Note history needs notes stored as files; not keeping it");
+        }
+        self.keep_history = keep && self.backend.stores_files();
+        self
+    }
+
+    /// Sets whether rendered notes get typographic quotes, dashes, and ellipses.
+    ///
+    /// Off by default. When on, `"` and `'` become curly quotes (`“ ” ‘ ’`),
+    /// `--` an en dash, `---` an em dash, and `...` an ellipsis, everywhere but
+    /// in code, preformatted blocks, and math. Table of contents entries are
+    /// converted too.
+    ///
+    /// # Arguments
+    /// * `enabled` - Convert straight punctuation in rendered prose
+    pub fn with_smart_typography(mut self, enabled: bool) -> Self {
+        self.smart_typography = enabled;
+        self.rendering_changed()
+    }
+
+    /// Sets the path the store's REST API is served under.
+    ///
+    /// Relative links to a note's assets are rendered as URLs under this path,
+    /// e.g. `{prefix}/notes/{id}/assets/{name}`. Defaults to [`DEFAULT_API_PREFIX`];
+    /// stores served as one of several collections use their collection's path.
+    ///
+    /// # Arguments
+    /// * `prefix` - Absolute path of the API, without a trailing `/`
+    pub fn with_api_prefix(mut self, prefix: &str) -> Self {
+        self.api_prefix = prefix.trim_end_matches('/').to_string();
+        // Asset links are part of the rendered content
+        self.rendering_changed()
+    }
+
+    /// Sets how deeply notes embedded with `![[id]]` may embed other notes.
+    ///
+    /// A note's own embeds are at depth 1, the embeds inside those at depth 2,
+    /// and so on. Embeds deeper than this, and embeds of a note that is already
+    /// being embedded, are shown as plain links. Defaults to [`DEFAULT_EMBED_DEPTH`];
+    /// 0 turns every embed into a link.
+    ///
+    /// # Arguments
+    /// * `depth` - The deepest level at which notes are embedded
+    pub fn with_embed_depth(mut self, depth: usize) -> Self {
+        self.embed_depth = depth;
+        self.rendering_changed()
+    }
+
+    /// Sets which markdown constructs are enabled when rendering notes.
+    ///
+    /// Defaults to GitHub-flavored markdown with raw HTML escaped.
+    ///
+    /// # Arguments
+    /// * `config` - The constructs to enable
+    pub fn with_markdown_config(mut self, config: MarkdownConfig) -> Self {
+        self.markdown = config;
+        self.rendering_changed()
+    }
+
+    /// Sets which delimiters mark LaTeX math in notes.
+    ///
+    /// Defaults to dollars only. Notes with `math: false` aren't searched for
+    /// math either way.
+    ///
+    /// # Arguments
+    /// * `delimiters` - The delimiters to recognize
+    pub fn with_math_delimiters(mut self, delimiters: MathDelimiters) -> Self {
+        self.math_delimiters = delimiters;
+        self.rendering_changed()
+    }
+
+    /// Adds syntax definitions and themes from a directory to the defaults.
+    ///
+    /// The directory is searched recursively for `.sublime-syntax` and
+    /// `.tmTheme` files. Call this before [`NoteStore::with_theme`] to select
+    /// one of the added themes. Code in languages that still aren't known is
+    /// left unhighlighted.
+    ///
+    /// # Arguments
+    /// * `dir` - Directory containing the extra syntaxes and themes
+    ///
+    /// # Returns
+    /// * `Result<Self, std::io::Error>` - The configured store, or an error if the
+    ///   directory doesn't exist or a syntax or theme file can't be loaded
+    pub fn with_syntax_dir<P: AsRef<Path>>(mut self, dir: P) -> std::io::Result<Self> {
+        let dir = dir.as_ref();
+        if !std::fs::metadata(dir)?.is_dir() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} is not a directory", dir.display()),
+            ));
+        }
+
+        let load_error = |kind: &str, e: syntect::LoadingError| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("failed to load {} from {}: {}", kind, dir.display(), e),
+            )
+        };
+
+        let mut syntaxes = std::mem::take(&mut self.syntax_set).into_builder();
+        syntaxes
+            .add_from_folder(dir, true)
+            .map_err(|e| load_error("syntaxes", e))?;
+        self.syntax_set = syntaxes.build();
+        self.theme_set
+            .add_from_folder(dir)
+            .map_err(|e| load_error("themes", e))?;
+
+        Ok(self.rendering_changed())
+    }
+
+    /// Sets LaTeX macros available to the inline and display math of every note.
+    ///
+    /// Each macro is checked by rendering it once, so a malformed definition
+    /// fails here rather than when a note uses it.
+    ///
+    /// # Arguments
+    /// * `macros` - Macro names (e.g. `\RR`) mapped to their expansions (e.g. `\mathbb{R}`)
+    ///
+    /// # Returns
+    /// * `Result<Self, std::io::Error>` - The configured store, or an `InvalidInput`
+    ///   error naming the first macro KaTeX rejects
+    pub fn with_katex_macros(mut self, macros: HashMap<String, String>) -> std::io::Result<Self> {
+        let invalid = |name: &str, reason: String| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid KaTeX macro {}: {}", name, reason),
+            )
+        };
+
+        let opts = macros
+            .iter()
+            .fold(Opts::builder(), |builder, (name, expansion)| {
+                builder.add_macro(name.clone(), expansion.clone())
+            })
+            .build()
+            .map_err(|e| invalid("definitions", e.to_string()))?;
+
+        let mut names: Vec<&String> = macros.keys().collect();
+        names.sort();
+        for name in names {
+            if !name.starts_with('\\') || name.len() < 2 {
+                return Err(invalid(
+                    name,
+                    "names must start with a backslash".to_string(),
+                ));
+            }
+
+            // Supply an argument for every parameter (`#1`, `#2`, ...) the macro uses
+            let arity = (1..=9)
+                .rev()
+                .find(|n| macros[name].contains(&format!("#{}", n)))
+                .unwrap_or(0);
+            let usage = format!("{}{}", name, "{x}".repeat(arity));
+            render_with_opts(&usage, &opts).map_err(|e| invalid(name, e.to_string()))?;
+        }
+
+        self.katex_macros = macros;
+        Ok(self.rendering_changed())
+    }
+
+    /// Reads LaTeX macros from a YAML file and sets them like
+    /// [`NoteStore::with_katex_macros`].
+    ///
+    /// The file maps macro names to expansions, e.g. `'\RR': '\mathbb{R}'`.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the YAML file
+    ///
+    /// # Returns
+    /// * `Result<Self, std::io::Error>` - The configured store, or an error if the file
+    ///   can't be read, isn't a map of strings, or defines an invalid macro
+    pub fn with_katex_macros_file<P: AsRef<Path>>(self, path: P) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        let macros: HashMap<String, String> = serde_yaml::from_str(&content).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid KaTeX macros file {}: {}", path.display(), e),
+            )
+        })?;
+        self.with_katex_macros(macros)
+    }
+
+    /// Returns the name of the syntax highlighting theme in use
+    pub fn theme_name(&self) -> &str {
+        &self.theme_name
+    }
+
+    /// Returns the path the store's REST API is served under
+    pub fn api_prefix(&self) -> &str {
+        &self.api_prefix
+    }
+
+    /// Gets the last modified time for a file
+    ///
+    /// # Arguments
+    /// * `path` - Path to the file to check
+    ///
+    /// # Returns
+    /// * `Result<SystemTime, std::io::Error>` - Last modified time or error
+    fn get_file_modified_time(path: &Path) -> std::io::Result<SystemTime> {
+        path.metadata()?.modified()
+    }
+
+    /// Returns the file a note is stored in, if the backend keeps notes as files.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note
+    ///
+    /// # Returns
+    /// * `Option<PathBuf>` - Path to the note file, or None if the ID is invalid
+    ///   or the backend doesn't store files
+    fn note_path(&self, id: &str) -> Option<PathBuf> {
+        self.backend.path(id)
+    }
+
+    /// Reads a note's content from the backend.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note to read
+    ///
+    /// # Returns
+    /// * `Result<String, std::io::Error>` - The content including frontmatter,
+    ///   or an IO error. Fails with `ErrorKind::NotFound` if the note doesn't exist.
+    fn read_note(&self, id: &str) -> std::io::Result<String> {
+        self.backend.read(id)?.ok_or_else(|| note_not_found(id))
+    }
+
+    /// Checks if a cached note is still valid by comparing timestamps
+    ///
+    /// A note whose modification time can't be read is treated as changed, so
+    /// it's parsed again rather than failing the lookup; the failure is logged.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note to check
+    /// * `cached` - The cached note entry to validate
+    ///
+    /// # Returns
+    /// * `bool` - Whether the cache is still valid
+    fn is_cache_valid(&self, id: &str, cached: &CachedNote) -> bool {
+        // The watcher evicts entries as soon as their files change
+        if self.watching.load(Ordering::Relaxed) {
+            return true;
+        }
+
+        let current_modified = match self.backend.modified_time(id) {
+            Ok(Some(modified)) => modified,
+            Ok(None) => return false,
+            Err(e) => {
+                warn!(
+                    "Can't read the modification time of note {}, parsing it again: {}",
+                    id, e
+                );
+                return false;
+            }
+        };
+        cached.is_fresh(Some(current_modified)) && self.embeds_unchanged(cached)
+    }
+
+    /// Checks that none of the notes embedded in a cached note changed since it was cached.
+    ///
+    /// # Arguments
+    /// * `cached` - The cache entry to check
+    ///
+    /// # Returns
+    /// * `bool` - False if an embedded note was modified or removed
+    fn embeds_unchanged(&self, cached: &CachedNote) -> bool {
+        cached.embeds.iter().all(|id| {
+            self.backend
+                .modified_time(id)
+                .ok()
+                .flatten()
+                .is_some_and(|modified| modified <= cached.cached_at)
+        })
+    }
+
+    /// Lists all published notes in the store, using cache when possible.
+    ///
+    /// Like [`NoteStore::list_all_notes`], but leaves out drafts.
+    ///
+    /// # Returns
+    /// * `Result<Vec<Note>, std::io::Error>` - List of notes sorted by ID, or IO error
+    pub fn list_notes(&self) -> std::io::Result<Vec<Note>> {
+        let mut notes = self.list_all_notes()?;
+        notes.retain(|note| !note.draft);
+        Ok(notes)
+    }
+
+    /// Lists all notes in the store, drafts included, using cache when possible.
+    ///
+    /// Lists every note in the backend and returns all valid notes. Notes in
+    /// subdirectories get IDs that include their relative path (e.g.
+    /// `projects/foo`). Uses cached versions of notes when available and still
+    /// valid.
+    ///
+    /// The cache is only locked to look up or insert individual notes, so
+    /// parsing doesn't hold up concurrent requests.
+    ///
+    /// # Returns
+    /// * `Result<Vec<Note>, std::io::Error>` - List of notes sorted by ID, or IO error
+    pub fn list_all_notes(&self) -> std::io::Result<Vec<Note>> {
+        let mut notes = Vec::new();
+
+        for id in self.backend.list()? {
+            // Check if we have a valid cached version
+            {
+                let cache = self.note_cache.read();
+                if let Some(cached) = cache.get(&id) {
+                    if self.is_cache_valid(&id, cached) {
+                        self.record_cache_hit(&id);
+                        notes.push(cached.note.clone());
+                        continue;
+                    }
+                }
+            }
+            self.record_cache_miss(&id);
+
+            // No valid cache, need to process the note
+            match self.read_and_load(&id) {
+                Ok(note) => notes.push(note),
+                Err(e) => warn!("Skipping note {}: {}", id, e),
+            }
+        }
+
+        notes.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(notes)
+    }
+
+    /// Lists the metadata of every note without rendering their content.
+    ///
+    /// Notes with a valid cache entry are taken from the cache. Others only have
+    /// their frontmatter read and scanned for wikilinks, and aren't cached, so
+    /// listing a large store doesn't render every note. Their `excerpt` is empty
+    /// until the note is rendered, which warming the cache at startup does for
+    /// every note. A note that fails to parse is left out with a warning rather
+    /// than failing the listing.
+    ///
+    /// # Arguments
+    /// * `include_drafts` - Whether drafts are listed too
+    ///
+    /// # Returns
+    /// * `Result<Vec<NoteMetadata>, std::io::Error>` - Note metadata sorted by ID, or IO error
+    pub fn list_metadata(&self, include_drafts: bool) -> std::io::Result<Vec<NoteMetadata>> {
+        let mut notes = Vec::new();
+        self.visit_metadata(include_drafts, |note| notes.push(NoteMetadata::from(note)))?;
+        notes.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(notes)
+    }
+
+    /// Passes every note to `visit` without rendering notes that aren't cached.
+    ///
+    /// Notes with a valid cache entry are passed as cached. Others come from
+    /// [`NoteStore::parse_note_metadata`], so they have no content. Notes are
+    /// visited in the backend's order.
+    ///
+    /// # Arguments
+    /// * `include_drafts` - Whether drafts are visited too
+    /// * `visit` - Called with each note
+    ///
+    /// # Returns
+    /// * `Result<(), std::io::Error>` - Success, or IO error if the notes can't be listed
+    fn visit_metadata(
+        &self,
+        include_drafts: bool,
+        mut visit: impl FnMut(&Note),
+    ) -> std::io::Result<()> {
+        for id in self.backend.list()? {
+            {
+                let cache = self.note_cache.read();
+                if let Some(cached) = cache.get(&id) {
+                    if self.is_cache_valid(&id, cached) {
+                        if include_drafts || !cached.note.draft {
+                            visit(&cached.note);
+                        }
+                        continue;
+                    }
+                }
+            }
+
+            let parsed = self
+                .read_note(&id)
+                .and_then(|content| Ok(self.parse_note_metadata(&id, &content)?));
+            match parsed {
+                Ok(note) if include_drafts || !note.draft => visit(&note),
+                Ok(_) => {}
+                Err(e) => warn!("Skipping note {}: {}", id, e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Lists one page of published note metadata, continuing from a cursor.
+    ///
+    /// Notes are sorted by ID, and the cursor is the ID of the last note on
+    /// the previous page, so pages stay consistent when notes are added or
+    /// removed in between: a page starts after the cursor even if that note
+    /// was deleted. Cursors should be treated as opaque. A `limit` of zero
+    /// is treated as one.
+    ///
+    /// # Arguments
+    /// * `cursor` - The `next_cursor` of the previous page, or None for the first page
+    /// * `limit` - Maximum number of notes on the page
+    ///
+    /// # Returns
+    /// * `Result<NoteListPage, std::io::Error>` - The page and the cursor of the next
+    ///   one, or IO error
+    pub fn list_notes_paged(
+        &self,
+        cursor: Option<String>,
+        limit: usize,
+    ) -> std::io::Result<NoteListPage> {
+        let limit = limit.max(1);
+        let mut remaining = self
+            .list_metadata(false)?
+            .into_iter()
+            .filter(|note| cursor.as_ref().is_none_or(|cursor| note.id > *cursor))
+            .peekable();
+
+        let notes: Vec<NoteMetadata> = remaining.by_ref().take(limit).collect();
+        let next_cursor = remaining
+            .peek()
+            .and_then(|_| notes.last())
+            .map(|note| note.id.clone());
+        Ok(NoteListPage { notes, next_cursor })
+    }
+
+    /// Parses every note in the store into the cache ahead of the first request.
+    ///
+    /// A note that fails to parse stays uncached, so its error is reported
+    /// again when it's requested. If the store holds more notes than the cache
+    /// capacity, only the most recently parsed notes stay cached.
+    ///
+    /// # Returns
+    /// * `Result<(), std::io::Error>` - Success, or an IO error if the notes
+    ///   can't be listed
+    pub fn warm_cache(&self) -> std::io::Result<()> {
+        let started = std::time::Instant::now();
+        let mut warmed = 0;
+
+        for id in self.backend.list()? {
+            match self.get_note(&id) {
+                Ok(Some(_)) => warmed += 1,
+                // Deleted since the notes were listed
+                Ok(None) => {}
+                Err(e) => warn!("Skipping note {}: {}", id, e),
+            }
+        }
+
+        info!(
+            "Warmed cache with {} notes in {:?}",
+            warmed,
+            started.elapsed()
+        );
+        Ok(())
+    }
+
+    /// Re-parses every note whose cache entry is missing or older than the note.
+    ///
+    /// Notes that fail to parse aren't counted; they're reported by
+    /// [`NoteStore::parse_errors`]. While a watcher is active, cached notes are
+    /// always current, so only uncached notes are parsed.
+    ///
+    /// # Returns
+    /// * `Result<usize, std::io::Error>` - Number of notes parsed into the cache,
+    ///   or an IO error if the notes can't be listed
+    pub fn refresh_cache(&self) -> std::io::Result<usize> {
+        let mut refreshed = 0;
+
+        for id in self.backend.list()? {
+            let fresh = match self.note_cache.read().get(&id) {
+                Some(cached) => self.is_cache_valid(&id, cached),
+                None => false,
+            };
+            if fresh {
+                continue;
+            }
+
+            if self.read_and_load(&id).is_ok() {
+                refreshed += 1;
+            }
+        }
+
+        Ok(refreshed)
+    }
+
+    /// Starts refreshing changed notes into the cache on a fixed interval.
+    ///
+    /// An alternative to [`NoteStore::watch`] where file system events aren't
+    /// available: requests keep checking modification times, but the refresh
+    /// re-parses changed notes ahead of them. Each run logs how many notes it
+    /// refreshed. Must be called from within a Tokio runtime.
+    ///
+    /// # Arguments
+    /// * `interval` - Time between refreshes; the first runs one interval after starting
+    ///
+    /// # Returns
+    /// * `CacheRefresh` - Handle that stops the refresh when dropped
+    pub fn start_refresh(self: &Arc<Self>, interval: Duration) -> CacheRefresh {
+        // A weak reference, so the task doesn't keep the store alive
+        let store: Weak<Self> = Arc::downgrade(self);
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // The first tick completes immediately
+            ticker.tick().await;
+
+            loop {
+                ticker.tick().await;
+                let Some(store) = store.upgrade() else {
+                    break;
+                };
+                match tokio::task::spawn_blocking(move || store.refresh_cache()).await {
+                    Ok(Ok(0)) => {}
+                    Ok(Ok(refreshed)) => info!("Refreshed {} notes in the cache", refreshed),
+                    Ok(Err(e)) => warn!("Failed to refresh the note cache: {}", e),
+                    Err(e) => warn!("Note cache refresh task failed: {}", e),
+                }
+            }
+        });
+
+        CacheRefresh { task }
+    }
+
+    /// Lists all published notes in the given order.
+    ///
+    /// # Arguments
+    /// * `sort` - How to order the notes
+    ///
+    /// # Returns
+    /// * `Result<Vec<Note>, std::io::Error>` - Sorted notes or IO error
+    pub fn list_notes_sorted(&self, sort: NoteSort) -> std::io::Result<Vec<Note>> {
+        let mut notes = self.list_notes()?;
+        sort.sort_notes(&mut notes);
+        Ok(notes)
+    }
+
+    /// Lists all published notes in the store without blocking the async runtime.
+    ///
+    /// Like [`NoteStore::list_all_notes_async`], but leaves out drafts.
+    ///
+    /// # Returns
+    /// * `Result<Vec<Note>, std::io::Error>` - List of notes sorted by ID, or IO error
+    pub async fn list_notes_async(self: &Arc<Self>) -> std::io::Result<Vec<Note>> {
+        let mut notes = self.list_all_notes_async().await?;
+        notes.retain(|note| !note.draft);
+        Ok(notes)
+    }
+
+    /// Lists all notes in the store, drafts included, without blocking the async runtime.
+    ///
+    /// Listing, reading, and parsing the notes run on the blocking thread pool.
+    /// Shares its cache with [`NoteStore::list_all_notes`].
+    ///
+    /// # Returns
+    /// * `Result<Vec<Note>, std::io::Error>` - List of notes sorted by ID, or IO error
+    pub async fn list_all_notes_async(self: &Arc<Self>) -> std::io::Result<Vec<Note>> {
+        let store = Arc::clone(self);
+        let ids = tokio::task::spawn_blocking(move || store.backend.list())
+            .await
+            .map_err(std::io::Error::other)??;
+
+        let mut notes = Vec::new();
+        for id in ids {
+            // Like list_all_notes, skip notes that can't be read or parsed
+            match self.get_note_async(&id).await {
+                Ok(Some(note)) => notes.push(note),
+                Ok(None) => {}
+                Err(e) => warn!("Skipping note {}: {}", id, e),
+            }
+        }
+
+        notes.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(notes)
+    }
+
+    /// Checks whether a note exists for an ID, without reading or parsing it.
+    ///
+    /// Aliases aren't resolved, and a note that fails to parse still counts as
+    /// existing.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note to look for
+    ///
+    /// # Returns
+    /// * `bool` - Whether the backend has a note with this ID
+    pub fn note_exists(&self, id: &str) -> bool {
+        self.modified_time(id)
+            .is_ok_and(|modified| modified.is_some())
+    }
+
+    /// Checks that a note exists and returns when it was last written, if known.
+    ///
+    /// A backend that can't tell when a note was written fails with
+    /// `ErrorKind::Unsupported`, which only happens for notes it has. Such a
+    /// note exists without a modification time: the error is logged, and its
+    /// cache entries are never current, so it's parsed again on every read.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note
+    ///
+    /// # Returns
+    /// * `Result<Option<Option<SystemTime>>, std::io::Error>` - None if the
+    ///   note doesn't exist, otherwise its modification time if the backend
+    ///   can tell, or IO error
+    fn modified_time(&self, id: &str) -> std::io::Result<Option<Option<SystemTime>>> {
+        match self.backend.modified_time(id) {
+            Ok(modified) => Ok(modified.map(Some)),
+            Err(e) if e.kind() == std::io::ErrorKind::Unsupported => {
+                warn!(
+                    "No modification time for note {}, reading it without the cache: {}",
+                    id, e
+                );
+                Ok(Some(None))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Retrieves a specific note by ID, using cache when possible.
+    ///
+    /// If no note has exactly this ID, it's resolved as an alias and then
+    /// case-insensitively, and the note it resolves to is returned.
+    ///
+    /// # Arguments
+    /// * `id` - The unique identifier of the note to retrieve, which may include
+    ///   a relative directory path (e.g. `projects/foo`)
+    ///
+    /// # Returns
+    /// * `Result<Option<Note>, std::io::Error>` - The note if found, None if not found,
+    ///   or IO error. Notes that can't be parsed fail with `ErrorKind::InvalidData`
+    ///   wrapping a [`ParseError`].
+    pub fn get_note(&self, id: &str) -> std::io::Result<Option<Note>> {
+        let modified = match self.modified_time(id)? {
+            Some(modified) => modified,
+            // Requests for an alias or a differently cased ID are served by the
+            // note it resolves to. Names only resolve to other note IDs, which
+            // are never resolved further, so this recurses at most once.
+            None => {
+                return match self.resolve_id(id)? {
+                    Some(canonical) => self.get_note(&canonical),
+                    None => Ok(None),
+                }
+            }
+        };
+
+        // Check cache first
+        {
+            let cache = self.note_cache.read();
+            if let Some(cached) = cache.get(id) {
+                if self.is_cache_valid(id, cached) {
+                    self.record_cache_hit(id);
+                    return Ok(Some(cached.note.clone()));
+                }
+            }
+        }
+
+        // No valid cache, need to process the note
+        self.record_cache_miss(id);
+        let content = self.read_note(id)?;
+        Ok(Some(self.load_note(id, &content, modified)?))
+    }
+
+    /// Retrieves a note's metadata by ID without rendering its content.
+    ///
+    /// IDs are resolved like [`NoteStore::get_note`]. A valid cache entry is
+    /// used if there is one; otherwise only the note's frontmatter is read and
+    /// scanned for wikilinks, as in [`NoteStore::list_metadata`], and nothing is
+    /// cached. The `excerpt` is empty unless the note was cached.
+    ///
+    /// # Arguments
+    /// * `id` - The unique identifier of the note
+    ///
+    /// # Returns
+    /// * `Result<Option<NoteMetadata>, std::io::Error>` - The note's metadata if
+    ///   found, None if not found, or IO error. Notes that can't be parsed fail
+    ///   like in [`NoteStore::get_note`].
+    pub fn get_note_metadata(&self, id: &str) -> std::io::Result<Option<NoteMetadata>> {
+        if self.modified_time(id)?.is_none() {
+            // Names only resolve to note IDs, so this recurses at most once
+            return match self.resolve_id(id)? {
+                Some(canonical) => self.get_note_metadata(&canonical),
+                None => Ok(None),
+            };
+        }
+
+        {
+            let cache = self.note_cache.read();
+            if let Some(cached) = cache.get(id) {
+                if self.is_cache_valid(id, cached) {
+                    return Ok(Some(NoteMetadata::from(&cached.note)));
+                }
+            }
+        }
+
+        let content = self.read_note(id)?;
+        let note = self.parse_note_metadata(id, &content)?;
+        Ok(Some(NoteMetadata::from(&note)))
+    }
+
+    /// Retrieves several notes by ID at once, using cache when possible.
+    ///
+    /// IDs are resolved like [`NoteStore::get_note`]. Notes that don't exist,
+    /// or can't be read or parsed, map to None instead of failing the batch;
+    /// failures are logged.
+    ///
+    /// # Arguments
+    /// * `ids` - IDs of the notes to retrieve
+    ///
+    /// # Returns
+    /// * `HashMap<String, Option<Note>>` - Each requested ID with its note, if found
+    pub fn get_notes_by_ids(&self, ids: &[String]) -> HashMap<String, Option<Note>> {
+        ids.iter()
+            .map(|id| {
+                let note = self.get_note(id).unwrap_or_else(|e| {
+                    warn!("Skipping note {}: {}", id, e);
+                    None
+                });
+                (id.clone(), note)
+            })
+            .collect()
+    }
+
+    /// Picks a published note uniformly at random.
+    ///
+    /// Only metadata is needed, so uncached notes aren't rendered; the chosen
+    /// note's `excerpt` is empty unless it was already rendered.
+    ///
+    /// # Returns
+    /// * `Result<Option<NoteMetadata>, std::io::Error>` - Metadata of the chosen
+    ///   note, None if there are no notes, or IO error
+    pub fn random_note(&self) -> std::io::Result<Option<NoteMetadata>> {
+        let notes = self.list_metadata(false)?;
+        Ok(notes.choose(&mut rand::thread_rng()).cloned())
+    }
+
+    /// Reads a note as written, frontmatter included, without rendering it.
+    ///
+    /// Bypasses the note cache. Aliases aren't resolved.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note to read
+    ///
+    /// # Returns
+    /// * `Result<Option<String>, std::io::Error>` - The raw markdown if the note
+    ///   exists, None if not, or IO error
+    pub fn get_note_raw(&self, id: &str) -> std::io::Result<Option<String>> {
+        self.backend.read(id)
+    }
+
+    /// Retrieves a specific note by ID without blocking the async runtime.
+    ///
+    /// The note is read and parsed on the blocking thread pool. Shares its
+    /// cache with [`NoteStore::get_note`].
+    ///
+    /// # Arguments
+    /// * `id` - The unique identifier of the note to retrieve, which may include
+    ///   a relative directory path (e.g. `projects/foo`)
+    ///
+    /// # Returns
+    /// * `Result<Option<Note>, std::io::Error>` - The note if found, None if not found,
+    ///   or IO error. Notes that can't be parsed fail like in [`NoteStore::get_note`].
+    pub async fn get_note_async(self: &Arc<Self>, id: &str) -> std::io::Result<Option<Note>> {
+        let canonical: String;
+        let (id, modified) = match self.modified_time_async(id).await? {
+            Some(modified) => (id, modified),
+            // Requests for an alias or a differently cased ID are served by
+            // the note it resolves to
+            None => {
+                let store = Arc::clone(self);
+                let name = id.to_string();
+                let target = tokio::task::spawn_blocking(move || store.resolve_id(&name))
+                    .await
+                    .map_err(std::io::Error::other)??;
+                let Some(target) = target else {
+                    return Ok(None);
+                };
+                canonical = target;
+                match self.modified_time_async(&canonical).await? {
+                    Some(modified) => (canonical.as_str(), modified),
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        // Serve the cache right away when that takes no IO: the watcher
+        // evicts entries as soon as their files change, and a note without
+        // embeds only needs the modification time already looked up
+        {
+            let cache = self.note_cache.read();
+            if let Some(cached) = cache.get(id) {
+                if self.watching.load(Ordering::Relaxed)
+                    || (cached.is_fresh(modified) && cached.embeds.is_empty())
+                {
+                    self.record_cache_hit(id);
+                    return Ok(Some(cached.note.clone()));
+                }
+            }
+        }
+
+        // Checking embedded notes, and reading and processing the note, touch
+        // the backend, so they run off the async runtime
+        let store = Arc::clone(self);
+        let id = id.to_string();
+        let note = tokio::task::spawn_blocking(move || {
+            {
+                let cache = store.note_cache.read();
+                if let Some(cached) = cache.get(&id) {
+                    if cached.is_fresh(modified) && store.embeds_unchanged(cached) {
+                        store.record_cache_hit(&id);
+                        return Ok(cached.note.clone());
+                    }
+                }
+            }
+
+            store.record_cache_miss(&id);
+            let content = store.read_note(&id)?;
+            Ok::<_, std::io::Error>(store.load_note(&id, &content, modified)?)
+        })
+        .await
+        .map_err(std::io::Error::other)??;
+        Ok(Some(note))
+    }
+
+    /// Looks up a note's modification time without blocking the async runtime.
+    ///
+    /// # Arguments
+    /// * `id` - The unique identifier of the note
+    ///
+    /// # Returns
+    /// * `Result<Option<SystemTime>, std::io::Error>` - The note's modification
+    ///   time, None if there's no such note, or IO error
+    async fn modified_time_async(
+        self: &Arc<Self>,
+        id: &str,
+    ) -> std::io::Result<Option<Option<SystemTime>>> {
+        let store = Arc::clone(self);
+        let id = id.to_string();
+        tokio::task::spawn_blocking(move || store.modified_time(&id))
+            .await
+            .map_err(std::io::Error::other)?
+    }
+
+    /// Counts a lookup served from the cache.
+    fn record_cache_hit(&self, id: &str) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+        debug!(note = id, "Cache hit");
+    }
+
+    /// Counts a lookup that missed the cache or found a stale entry.
+    fn record_cache_miss(&self, id: &str) {
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
+        debug!(note = id, "Cache miss");
+    }
+
+    /// Reads a note from the backend, parses it, and stores the result in the cache.
+    ///
+    /// The modification time is taken before reading, so a write in between
+    /// leaves the cache entry stale rather than wrongly fresh.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note
+    ///
+    /// # Returns
+    /// * `Result<Note, std::io::Error>` - The processed note, or an IO error.
+    ///   Fails with `ErrorKind::NotFound` if the note doesn't exist.
+    fn read_and_load(&self, id: &str) -> std::io::Result<Note> {
+        let modified = self.modified_time(id)?.ok_or_else(|| note_not_found(id))?;
+        let content = self.read_note(id)?;
+        Ok(self.load_note(id, &content, modified)?)
+    }
+
+    /// Parses a note read from the backend and stores the result in the cache.
+    ///
+    /// # Arguments
+    /// * `id` - ID the note is addressed by
+    /// * `content` - Raw note content including frontmatter
+    /// * `modified` - Modification time of the note when it was read
+    ///
+    /// # Returns
+    /// * `Result<Note, ParseError>` - The processed note, or why parsing failed
+    fn load_note(
+        &self,
+        id: &str,
+        content: &str,
+        modified: Option<SystemTime>,
+    ) -> Result<Note, ParseError> {
+        let note = self
+            .parse_note(id, content)
+            .inspect_err(|e| debug!(note = id, error = %e, "Failed to parse note"))?;
+
+        // The note may have changed its references or aliases
+        self.invalidate_indexes();
+
+        // Update cache with the new processed note
+        self.note_cache.write().insert(
+            id.to_string(),
+            CachedNote::new(note.clone(), markdown_body(content), modified),
+        );
+        Ok(note)
+    }
+
+    /// Creates a new note from the given note metadata and markdown body.
+    ///
+    /// The frontmatter (id, title, tags, references) is serialized as YAML and
+    /// written to the backend followed by the body (`{id}.md` on the
+    /// filesystem). Any stale cache entry for the ID is invalidated afterwards.
+    ///
+    /// # Arguments
+    /// * `note` - Note whose frontmatter fields should be written
+    /// * `body` - Markdown body of the note
+    ///
+    /// # Returns
+    /// * `Result<(), std::io::Error>` - Success, or an IO error. Fails with
+    ///   `ErrorKind::AlreadyExists` if a note with this ID already exists and
+    ///   `ErrorKind::InvalidInput` if the backend doesn't accept the ID.
+    pub fn create_note(&self, note: &Note, body: &str) -> std::io::Result<()> {
+        let file_content = render_note_file(note, body)?;
+        self.backend.write(&note.id, &file_content, true)?;
+
+        self.invalidate_cache(&note.id);
+        Ok(())
+    }
+
+    /// Creates a note from one of the templates in the `.templates` directory.
+    ///
+    /// The template `{name}` is the file `.templates/{name}.md` in the notes
+    /// directory (the note ID `.templates/{name}` in other backends). Every
+    /// `{{var}}` placeholder in it, frontmatter included, is replaced by the
+    /// variable's value, and the result is written as a new note like
+    /// [`NoteStore::create_note`]. Besides `vars`, `{{date}}` is today's UTC
+    /// date as `YYYY-MM-DD` unless `vars` sets it.
+    ///
+    /// # Arguments
+    /// * `template` - Name of the template
+    /// * `vars` - Placeholder values. `id` is required and is the new note's ID.
+    ///
+    /// # Returns
+    /// * `Result<String, std::io::Error>` - ID of the new note, or an IO error.
+    ///   Fails with `ErrorKind::NotFound` if the template doesn't exist,
+    ///   `ErrorKind::InvalidInput` if `id` is missing or invalid or a
+    ///   placeholder has no value, `ErrorKind::InvalidData` if the filled-in
+    ///   template can't be parsed, and `ErrorKind::AlreadyExists` if a note with
+    ///   the ID already exists.
+    pub fn create_from_template(
+        &self,
+        template: &str,
+        mut vars: HashMap<String, String>,
+    ) -> std::io::Result<String> {
+        let id = vars.get("id").cloned().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "missing template variable: id",
+            )
+        })?;
+        if template.is_empty() || template.contains('/') {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid template name: {:?}", template),
+            ));
+        }
+        let content = self
+            .backend
+            .read(&format!("{}/{}", TEMPLATES_DIR, template))?
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("template not found: {}", template),
+                )
+            })?;
+        vars.entry("date".to_string())
+            .or_insert_with(|| Utc::now().format("%Y-%m-%d").to_string());
+
+        let placeholder_regex =
+            Regex::new(r"\{\{\s*([A-Za-z0-9_-]+)\s*\}\}").map_err(std::io::Error::other)?;
+        let mut unresolved: Vec<String> = Vec::new();
+        let content = placeholder_regex.replace_all(&content, |caps: &regex::Captures| match vars
+            .get(&caps[1])
+        {
+            Some(value) => value.clone(),
+            None => {
+                if !unresolved.iter().any(|name| name == &caps[1]) {
+                    unresolved.push(caps[1].to_string());
+                }
+                caps[0].to_string()
+            }
+        });
+        if !unresolved.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "unresolved template variables in {}: {}",
+                    template,
+                    unresolved.join(", ")
+                ),
+            ));
+        }
+
+        // A value can break the frontmatter, so check before writing
+        parse_frontmatter(&content)?;
+        self.backend.write(&id, &content, true)?;
+
+        self.invalidate_cache(&id);
+        Ok(id)
+    }
+
+    /// Overwrites an existing note with new frontmatter and body.
+    ///
+    /// With [`NoteStore::with_history`] on and a backend storing files, the
+    /// file as it was is first saved to `{id}.history/{timestamp}.md`, named by
+    /// the current UTC time.
+    ///
+    /// # Arguments
+    /// * `note` - Note whose frontmatter fields should be written
+    /// * `body` - Markdown body of the note
+    ///
+    /// # Returns
+    /// * `Result<(), std::io::Error>` - Success, or an IO error. Fails with
+    ///   `ErrorKind::NotFound` if no note with this ID exists.
+    pub fn update_note(&self, note: &Note, body: &str) -> std::io::Result<()> {
+        if self.modified_time(&note.id)?.is_none() {
+            return Err(note_not_found(&note.id));
+        }
+        let content = render_note_file(note, body)?;
+
+        if let Some(path) = self.note_path(&note.id).filter(|_| self.keep_history) {
+            let history_dir = path.with_extension(HISTORY_EXTENSION);
+            std::fs::create_dir_all(&history_dir)?;
+            let version = Utc::now().format(HISTORY_TIMESTAMP_FORMAT);
+            std::fs::copy(&path, history_dir.join(format!("{}Z.md", version)))?;
+        }
+
+        self.backend.write(&note.id, &content, false)?;
+
+        self.invalidate_cache(&note.id);
+        Ok(())
+    }
+
+    /// Deletes a note. On the filesystem, its `.assets` and `.history`
+    /// directories are removed too.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note to delete
+    ///
+    /// # Returns
+    /// * `Result<(), std::io::Error>` - Success, or an IO error. Fails with
+    ///   `ErrorKind::NotFound` if no note with this ID exists.
+    pub fn delete_note(&self, id: &str) -> std::io::Result<()> {
+        self.backend.delete(id)?;
+        self.invalidate_cache(id);
+        Ok(())
+    }
+
+    /// Renames a note through [`NoteBackend::rename`], which on the filesystem
+    /// moves its file along with its `.assets` and `.history` directories, and
+    /// updates the notes linking to it.
+    ///
+    /// Wikilinks to `old_id` in the markdown body of every note are pointed at
+    /// `new_id`, keeping their headings and display text, and so are `old_id`
+    /// entries of `references` lists. The rest of the frontmatter is kept byte
+    /// for byte, and no history versions are saved. Links through aliases keep
+    /// working and are left alone.
+    ///
+    /// The rewritten notes are worked out before anything changes, so a note
+    /// that can't be read stops the rename with nothing moved or rewritten.
+    ///
+    /// # Arguments
+    /// * `old_id` - Current ID of the note
+    /// * `new_id` - ID to rename the note to
+    ///
+    /// # Returns
+    /// * `Result<Vec<String>, std::io::Error>` - IDs of the notes whose links
+    ///   were updated, or an IO error. Fails with `ErrorKind::NotFound` if no
+    ///   note with `old_id` exists, `ErrorKind::InvalidInput` if the backend
+    ///   doesn't accept `new_id`, and `ErrorKind::AlreadyExists` if it's taken.
+    pub fn rename_note(&self, old_id: &str, new_id: &str) -> std::io::Result<Vec<String>> {
+        let mut rewrites = Vec::new();
+        for note_id in self.backend.list()? {
+            let Some(content) = self.backend.read(&note_id)? else {
+                continue;
+            };
+            // Files without frontmatter aren't notes
+            let Some(body_start) = frontmatter_len(&content) else {
+                continue;
+            };
+            let (frontmatter, body) = content.split_at(body_start);
+            let renamed = format!(
+                "{}{}",
+                rename_references(frontmatter, old_id, new_id),
+                rename_wikilinks(body, old_id, new_id)
+            );
+            if renamed != content {
+                rewrites.push((note_id, renamed));
+            }
+        }
+
+        self.backend.rename(old_id, new_id)?;
+        self.invalidate_cache(old_id);
+        self.invalidate_cache(new_id);
+
+        let mut updated = Vec::new();
+        for (note_id, content) in rewrites {
+            // The note's links to itself moved along with it
+            let note_id = if note_id == old_id {
+                new_id.to_string()
+            } else {
+                note_id
+            };
+            self.backend.write(&note_id, &content, false)?;
+            self.invalidate_cache(&note_id);
+            updated.push(note_id);
+        }
+        Ok(updated)
+    }
+
+    /// Lists the previous versions of a note saved by [`NoteStore::update_note`].
+    ///
+    /// Files in the note's `.history` directory that aren't named like a saved
+    /// version are left out. Aliases aren't resolved.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note
+    ///
+    /// # Returns
+    /// * `Result<Vec<NoteVersion>, std::io::Error>` - Saved versions, newest
+    ///   first, empty if there are none or the note doesn't exist, or IO error.
+    ///   Fails with `ErrorKind::Unsupported` if the backend doesn't store notes
+    ///   as files.
+    pub fn get_note_history(&self, id: &str) -> std::io::Result<Vec<NoteVersion>> {
+        if !self.backend.stores_files() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "note history needs notes stored as files",
+            ));
+        }
+        let Some(history_dir) = self
+            .note_path(id)
+            .map(|path| path.with_extension(HISTORY_EXTENSION))
+            .filter(|dir| dir.is_dir())
+        else {
+            return Ok(Vec::new());
+        };
+
+        let mut versions = Vec::new();
+        for entry in std::fs::read_dir(&history_dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "md") {
+                continue;
+            }
+            let Some(version) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let saved = version.strip_suffix('Z').and_then(|time| {
+                NaiveDateTime::parse_from_str(time, HISTORY_TIMESTAMP_FORMAT).ok()
+            });
+            if let Some(saved) = saved {
+                versions.push(NoteVersion {
+                    version: version.to_string(),
+                    saved: saved.and_utc(),
+                    size: entry.metadata()?.len(),
+                });
+            }
+        }
+
+        versions.sort_by_key(|version| std::cmp::Reverse(version.saved));
+        Ok(versions)
+    }
+
+    /// Resolves the path of an asset file belonging to a note.
+    ///
+    /// The asset name must be a plain file name inside the note's `.assets`
+    /// directory; names containing path separators or `.`/`..` are rejected.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note owning the asset
+    /// * `name` - File name of the asset
+    ///
+    /// # Returns
+    /// * `Option<PathBuf>` - Path to the asset file, or None if it doesn't exist,
+    ///   which is always the case when the backend doesn't store notes as files
+    pub fn asset_path(&self, id: &str, name: &str) -> Option<PathBuf> {
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return None;
+        }
+        let path = self.note_path(id)?.with_extension("assets").join(name);
+        path.is_file().then_some(path)
+    }
+
+    /// Returns the path of a PNG thumbnail of an image asset, generating it if needed.
+    ///
+    /// Thumbnails are cached in a `.thumbnails` directory inside the note's
+    /// `.assets` directory, which isn't listed as an asset, and regenerated
+    /// once the asset is newer than its thumbnail. The width is clamped to
+    /// [`MAX_THUMBNAIL_WIDTH`], and images narrower than it aren't upscaled.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note owning the asset
+    /// * `name` - File name of the asset
+    /// * `width` - Requested thumbnail width in pixels, keeping the aspect ratio
+    ///
+    /// # Returns
+    /// * `Result<Option<PathBuf>, std::io::Error>` - Path to the thumbnail, None if
+    ///   the asset doesn't exist, or an `Unsupported` error if it isn't an image
+    ///   that can be decoded
+    pub fn asset_thumbnail(
+        &self,
+        id: &str,
+        name: &str,
+        width: u32,
+    ) -> std::io::Result<Option<PathBuf>> {
+        let Some(path) = self.asset_path(id, name) else {
+            return Ok(None);
+        };
+        if mime_guess::from_path(&path).first_or_octet_stream().type_() != mime_guess::mime::IMAGE {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("{} is not an image", name),
+            ));
+        }
+
+        let width = width.clamp(1, MAX_THUMBNAIL_WIDTH);
+        let thumbnail_dir = path.with_file_name(THUMBNAIL_DIR);
+        let thumbnail_path = thumbnail_dir.join(format!("{}.{}.png", name, width));
+        // Without modification times there's no telling whether the thumbnail
+        // is current, so it's generated again
+        if let Ok(thumbnail_modified) = Self::get_file_modified_time(&thumbnail_path) {
+            if Self::get_file_modified_time(&path)
+                .is_ok_and(|modified| thumbnail_modified >= modified)
+            {
+                return Ok(Some(thumbnail_path));
+            }
+        }
+
+        let image = image::open(&path).map_err(|e| match e {
+            ImageError::IoError(e) => e,
+            ImageError::Unsupported(e) => std::io::Error::new(std::io::ErrorKind::Unsupported, e),
+            e => std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        })?;
+        let thumbnail = if image.width() > width {
+            image.thumbnail(width, u32::MAX)
+        } else {
+            image
+        };
+
+        // Encode in memory first, so a failure doesn't leave a partial file behind
+        let mut png = std::io::Cursor::new(Vec::new());
+        thumbnail
+            .write_to(&mut png, ImageFormat::Png)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::create_dir_all(&thumbnail_dir)?;
+        std::fs::write(&thumbnail_path, png.into_inner())?;
+        Ok(Some(thumbnail_path))
+    }
+
+    /// Returns the number of cached notes and the cache hit and miss counts.
+    ///
+    /// Hits and misses are counted per note lookup by [`NoteStore::get_note`],
+    /// [`NoteStore::list_notes`], and their async variants, so a listing
+    /// counts once for every note.
+    pub fn cache_stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.note_cache.read().len(),
+            hits: self.cache_hits.load(Ordering::Relaxed),
+            misses: self.cache_misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Clears the entire note cache
+    ///
+    /// This forces all subsequent note requests to reprocess the source files.
+    pub fn clear_cache(&self) {
+        let mut cache = self.note_cache.write();
+        cache.clear();
+        self.invalidate_indexes();
+    }
+
+    /// Removes a specific note from the cache
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note to remove from cache
+    pub fn invalidate_cache(&self, id: &str) {
+        let mut cache = self.note_cache.write();
+        cache.remove(id);
+        self.invalidate_indexes();
+    }
+
+    /// Drops the cached backlink and alias indexes so they are rebuilt on next use
+    fn invalidate_indexes(&self) {
+        self.backlinks.write().take();
+        self.aliases.write().take();
+    }
+
+    /// Returns the alias index, building it from the metadata of all notes if
+    /// it isn't cached.
+    ///
+    /// The index is kept until the cache is invalidated, as when a note is
+    /// reloaded, written, or changed on disk while the store is watched.
+    ///
+    /// An alias resolves only if exactly one note claims it and no note has it
+    /// as its ID; every other alias is recorded as a conflict and logged. Note
+    /// IDs that differ only in case are logged too.
+    fn alias_index(&self) -> std::io::Result<Arc<AliasIndex>> {
+        if let Some(index) = self.aliases.read().as_ref() {
+            return Ok(Arc::clone(index));
+        }
+
+        // Drafts can be fetched directly, so their aliases count too
+        let mut notes: Vec<(String, Vec<String>)> = Vec::new();
+        self.visit_metadata(true, |note| {
+            notes.push((note.id.clone(), note.aliases.clone()))
+        })?;
+        notes.sort();
+
+        // Collect the notes claiming each alias
+        let ids: HashSet<&str> = notes.iter().map(|(id, _)| id.as_str()).collect();
+        let mut claims: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (id, aliases) in &notes {
+            for alias in aliases {
+                let claimants = claims.entry(alias.as_str()).or_default();
+                if alias != id && !claimants.contains(&id.as_str()) {
+                    claimants.push(id);
+                }
+            }
+        }
+
+        let mut index = AliasIndex::default();
+        for (alias, claimants) in claims {
+            let ids: Vec<String> = match claimants.as_slice() {
+                [] => continue,
+                // A note ID always takes precedence over an alias
+                _ if ids.contains(alias) => std::iter::once(alias)
+                    .chain(claimants.iter().copied())
+                    .map(str::to_string)
+                    .collect(),
+                [id] => {
+                    index.targets.insert(alias.to_string(), id.to_string());
+                    continue;
+                }
+                _ => claimants.iter().map(|id| id.to_string()).collect(),
+            };
+            warn!(
+                "Alias {:?} is claimed by more than one note: {}",
+                alias,
+                ids.join(", ")
+            );
+            index.conflicts.push(AliasConflict {
+                alias: alias.to_string(),
+                ids,
+            });
+        }
+
+        for (id, _) in &notes {
+            index
+                .folded_ids
+                .entry(id.to_lowercase())
+                .or_default()
+                .push(id.clone());
+        }
+        for ids in index.folded_ids.values().filter(|ids| ids.len() > 1) {
+            warn!(
+                "Note IDs differ only in case, so they can only be requested exactly: {}",
+                ids.join(", ")
+            );
+        }
+
+        let index = Arc::new(index);
+        *self.aliases.write() = Some(Arc::clone(&index));
+        Ok(index)
+    }
+
+    /// Resolves an alias to the ID of the note claiming it.
+    ///
+    /// # Arguments
+    /// * `alias` - An alias listed in a note's frontmatter
+    ///
+    /// # Returns
+    /// * `Result<Option<String>, std::io::Error>` - The note's ID, None if no note
+    ///   unambiguously claims the alias, or IO error
+    pub fn resolve_alias(&self, alias: &str) -> std::io::Result<Option<String>> {
+        Ok(self.alias_index()?.targets.get(alias).cloned())
+    }
+
+    /// Resolves a name that isn't a note's exact ID to the note it refers to.
+    ///
+    /// Aliases are tried first, then a case-insensitive match against note IDs
+    /// (so `MyNote` finds `mynote`). A name matching several IDs that differ
+    /// only in case is ambiguous and doesn't resolve.
+    ///
+    /// # Arguments
+    /// * `name` - An alias or a differently cased note ID
+    ///
+    /// # Returns
+    /// * `Result<Option<String>, std::io::Error>` - The note's ID, None if the
+    ///   name doesn't resolve unambiguously, or IO error
+    pub fn resolve_id(&self, name: &str) -> std::io::Result<Option<String>> {
+        Ok(self.alias_index()?.resolve(name).map(str::to_string))
+    }
+
+    /// Finds aliases that don't resolve because they're claimed by more than
+    /// one note or are also a note's ID.
+    ///
+    /// # Returns
+    /// * `Result<Vec<AliasConflict>, std::io::Error>` - Conflicts ordered by alias, or IO error
+    pub fn alias_conflicts(&self) -> std::io::Result<Vec<AliasConflict>> {
+        Ok(self.alias_index()?.conflicts.clone())
+    }
+
+    /// Returns metadata for every note that references the given note.
+    ///
+    /// The reverse reference index is built from the metadata of all published
+    /// notes on first use, without rendering them, and cached until a note is
+    /// reprocessed or the cache is invalidated.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note to find backlinks for
+    ///
+    /// # Returns
+    /// * `Result<Vec<NoteMetadata>, std::io::Error>` - Notes referencing `id`,
+    ///   sorted by ID, or IO error
+    pub fn get_backlinks(&self, id: &str) -> std::io::Result<Vec<NoteMetadata>> {
+        if let Some(index) = self.backlinks.read().as_ref() {
+            return Ok(index.get(id).cloned().unwrap_or_default());
+        }
+
+        // Build the reverse index: target ID -> notes that reference it
+        let mut index: HashMap<String, Vec<NoteMetadata>> = HashMap::new();
+        self.visit_metadata(false, |note| {
+            for target in &note.references {
+                index
+                    .entry(target.clone())
+                    .or_default()
+                    .push(NoteMetadata::from(note));
+            }
+        })?;
+        for sources in index.values_mut() {
+            sources.sort_by(|a, b| a.id.cmp(&b.id));
+        }
+
+        let backlinks = index.get(id).cloned().unwrap_or_default();
+        *self.backlinks.write() = Some(index);
+        Ok(backlinks)
+    }
+
+    /// Suggests notes related to the given note by the tags they share.
+    ///
+    /// Other notes are ranked by the number of tags they have in common with
+    /// the note, ties ordered by ID. Notes without shared tags, and notes the
+    /// note already references, are left out. Only metadata is needed, so
+    /// uncached notes aren't rendered.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note to find related notes for
+    /// * `limit` - Maximum number of notes to return
+    ///
+    /// # Returns
+    /// * `Result<Vec<NoteMetadata>, std::io::Error>` - The most related notes, empty
+    ///   if the note doesn't exist, or IO error
+    pub fn related_notes(&self, id: &str, limit: usize) -> std::io::Result<Vec<NoteMetadata>> {
+        let notes = self.list_metadata(false)?;
+        let Some(note) = notes.iter().find(|note| note.id == id) else {
+            return Ok(Vec::new());
+        };
+
+        // Tags may keep their casing, but they're compared case-insensitively
+        let tags: HashSet<String> = note.tags.iter().map(|tag| tag.to_lowercase()).collect();
+
+        let mut scored: Vec<(usize, &NoteMetadata)> = notes
+            .iter()
+            .filter(|other| other.id != id && !note.references.contains(&other.id))
+            .filter_map(|other| {
+                let shared = other
+                    .tags
+                    .iter()
+                    .filter(|tag| tags.contains(&tag.to_lowercase()))
+                    .count();
+                (shared > 0).then_some((shared, other))
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.id.cmp(&b.1.id)));
+        Ok(scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, other)| other.clone())
+            .collect())
+    }
+
+    /// Finds mentions of a note's title in other notes that don't link to it.
+    ///
+    /// Every other published note that doesn't reference the note, through its
+    /// references, a wikilink, or a markdown link to its page (by ID, alias, or
+    /// differently cased ID), has its markdown body searched for the title.
+    /// Matches are case-insensitive and must be whole words; code blocks and
+    /// inline code aren't searched. Notes whose frontmatter doesn't parse can't
+    /// be told apart from drafts, so they're skipped with a warning.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note whose title to look for
+    ///
+    /// # Returns
+    /// * `Result<Vec<UnlinkedMention>, std::io::Error>` - Each mention with the
+    ///   text around it, ordered by note ID and position, empty if the note
+    ///   doesn't exist, or IO error
+    pub fn unlinked_mentions(&self, id: &str) -> std::io::Result<Vec<UnlinkedMention>> {
+        let Some(target) = self.get_note_metadata(id)? else {
+            return Ok(Vec::new());
+        };
+        let title = target.title.trim();
+        if title.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // `\b` only applies next to word characters, so titles like "C++" still match
+        let boundary = |c: Option<char>| match c {
+            Some(c) if c.is_alphanumeric() || c == '_' => r"\b",
+            _ => "",
+        };
+        let pattern = format!(
+            "(?i){}{}{}",
+            boundary(title.chars().next()),
+            regex::escape(title),
+            boundary(title.chars().last())
+        );
+        let (mention_regex, link_regex) =
+            match (Regex::new(&pattern), Regex::new(r"\]\(\s*/([^)\s#?]+)")) {
+                (Ok(mention), Ok(link)) => (mention, link),
+                _ => return Ok(Vec::new()),
+            };
+
+        let aliases = self.alias_index()?;
+        let links_to_target =
+            |name: &str| name == target.id || aliases.resolve(name) == Some(target.id.as_str());
+
+        let mut mentions = Vec::new();
+        for note_id in self.backend.list()? {
+            if note_id == target.id {
+                continue;
+            }
+            let parsed = self
+                .read_note(&note_id)
+                .and_then(|content| Ok(parse_frontmatter(&content)?));
+            let (data, body) = match parsed {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    warn!("Skipping note {}: {}", note_id, e);
+                    continue;
+                }
+            };
+            if data.draft {
+                continue;
+            }
+
+            let (_, wikilink_targets) = process_wikilinks(&body);
+            let linked = data
+                .references
+                .iter()
+                .chain(&wikilink_targets)
+                .map(String::as_str)
+                .chain(
+                    link_regex
+                        .captures_iter(&body)
+                        .filter_map(|caps| caps.get(1).map(|m| m.as_str())),
+                )
+                .any(links_to_target);
+            if linked {
+                continue;
+            }
+
+            map_markdown_prose(&body, |segment| {
+                for found in mention_regex.find_iter(segment) {
+                    mentions.push(UnlinkedMention {
+                        note_id: note_id.clone(),
+                        snippet: mention_snippet(segment, found.start(), found.end()),
+                    });
+                }
+                segment.to_string()
+            });
+        }
+
+        mentions.sort_by(|a, b| a.note_id.cmp(&b.note_id));
+        Ok(mentions)
+    }
+
+    /// Lists every tag used by a note along with how many notes use it.
+    ///
+    /// # Returns
+    /// * `Result<Vec<(String, usize)>, std::io::Error>` - Tags and their note counts
+    ///   ordered by tag, or IO error
+    pub fn list_tags(&self) -> std::io::Result<Vec<(String, usize)>> {
+        // Keyed by lowercased tag, since casing may be preserved per note
+        let mut counts: BTreeMap<String, (String, usize)> = BTreeMap::new();
+        for note in self.list_notes()? {
+            for tag in note.tags {
+                counts.entry(tag.to_lowercase()).or_insert((tag, 0)).1 += 1;
+            }
+        }
+        Ok(counts.into_values().collect())
+    }
+
+    /// Maps every tag used by a published note to the IDs of the notes using it.
+    ///
+    /// Tags differing only in case are merged under the first casing found,
+    /// like in [`NoteStore::list_tags`].
+    ///
+    /// # Returns
+    /// * `Result<HashMap<String, Vec<String>>, std::io::Error>` - Note IDs, sorted,
+    ///   by tag, or IO error
+    pub fn tag_index(&self) -> std::io::Result<HashMap<String, Vec<String>>> {
+        // Keyed by lowercased tag, since casing may be preserved per note
+        let mut index: HashMap<String, (String, Vec<String>)> = HashMap::new();
+        // Notes are listed sorted by ID, so each list of IDs comes out sorted
+        for note in self.list_notes()? {
+            for tag in note.tags {
+                index
+                    .entry(tag.to_lowercase())
+                    .or_insert_with(|| (tag, Vec::new()))
+                    .1
+                    .push(note.id.clone());
+            }
+        }
+        Ok(index.into_values().collect())
+    }
+
+    /// Builds the tag hierarchy from every tag used by a note.
+    ///
+    /// Tags are split into levels on `/`, so `project/miniref` counts towards
+    /// both `project` and `project/miniref`. A note is counted once per level,
+    /// however many of its tags fall under it. Tags without `/` are top-level.
+    ///
+    /// # Returns
+    /// * `Result<TagNode, std::io::Error>` - The root of the hierarchy, counting
+    ///   every tagged note, or IO error
+    pub fn tag_tree(&self) -> std::io::Result<TagNode> {
+        // Keyed by lowercased tag, since casing may be preserved per note
+        let mut counts: BTreeMap<String, (String, usize)> = BTreeMap::new();
+        let mut tagged = 0;
+        for note in self.list_notes()? {
+            let mut levels: BTreeMap<String, String> = BTreeMap::new();
+            for tag in &note.tags {
+                let segments: Vec<&str> = tag
+                    .split('/')
+                    .map(str::trim)
+                    .filter(|segment| !segment.is_empty())
+                    .collect();
+                for depth in 1..=segments.len() {
+                    let path = segments[..depth].join("/");
+                    levels.entry(path.to_lowercase()).or_insert(path);
+                }
+            }
+            if !levels.is_empty() {
+                tagged += 1;
+            }
+            for (key, path) in levels {
+                counts.entry(key).or_insert((path, 0)).1 += 1;
+            }
+        }
+
+        Ok(TagNode {
+            count: tagged,
+            children: tag_children(&counts, ""),
+            ..Default::default()
+        })
+    }
+
+    /// Finds references that point at notes which don't exist.
+    ///
+    /// Checks both frontmatter references and wikilink targets, since both
+    /// are merged into each note's `references`. Targets are checked by file
+    /// existence, so a reference to a note that fails to parse isn't broken.
+    /// Neither is a reference to an alias or a differently cased ID that resolves.
+    ///
+    /// # Returns
+    /// * `Result<Vec<BrokenRef>, std::io::Error>` - Broken references ordered by
+    ///   source note, or IO error
+    pub fn validate_references(&self) -> std::io::Result<Vec<BrokenRef>> {
+        let aliases = self.alias_index()?;
+        let notes = self.list_notes()?;
+
+        Ok(notes
+            .iter()
+            .flat_map(|note| {
+                note.references
+                    .iter()
+                    .filter(|target| !self.note_exists(target) && aliases.resolve(target).is_none())
+                    .map(|target| BrokenRef {
+                        from: note.id.clone(),
+                        to: target.clone(),
+                    })
+            })
+            .collect())
+    }
+
+    /// Lists every note with its modification time, for building a sitemap.
+    ///
+    /// Drafts are left out. Notes that fail to parse are included.
+    ///
+    /// # Returns
+    /// * `Result<Vec<(String, Option<SystemTime>)>, std::io::Error>` - Note IDs
+    ///   and modification times ordered by ID, None where the backend can't
+    ///   tell, or IO error
+    pub fn sitemap_entries(&self) -> std::io::Result<Vec<(String, Option<SystemTime>)>> {
+        // Only the frontmatter is needed to tell drafts apart
+        let mut drafts = HashSet::new();
+        self.visit_metadata(true, |note| {
+            if note.draft {
+                drafts.insert(note.id.clone());
+            }
+        })?;
+        let mut entries = Vec::new();
+        for id in self.backend.list()? {
+            if drafts.contains(&id) {
+                continue;
+            }
+            // Skip notes deleted since they were listed
+            if let Some(modified) = self.modified_time(&id)? {
+                entries.push((id, modified));
+            }
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(entries)
+    }
+
+    /// Finds notes that currently fail to parse.
+    ///
+    /// # Returns
+    /// * `Result<Vec<ParseFailure>, std::io::Error>` - Failing notes ordered by ID,
+    ///   or IO error if the notes can't be listed
+    pub fn parse_errors(&self) -> std::io::Result<Vec<ParseFailure>> {
+        let mut failures: Vec<ParseFailure> = self
+            .backend
+            .list()?
+            .into_iter()
+            .filter_map(|id| {
+                let error = self.get_note(&id).err()?;
+                Some(ParseFailure {
+                    id,
+                    error: error.to_string(),
+                })
+            })
+            .collect();
+
+        failures.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(failures)
+    }
+
+    /// Writes every note and its assets to a zip archive.
+    ///
+    /// Notes are stored under their ID with an `.md` extension (e.g.
+    /// `projects/foo.md`) followed by the files in their `.assets` directory.
+    /// Notes are read one at a time and asset files copied into the archive as
+    /// they're read, so notes are never all held in memory at once. Only
+    /// backends that store notes as files have assets to export.
+    ///
+    /// # Arguments
+    /// * `writer` - Destination of the archive. Zip entries are finalized by
+    ///   seeking back to their headers, so this must be seekable (e.g. a file).
+    ///
+    /// # Returns
+    /// * `Result<W, std::io::Error>` - The writer after the archive is complete, or IO error
+    pub fn export_to_writer<W: Write + Seek>(&self, writer: W) -> std::io::Result<W> {
+        let mut archive = zip::ZipWriter::new(writer);
+        let options = zip::write::SimpleFileOptions::default();
+
+        let mut ids = self.backend.list()?;
+        ids.sort();
+        for id in ids {
+            // Skip notes deleted since they were listed
+            let Some(content) = self.backend.read(&id)? else {
+                continue;
+            };
+            archive.start_file(format!("{}.md", id), options)?;
+            archive.write_all(content.as_bytes())?;
+
+            // Assets are a flat directory of files next to the note
+            let Some(assets_dir) = self
+                .note_path(&id)
+                .map(|path| path.with_extension("assets"))
+                .filter(|dir| dir.is_dir())
+            else {
+                continue;
+            };
+            let mut assets = Vec::new();
+            for entry in std::fs::read_dir(&assets_dir)? {
+                let entry = entry?;
+                if entry.file_type()?.is_file() {
+                    assets.push(entry.path());
+                }
+            }
+            assets.sort();
+            for asset in assets {
+                let Some(name) = asset.file_name().and_then(|name| name.to_str()) else {
+                    continue;
+                };
+                archive.start_file(format!("{}.assets/{}", id, name), options)?;
+                std::io::copy(&mut std::fs::File::open(&asset)?, &mut archive)?;
+            }
+        }
+
+        Ok(archive.finish()?)
+    }
+
+    /// Computes aggregate numbers over all published notes.
+    ///
+    /// A note counts as an orphan if none of its references point at another
+    /// existing note and no other note references it. References to missing
+    /// notes still count towards `total_references`.
+    ///
+    /// # Returns
+    /// * `Result<CorpusStats, std::io::Error>` - The numbers, or IO error
+    pub fn corpus_stats(&self) -> std::io::Result<CorpusStats> {
+        let notes = self.list_notes()?;
+        let ids: HashSet<&str> = notes.iter().map(|note| note.id.as_str()).collect();
+
+        // Outgoing links, and the reverse index of notes that are linked to
+        let mut linking: HashSet<&str> = HashSet::new();
+        let mut linked: HashSet<&str> = HashSet::new();
+        for note in &notes {
+            for target in &note.references {
+                if *target != note.id && ids.contains(target.as_str()) {
+                    linking.insert(&note.id);
+                    linked.insert(target);
+                }
+            }
+        }
+
+        let tags: HashSet<String> = notes
+            .iter()
+            .flat_map(|note| note.tags.iter().map(|tag| tag.to_lowercase()))
+            .collect();
+        let total_words: usize = notes.iter().map(|note| note.word_count).sum();
+
+        Ok(CorpusStats {
+            total_notes: notes.len(),
+            total_tags: tags.len(),
+            total_references: notes.iter().map(|note| note.references.len()).sum(),
+            orphan_notes: notes
+                .iter()
+                .filter(|note| {
+                    !linking.contains(note.id.as_str()) && !linked.contains(note.id.as_str())
+                })
+                .count(),
+            average_word_count: if notes.is_empty() {
+                0.0
+            } else {
+                total_words as f64 / notes.len() as f64
+            },
+        })
+    }
+
+    /// Builds the graph of references between notes.
+    ///
+    /// Every note becomes a node, and every reference to an existing note
+    /// becomes an edge. References to missing notes are left out; use
+    /// [`NoteStore::validate_references`] to find them. Only metadata is
+    /// needed, so uncached notes aren't rendered.
+    ///
+    /// # Returns
+    /// * `Result<ReferenceGraph, std::io::Error>` - Nodes and edges ordered by note ID, or IO error
+    pub fn reference_graph(&self) -> std::io::Result<ReferenceGraph> {
+        let notes = self.list_metadata(false)?;
+        let ids: HashSet<&str> = notes.iter().map(|note| note.id.as_str()).collect();
+
+        let nodes = notes
+            .iter()
+            .map(|note| GraphNode {
+                id: note.id.clone(),
+                title: note.title.clone(),
+            })
+            .collect();
+        let edges = notes
+            .iter()
+            .flat_map(|note| {
+                note.references
+                    .iter()
+                    .filter(|target| ids.contains(target.as_str()))
+                    .map(|target| GraphEdge {
+                        source: note.id.clone(),
+                        target: target.clone(),
+                    })
+            })
+            .collect();
+
+        Ok(ReferenceGraph { nodes, edges })
+    }
+
+    /// Finds groups of notes that reference each other in a cycle.
+    ///
+    /// Each group is a strongly connected component of the reference graph
+    /// with more than one note, so every note in it can reach every other by
+    /// following references. A note that references itself is reported as a
+    /// group of one. References to missing notes are ignored, like in
+    /// [`NoteStore::reference_graph`].
+    ///
+    /// # Returns
+    /// * `Result<Vec<Vec<String>>, std::io::Error>` - IDs of the notes in each
+    ///   cycle, sorted within and across groups, or IO error
+    pub fn reference_cycles(&self) -> std::io::Result<Vec<Vec<String>>> {
+        let notes = self.list_notes()?;
+        let positions: HashMap<&str, usize> = notes
+            .iter()
+            .enumerate()
+            .map(|(position, note)| (note.id.as_str(), position))
+            .collect();
+        let edges: Vec<Vec<usize>> = notes
+            .iter()
+            .map(|note| {
+                note.references
+                    .iter()
+                    .filter_map(|target| positions.get(target.as_str()).copied())
+                    .collect()
+            })
+            .collect();
+
+        let mut cycles: Vec<Vec<String>> = strongly_connected_components(&edges)
+            .into_iter()
+            .filter(|component| component.len() > 1 || edges[component[0]].contains(&component[0]))
+            .map(|component| {
+                let mut ids: Vec<String> = component
+                    .into_iter()
+                    .map(|position| notes[position].id.clone())
+                    .collect();
+                ids.sort();
+                ids
+            })
+            .collect();
+        cycles.sort();
+        Ok(cycles)
+    }
+
+    /// Searches notes by title, tags, and raw markdown body.
+    ///
+    /// Matching is a case-insensitive substring search. Results are ranked with
+    /// title matches first, then tag matches, then body matches; notes within
+    /// the same rank are ordered by title.
+    ///
+    /// # Arguments
+    /// * `query` - Text to search for
+    ///
+    /// # Returns
+    /// * `Result<Vec<NoteMetadata>, std::io::Error>` - Matching notes or IO error
+    pub fn search_notes(&self, query: &str) -> std::io::Result<Vec<NoteMetadata>> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut matches: Vec<(u8, NoteMetadata)> = Vec::new();
+        for note in self.list_metadata(false)? {
+            let rank = if note.title.to_lowercase().contains(&query) {
+                0
+            } else if note
+                .tags
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(&query))
+            {
+                1
+            } else if self
+                .search_body(&note.id)?
+                .is_some_and(|body| body.to_lowercase().contains(&query))
+            {
+                2
+            } else {
+                continue;
+            };
+            matches.push((rank, note));
+        }
+
+        matches.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.title.cmp(&b.1.title)));
+        Ok(matches.into_iter().map(|(_, meta)| meta).collect())
+    }
+
+    /// Returns a note's markdown body, without frontmatter, for full-text search.
+    ///
+    /// The body is taken from the cache if the note's entry is still valid and
+    /// read from the backend otherwise, so notes evicted from the cache are
+    /// searched too.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note
+    ///
+    /// # Returns
+    /// * `Result<Option<String>, std::io::Error>` - The body, None if the note
+    ///   no longer exists, or IO error
+    fn search_body(&self, id: &str) -> std::io::Result<Option<String>> {
+        if let Some(cached) = self.note_cache.read().peek(id) {
+            if self.is_cache_valid(id, cached) {
+                return Ok(Some(cached.body.clone()));
+            }
+        }
+        Ok(self
+            .backend
+            .read(id)?
+            .map(|content| markdown_body(&content)))
+    }
+
+    /// Builds KaTeX options with the configured macros.
+    ///
+    /// # Arguments
+    /// * `display_mode` - Whether to render display math rather than inline math
+    fn katex_opts(&self, display_mode: bool) -> Result<Opts, ParseError> {
+        let mut builder = self
+            .katex_macros
+            .iter()
+            .fold(Opts::builder(), |builder, (name, expansion)| {
+                builder.add_macro(name.clone(), expansion.clone())
+            });
+        builder
+            .display_mode(display_mode)
+            .output_type(katex::OutputType::Html)
+            .build()
+            .map_err(|e| ParseError::Katex(e.to_string()))
+    }
+
+    /// Highlights a code block, marking up its tokens with classes.
+    ///
+    /// Tokens get `syn-`-prefixed classes named after their syntect scopes,
+    /// colored by the stylesheet from [`NoteStore::highlight_css`], so the
+    /// rendered HTML doesn't depend on the theme.
+    ///
+    /// # Arguments
+    /// * `language` - Language token from the code fence (e.g. `rust`)
+    /// * `code` - Source code of the block, with HTML entities already decoded
+    ///
+    /// # Returns
+    /// * `Option<String>` - A `<pre class="syn-code"><code class="language-…">` block
+    ///   of highlighted HTML, or None if the language is unknown or highlighting fails
+    fn highlight_code_block(&self, language: &str, code: &str) -> Option<String> {
+        let syntax = self.syntax_set.find_syntax_by_token(language)?;
+        let mut generator = ClassedHTMLGenerator::new_with_class_style(
+            syntax,
+            &self.syntax_set,
+            HIGHLIGHT_CLASS_STYLE,
+        );
+        for line in LinesWithEndings::from(code) {
+            generator
+                .parse_html_for_line_which_includes_newline(line)
+                .ok()?;
+        }
+        Some(format!(
+            r#"<pre class="syn-code"><code class="language-{}">{}</code></pre>"#,
+            language,
+            generator.finalize()
+        ))
+    }
+
+    /// Returns the stylesheet coloring highlighted code with the configured theme.
+    ///
+    /// # Returns
+    /// * `Result<String, std::io::Error>` - CSS for the classes added by
+    ///   highlighting, or an error if the theme can't be converted
+    pub fn highlight_css(&self) -> std::io::Result<String> {
+        let theme = self.theme_set.themes.get(&self.theme_name).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("unknown syntax highlighting theme {:?}", self.theme_name),
+            )
+        })?;
+        css_for_theme_with_class_style(theme, HIGHLIGHT_CLASS_STYLE).map_err(std::io::Error::other)
+    }
+
+    /// Parses and processes a note's raw content into a structured Note object.
+    ///
+    /// This function handles:
+    /// 1. Frontmatter extraction (YAML, TOML, or JSON)
+    /// 2. Wikilink rewriting (`[[target]]` and `[[target|Display Text]]`)
+    /// 3. Markdown to HTML conversion (GitHub-flavored, including footnotes and task lists)
+    /// 4. Asset scanning and rewriting of relative asset links
+    /// 5. Heading anchors and table of contents generation
+    /// 6. Syntax highlighting for code blocks
+    /// 7. LaTeX math rendering
+    /// 8. Embedding the notes named by `![[target]]`
+    ///
+    /// # Arguments
+    /// * `id` - ID the note is addressed by
+    /// * `content` - Raw note content including frontmatter
+    ///
+    /// # Returns
+    /// * `Result<Note, ParseError>` - Parsed and processed note, or why parsing failed
+    fn parse_note(&self, id: &str, content: &str) -> Result<Note, ParseError> {
+        self.render_note(id, content, &[])
+    }
+
+    /// Processes a note like [`NoteStore::parse_note`], as embedded by other notes.
+    ///
+    /// # Arguments
+    /// * `id` - ID the note is addressed by
+    /// * `content` - Raw note content including frontmatter
+    /// * `embedders` - IDs of the notes this note is embedded in, outermost
+    ///   first; empty when rendering the note itself
+    ///
+    /// # Returns
+    /// * `Result<Note, ParseError>` - Parsed and processed note, or why parsing failed
+    fn render_note(
+        &self,
+        id: &str,
+        content: &str,
+        embedders: &[String],
+    ) -> Result<Note, ParseError> {
+        // Parse frontmatter and content
+        let (data, body) = parse_frontmatter(content)?;
+
+        let source = self.include_source.then(|| body.clone());
+
+        // Mark `![[embeds]]` before wikilinks, which would read them as images
+        let (markdown_body, embed_targets) = process_embeds(&body);
+
+        // Rewrite [[wikilinks]] into regular markdown links before rendering
+        let (markdown_body, wikilink_targets) = process_wikilinks(&markdown_body);
+
+        // Keep `\$` escapes (and `\(`-style delimiters) intact through markdown
+        // rendering so math processing sees them; without math, markdown
+        // unescapes them itself
+        let markdown_body = if data.math {
+            preserve_math_escapes(&markdown_body, self.math_delimiters)
+        } else {
+            markdown_body
+        };
+
+        // Convert Markdown to HTML with the store's constructs, GitHub-flavored
+        // by default. Footnotes render as `[^label]` references linking to a
+        // `user-content-fn-{label}` list item, which links back to each
+        // reference (`user-content-fnref-{label}`, then `-2`, `-3`, ... for repeats)
+        let options = self.markdown.options();
+        let html_output = match to_html_with_options(&markdown_body, &options) {
+            Ok(html) => html,
+            Err(e) => return Err(ParseError::Markdown(e.to_string())),
+        };
+
+        // Tag task list checkboxes with their state and line in the note file
+        let body_first_line = content
+            .strip_suffix(body.as_str())
+            .map_or(0, |frontmatter| frontmatter.matches('\n').count());
+        let html_output = process_task_lists(&html_output, &body, body_first_line);
+
+        // Turn `> [!NOTE]`-style blockquotes into callouts
+        let html_output = process_callouts(&html_output);
+
+        // Scan for associated assets if the note is stored as a file
+        let note_path = self.note_path(id);
+        let assets = note_path.as_deref().map(scan_assets).unwrap_or_default();
+
+        // Notes are addressed by their ID in the backend, which is
+        // authoritative over the frontmatter id
+        if !data.id.is_empty() && data.id != id {
+            // A declared ID that belongs to another note would otherwise go unnoticed
+            let owner = if self.note_exists(&data.id) {
+                ", the ID of another note,"
+            } else {
+                ""
+            };
+            let location = note_path
+                .as_ref()
+                .map_or_else(|| id.to_string(), |path| path.display().to_string());
+            warn!(
+                "Note {} declares id {:?}{} but is addressed as {:?}",
+                location, data.id, owner, id
+            );
+        }
+        let note_id = id.to_string();
+        if note_id.is_empty() {
+            return Err(ParseError::MissingField("id"));
+        }
+
+        // Point relative image/link URLs that name an asset at the asset endpoint
+        let html_output = rewrite_asset_links(&html_output, &self.api_prefix, &note_id, &assets);
+
+        // Give headings slug IDs and collect them into a table of contents
+        let (html_output, toc) = process_headings(&html_output);
+
+        // Notes without a frontmatter title are named after their first heading or ID
+        let title = note_title(
+            &data.title,
+            first_heading(&html_output).as_deref(),
+            &note_id,
+        );
+
+        // Regular expression for finding code blocks, which span several lines
+        let code_block_regex =
+            match Regex::new(r#"(?s)<pre><code class="language-([^"]+)">(.*?)</code></pre>"#) {
+                Ok(re) => re,
+                Err(e) => return Err(ParseError::Highlight(e.to_string())),
+            };
+
+        // Process code blocks with syntax highlighting, keeping blocks in
+        // unknown languages as rendered by markdown
+        let highlighted = code_block_regex.replace_all(&html_output, |caps: &regex::Captures| {
+            let code = html_escape::decode_html_entities(&caps[2]);
+            self.highlight_code_block(&caps[1], &code)
+                .unwrap_or_else(|| caps[0].to_string())
+        });
+
+        // Process display ($$ or \[) and inline ($ or \() LaTeX math expressions
+        // in a single pass, unless the note opts out with `math: false`
+        let final_content = if data.math {
+            let katex_opts = self.katex_opts(false)?;
+            let display_opts = self.katex_opts(true)?;
+            process_math(
+                &highlighted,
+                &katex_opts,
+                &display_opts,
+                self.math_delimiters,
+            )
+        } else {
+            highlighted.into_owned()
+        };
+
+        // Typographic punctuation goes in last, once code and math are marked up
+        // and can be skipped
+        let (final_content, toc) = if self.smart_typography {
+            let toc = toc
+                .into_iter()
+                .map(|entry| TocEntry {
+                    text: smarten_typography(&entry.text),
+                    ..entry
+                })
+                .collect();
+            (smarten_typography(&final_content), toc)
+        } else {
+            (final_content, toc)
+        };
+
+        // Summarize the rendered content once, so previews don't re-derive it
+        let excerpt = truncate_words(&strip_tags(&strip_katex(&final_content)), EXCERPT_LENGTH);
+
+        // Count only prose: code blocks and math aren't read word by word
+        let word_count = strip_tags(&strip_code_blocks(&strip_katex(&final_content)))
+            .split_whitespace()
+            .count();
+        let reading_time_minutes = word_count.div_ceil(WORDS_PER_MINUTE) as u32;
+
+        // Inline embedded notes last, so they're rendered with their own
+        // settings and don't count towards this note's excerpt and length
+        let final_content = self.inline_embeds(&final_content, &note_id, &embed_targets, embedders);
+
+        // Fall back to stored timestamps when the frontmatter doesn't provide them
+        let (created, modified) = self.note_timestamps(id, &data);
+
+        let references = merge_references(
+            merge_references(data.references, embed_targets),
+            wikilink_targets,
+        );
+
+        // Fingerprint everything the note is built from: the file itself, its
+        // rendering (which depends on the store's macros), its assets and
+        // timestamps, and the settings that shape the other fields
+        let hash = {
+            let mut hasher = DefaultHasher::new();
+            (&note_id, content, &final_content, &created, &modified).hash(&mut hasher);
+            (self.preserve_tag_case, self.include_source).hash(&mut hasher);
+            for asset in &assets {
+                asset.name.hash(&mut hasher);
+            }
+            format!("{:016x}", hasher.finish())
+        };
+
+        // Construct the final note object
+        Ok(Note {
+            id: note_id,
+            title,
+            content: final_content,
+            excerpt,
+            word_count,
+            reading_time_minutes,
+            tags: normalize_tags(&data.tags, self.preserve_tag_case),
+            references,
+            aliases: data.aliases,
+            assets,
+            toc,
+            created,
+            modified,
+            math: data.math,
+            draft: data.draft,
+            layout: data.layout,
+            hash,
+            source,
+        })
+    }
+
+    /// Replaces the embed links marked by [`process_embeds`] with the content
+    /// of the notes they name.
+    ///
+    /// Each embedded note is rendered from its file, with its own embeds, and
+    /// wrapped in `<section class="embed" data-embed="{id}">`; an embed alone
+    /// in a paragraph replaces the paragraph. Embeds of missing notes, of the
+    /// note itself or a note embedding it, and beyond the store's embed depth
+    /// are left as plain links. Aliases aren't resolved.
+    ///
+    /// # Arguments
+    /// * `html` - Rendered HTML of the note
+    /// * `note_id` - ID of the note being rendered
+    /// * `targets` - Embedded note IDs, indexed by the marks in the HTML
+    /// * `embedders` - IDs of the notes the note is embedded in, outermost first
+    ///
+    /// # Returns
+    /// * `String` - The HTML with embedded notes inlined
+    fn inline_embeds(
+        &self,
+        html: &str,
+        note_id: &str,
+        targets: &[String],
+        embedders: &[String],
+    ) -> String {
+        if targets.is_empty() {
+            return html.to_string();
+        }
+        let embed_regex = match Regex::new(&format!(
+            r#"(?s)(<p>)?<a href="([^"]*)" title="{}(\d+)">(.*?)</a>(</p>)?"#,
+            EMBED_TITLE_PREFIX
+        )) {
+            Ok(re) => re,
+            Err(_) => return html.to_string(),
+        };
+
+        let depth = embedders.len() + 1;
+        let embedders: Vec<String> = embedders
+            .iter()
+            .cloned()
+            .chain(std::iter::once(note_id.to_string()))
+            .collect();
+
+        embed_regex
+            .replace_all(html, |caps: &regex::Captures| {
+                let open = caps.get(1).map_or("", |m| m.as_str());
+                let close = caps.get(5).map_or("", |m| m.as_str());
+                let embedded = caps[3]
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| targets.get(index))
+                    .filter(|target| depth <= self.embed_depth && !embedders.contains(*target))
+                    .and_then(|target| {
+                        let rendered = match self.backend.read(target) {
+                            Ok(Some(content)) => self
+                                .render_note(target, &content, &embedders)
+                                .map_err(std::io::Error::from),
+                            // Missing notes are left as links
+                            Ok(None) => return None,
+                            Err(e) => Err(e),
+                        };
+                        match rendered {
+                            Ok(note) => Some(format!(
+                                "<section class=\"embed\" data-embed=\"{}\">{}</section>",
+                                html_escape::encode_double_quoted_attribute(target),
+                                note.content
+                            )),
+                            Err(e) => {
+                                warn!("Failed to embed note {}: {}", target, e);
+                                None
+                            }
+                        }
+                    });
+                match embedded {
+                    // A section can't be inside a paragraph, so it takes the paragraph's place
+                    Some(section) if !open.is_empty() && !close.is_empty() => section,
+                    Some(section) => format!("{}{}{}", open, section, close),
+                    None => format!("{}<a href=\"{}\">{}</a>{}", open, &caps[2], &caps[4], close),
+                }
+            })
+            .into_owned()
+    }
+
+    /// Parses a note's frontmatter without rendering its markdown.
+    ///
+    /// The returned note has the fields frontmatter sets, references including
+    /// its wikilink and embed targets, and timestamps falling back to stored
+    /// ones, but no content, excerpt, table of contents, assets, or hash, even
+    /// if the frontmatter names them. A missing title
+    /// falls back like in [`NoteStore::parse_note`], but a heading's markdown
+    /// is used as written.
+    ///
+    /// # Arguments
+    /// * `id` - ID the note is addressed by
+    /// * `content` - Raw content of the note
+    ///
+    /// # Returns
+    /// * `Result<Note, ParseError>` - The note's metadata, or why parsing failed
+    fn parse_note_metadata(&self, id: &str, content: &str) -> Result<Note, ParseError> {
+        let (data, body) = parse_frontmatter(content)?;
+        let (markdown_body, embed_targets) = process_embeds(&body);
+        let (_, wikilink_targets) = process_wikilinks(&markdown_body);
+        let (created, modified) = self.note_timestamps(id, &data);
+
+        Ok(Note {
+            id: id.to_string(),
+            title: note_title(&data.title, first_markdown_heading(&body).as_deref(), id),
+            tags: normalize_tags(&data.tags, self.preserve_tag_case),
+            references: merge_references(
+                merge_references(data.references, embed_targets),
+                wikilink_targets,
+            ),
+            aliases: data.aliases,
+            created,
+            modified,
+            math: data.math,
+            draft: data.draft,
+            layout: data.layout,
+            ..Default::default()
+        })
+    }
+
+    /// Returns a note's created and modified times, falling back to its file's
+    /// timestamps, then to the backend's modification time, for those its
+    /// frontmatter doesn't provide.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the note
+    /// * `data` - The note's frontmatter
+    fn note_timestamps(
+        &self,
+        id: &str,
+        data: &Note,
+    ) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+        let (created, modified) = note_timestamps(data, self.note_path(id).as_deref());
+        let modified = modified.or_else(|| {
+            self.backend
+                .modified_time(id)
+                .ok()
+                .flatten()
+                .map(DateTime::<Utc>::from)
+        });
+        (created, modified)
+    }
+}
+
+#[cfg(feature = "ssr")]
+/// Returns the text around a match, up to [`MENTION_CONTEXT`] characters on
+/// each side, with whitespace collapsed and `…` where text was cut off.
+///
+/// # Arguments
+/// * `text` - Text containing the match
+/// * `start` - Byte offset where the match starts
+/// * `end` - Byte offset where the match ends
+fn mention_snippet(text: &str, start: usize, end: usize) -> String {
+    let from = text[..start]
+        .char_indices()
+        .rev()
+        .nth(MENTION_CONTEXT - 1)
+        .map_or(0, |(i, _)| i);
+    let to = text[end..]
+        .char_indices()
+        .nth(MENTION_CONTEXT)
+        .map_or(text.len(), |(i, _)| end + i);
+
+    let mut snippet = text[from..to]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if from > 0 {
+        snippet.insert(0, '…');
+    }
+    if to < text.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+#[cfg(feature = "ssr")]
+/// Splits a note file into its deserialized frontmatter and markdown body.
+///
+/// # Returns
+/// * `Result<(Note, String), ParseError>` - The frontmatter fields and the body,
+///   or an error if the frontmatter is missing or invalid
+fn parse_frontmatter(content: &str) -> Result<(Note, String), ParseError> {
+    let (frontmatter, body) = split_frontmatter(content);
+    let data: Note = frontmatter
+        .ok_or(ParseError::MissingFrontmatter)?
+        .deserialize()
+        .map_err(|e| ParseError::InvalidFrontmatter(e.to_string()))?;
+    Ok((data, body))
+}
+
+#[cfg(feature = "ssr")]
+/// Returns the plain text of the first `<h1>` in rendered HTML, if any.
+fn first_heading(html: &str) -> Option<String> {
+    let heading_regex = Regex::new(r"(?s)<h1[^>]*>(.*?)</h1>").ok()?;
+    let caps = heading_regex.captures(html)?;
+    Some(strip_tags(&caps[1]))
+}
+
+#[cfg(feature = "ssr")]
+/// Returns the text of the first `# Heading` line in markdown, if any.
+///
+/// Lines inside fenced code blocks are skipped. The heading's inline markdown
+/// isn't rendered.
+fn first_markdown_heading(markdown: &str) -> Option<String> {
+    // The fence marker (``` or ~~~) of the code block we're currently inside, if any
+    let mut open_fence: Option<&str> = None;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        let fence = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| trimmed.starts_with(marker));
+        match (open_fence, fence) {
+            (None, Some(marker)) => open_fence = Some(marker),
+            (Some(open), Some(marker)) if open == marker => open_fence = None,
+            (Some(_), _) => {}
+            (None, None) => {
+                // Up to three spaces of indentation, then `#` and a space or nothing
+                if line.len() - trimmed.len() > 3 {
+                    continue;
+                }
+                let Some(text) = trimmed.strip_prefix('#') else {
+                    continue;
+                };
+                if text.is_empty() || text.starts_with([' ', '\t']) {
+                    // A closing run of `#`s only counts when separated by a space
+                    let text = text.trim();
+                    let unclosed = text.trim_end_matches('#');
+                    return Some(if unclosed.is_empty() || unclosed.ends_with([' ', '\t']) {
+                        unclosed.trim().to_string()
+                    } else {
+                        text.to_string()
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(feature = "ssr")]
+/// Returns a note's created and modified times, falling back to the file's
+/// timestamps for those its frontmatter doesn't provide.
+fn note_timestamps(
+    data: &Note,
+    note_path: Option<&Path>,
+) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+    let file_metadata = note_path.and_then(|path| path.metadata().ok());
+    let created = data.created.or_else(|| {
+        file_metadata
+            .as_ref()
+            .and_then(|metadata| metadata.created().ok())
+            .map(DateTime::<Utc>::from)
+    });
+    let modified = data.modified.or_else(|| {
+        file_metadata
+            .as_ref()
+            .and_then(|metadata| metadata.modified().ok())
+            .map(DateTime::<Utc>::from)
+    });
+    (created, modified)
+}
+
+#[cfg(feature = "ssr")]
+/// Builds the `NotFound` error for an ID no note is stored under.
+fn note_not_found(id: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("note not found: {}", id),
+    )
+}
+
+#[cfg(feature = "ssr")]
+/// Merges wikilink targets into frontmatter references, skipping duplicates.
+fn merge_references(mut references: Vec<String>, targets: Vec<String>) -> Vec<String> {
+    for target in targets {
+        if !references.contains(&target) {
+            references.push(target);
+        }
+    }
+    references
+}
+
+#[cfg(feature = "ssr")]
+/// Processes LaTeX math expressions: display math surrounded by `$$` or
+/// `\[...\]` and inline math surrounded by single `$` or `\(...\)`, for
+/// whichever delimiters are enabled.
+///
+/// The content is scanned once from left to right, so a `$$` always opens
+/// display math and is never split into two inline delimiters. Content inside
+/// `<pre>` and `<code>` elements is left untouched, and an escaped `\$` is
+/// rendered as a literal dollar sign. To keep prices like "$5 and $10" from
+/// being read as math, inline expressions can't start with whitespace, and the
+/// closing `$` can't follow whitespace or precede a digit. Bracket delimiters
+/// that don't enclose math render as the bare bracket, like markdown would.
+///
+/// # Arguments
+/// * `content` - HTML content containing math expressions
+/// * `inline_opts` - KaTeX rendering options for inline math
+/// * `display_opts` - KaTeX rendering options for display math
+/// * `delimiters` - The delimiters to recognize
+///
+/// # Returns
+/// * `String` - Processed content with rendered math
+fn process_math(
+    content: &str,
+    inline_opts: &Opts,
+    display_opts: &Opts,
+    delimiters: MathDelimiters,
+) -> String {
+    map_outside_code(content, |text| {
+        let mut output = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(start) = next_math_delimiter(rest, delimiters) {
+            let (before, after) = rest.split_at(start);
+
+            // `\(` and `\[` open inline and display math
+            if let Some(bracket) = after.strip_prefix('\\') {
+                output.push_str(before);
+                let close = match bracket.chars().next() {
+                    Some('(') => "\\)",
+                    Some('[') => "\\]",
+                    // A closing delimiter without an opening one
+                    _ => {
+                        output.push_str(&bracket[..1]);
+                        rest = &bracket[1..];
+                        continue;
+                    }
+                };
+                let display = close == "\\]";
+                let body = &bracket[1..];
+                let rendered = body
+                    .find(close)
+                    .filter(|&end| !body[..end].trim().is_empty())
+                    .and_then(|end| {
+                        let opts = if display { display_opts } else { inline_opts };
+                        Some((end, render_with_opts(&body[..end], opts).ok()?))
+                    });
+                match rendered {
+                    Some((end, rendered)) if display => {
+                        output.push_str(&format!("<div class=\"math-display\">{}</div>", rendered));
+                        rest = &body[end + close.len()..];
+                    }
+                    Some((end, rendered)) => {
+                        output.push_str(&rendered);
+                        rest = &body[end + close.len()..];
+                    }
+                    // Not math, so keep the bracket markdown would have unescaped
+                    None => {
+                        output.push_str(&bracket[..1]);
+                        rest = body;
+                    }
+                }
+                continue;
+            }
+
+            // An escaped `\$` is a literal dollar sign
+            if let Some(before) = before.strip_suffix('\\') {
+                output.push_str(before);
+                output.push('$');
+                rest = &after[1..];
+                continue;
+            }
+            output.push_str(before);
+
+            // `$$` opens display math
+            if let Some(body) = after.strip_prefix("$$") {
+                let rendered = find_display_math_end(body).and_then(|end| {
+                    Some((end, render_with_opts(&body[..end], display_opts).ok()?))
+                });
+                match rendered {
+                    Some((end, rendered)) => {
+                        output.push_str(&format!("<div class=\"math-display\">{}</div>", rendered));
+                        rest = &body[end + 2..];
+                    }
+                    None => {
+                        output.push_str("$$");
+                        rest = body;
+                    }
+                }
+                continue;
+            }
+
+            let rendered = find_inline_math_end(&after[1..]).and_then(|end| {
+                let expression = &after[1..=end];
+                Some((end, render_with_opts(expression, inline_opts).ok()?))
+            });
+            match rendered {
+                Some((end, rendered)) => {
+                    output.push_str(&rendered);
+                    rest = &after[end + 2..];
+                }
+                // Not math (or KaTeX rejected it), so keep the `$` and scan on
+                None => {
+                    output.push('$');
+                    rest = &after[1..];
+                }
+            }
+        }
+
+        output.push_str(rest);
+        output
+    })
+}
+
+/// Removes KaTeX-rendered math from HTML.
+///
+/// KaTeX's HTML output is a tree of positioned `<span>`s whose text isn't
+/// readable on its own, so each `<span class="katex">` is dropped along with
+/// everything inside it.
+///
+/// # Arguments
+/// * `html` - HTML content produced by [`process_math`]
+///
+/// # Returns
+/// * `String` - The HTML without rendered math
+#[cfg(feature = "ssr")]
+fn strip_katex(html: &str) -> String {
+    const OPEN: &str = "<span class=\"katex\">";
+
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(OPEN) {
+        output.push_str(&rest[..start]);
+        rest = &rest[start + OPEN.len()..];
+
+        // Skip past the `</span>` matching the opening tag
+        let mut depth = 1;
+        while depth > 0 {
+            let Some(close) = rest.find("</span>") else {
+                rest = "";
+                break;
+            };
+            match rest.find("<span") {
+                Some(open) if open < close => {
+                    depth += 1;
+                    rest = &rest[open + "<span".len()..];
+                }
+                _ => {
+                    depth -= 1;
+                    rest = &rest[close + "</span>".len()..];
+                }
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Removes code blocks from HTML.
+///
+/// Both highlighted blocks and blocks in unknown languages are `<pre>`
+/// elements, which never nest.
+///
+/// # Arguments
+/// * `html` - Rendered HTML content
+///
+/// # Returns
+/// * `String` - The HTML without `<pre>` elements
+#[cfg(feature = "ssr")]
+fn strip_code_blocks(html: &str) -> String {
+    match Regex::new(r"(?s)<pre[\s>].*?</pre>") {
+        Ok(re) => re.replace_all(html, " ").into_owned(),
+        Err(_) => html.to_string(),
+    }
+}
+
+/// Finds the `$` closing an inline math expression whose content starts `text`.
+///
+/// # Arguments
+/// * `text` - HTML following an opening `$`
+///
+/// # Returns
+/// * `Option<usize>` - Byte offset of the closing `$`, or None if the opening
+///   `$` doesn't start an expression
+#[cfg(feature = "ssr")]
+fn find_inline_math_end(text: &str) -> Option<usize> {
+    if text.is_empty() || text.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            // Escaped characters never close the expression
+            b'\\' => i += 1,
+            b'$' => {
+                let after_space = i == 0 || bytes[i - 1].is_ascii_whitespace();
+                let before_digit = bytes.get(i + 1).is_some_and(u8::is_ascii_digit);
+                if !after_space && !before_digit {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Finds the `$$` closing a display math expression whose content starts `text`.
+///
+/// # Arguments
+/// * `text` - HTML following an opening `$$`
+///
+/// # Returns
+/// * `Option<usize>` - Byte offset of the closing `$$`, or None if it's missing
+///   or the expression is empty
+#[cfg(feature = "ssr")]
+fn find_display_math_end(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        match bytes[i] {
+            // Escaped characters never close the expression
+            b'\\' => i += 1,
+            b'$' if bytes[i + 1] == b'$' => {
+                return (!text[..i].trim().is_empty()).then_some(i);
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Applies `f` to the parts of rendered HTML outside `<pre>` and `<code>` elements.
+///
+/// # Arguments
+/// * `html` - Rendered HTML content
+/// * `f` - Transformation applied to each run of HTML between code elements
+///
+/// # Returns
+/// * `String` - The HTML with everything but code transformed
+#[cfg(feature = "ssr")]
+fn map_outside_code(html: &str, mut f: impl FnMut(&str) -> String) -> String {
+    let code_regex = match Regex::new(r"(?s)<pre\b.*?</pre>|<code\b.*?</code>") {
+        Ok(re) => re,
+        Err(_) => return f(html),
+    };
+
+    let mut output = String::with_capacity(html.len());
+    let mut last = 0;
+    for code in code_regex.find_iter(html) {
+        output.push_str(&f(&html[last..code.start()]));
+        output.push_str(code.as_str());
+        last = code.end();
+    }
+    output.push_str(&f(&html[last..]));
+    output
+}
+
+/// Derives a note ID from the path of its markdown file relative to a root directory.
+///
+/// # Arguments
+/// * `root` - The notes root directory
+/// * `path` - Path to a markdown file inside `root`
+///
+/// # Returns
+/// * `Option<String>` - The relative, `/`-separated ID without extension
+#[cfg(feature = "ssr")]
+fn note_id_relative_to(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?.with_extension("");
+    let parts = relative
+        .components()
+        .map(|component| match component {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(parts.join("/"))
+}
+
+/// Maps a path reported by the file watcher to the ID of the affected note.
+///
+/// Markdown files map to their own note; files inside a `{id}.assets`
+/// directory map to the note owning the assets.
+///
+/// # Arguments
+/// * `root` - The canonical notes root directory
+/// * `path` - Changed path reported by the watcher
+///
+/// # Returns
+/// * `Option<String>` - ID of the affected note, if any
+#[cfg(feature = "ssr")]
+fn watched_note_id(root: &Path, path: &Path) -> Option<String> {
+    // Saved versions don't change the note
+    if path.strip_prefix(root).is_ok_and(|relative| {
+        relative.components().any(|component| {
+            component
+                .as_os_str()
+                .to_string_lossy()
+                .ends_with(".history")
+        })
+    }) {
+        return None;
+    }
+    if path.extension().is_some_and(|ext| ext == "md") {
+        return note_id_relative_to(root, path);
+    }
+    // Generated thumbnails don't change the note
+    if path
+        .components()
+        .any(|component| component.as_os_str() == THUMBNAIL_DIR)
+    {
+        return None;
+    }
+    path.ancestors()
+        .find(|ancestor| ancestor.extension().is_some_and(|ext| ext == "assets"))
+        .and_then(|assets_dir| note_id_relative_to(root, &assets_dir.with_extension("md")))
+}
+
+/// Renders the on-disk representation of a note: YAML frontmatter followed by the body.
+///
+/// # Arguments
+/// * `note` - Note whose frontmatter fields should be written
+/// * `body` - Markdown body of the note
+///
+/// # Returns
+/// * `Result<String, std::io::Error>` - File contents, or an error if serialization fails
+#[cfg(feature = "ssr")]
+fn render_note_file(note: &Note, body: &str) -> std::io::Result<String> {
+    let frontmatter = Frontmatter {
+        id: &note.id,
+        title: &note.title,
+        tags: &note.tags,
+        references: &note.references,
+        aliases: &note.aliases,
+        math: note.math,
+        draft: note.draft,
+        layout: note.layout,
+        created: note.created,
+        modified: note.modified,
+    };
+    let yaml = serde_yaml::to_string(&frontmatter)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    Ok(format!("---\n{}---\n\n{}", yaml, body))
+}
+
+/// Marks GFM task list checkboxes with their state and source line.
+///
+/// Each checkbox rendered for a `- [ ]` or `- [x]` item gets a `task-todo` or
+/// `task-done` class. When the task items found in the markdown line up with
+/// the rendered checkboxes, each also gets a `data-line` attribute holding the
+/// 1-based line of its item in the note file, for editors to toggle it.
+///
+/// # Arguments
+/// * `html` - Rendered HTML content
+/// * `markdown` - Markdown body the HTML was rendered from
+/// * `first_line` - Number of lines preceding the body in the note file
+///
+/// # Returns
+/// * `String` - HTML with annotated checkboxes
+#[cfg(feature = "ssr")]
+fn process_task_lists(html: &str, markdown: &str, first_line: usize) -> String {
+    // Raw HTML is escaped, so every checkbox comes from a task list item
+    let checkbox_regex = match Regex::new(r#"<input type="checkbox" ([^>]*)/>"#) {
+        Ok(re) => re,
+        Err(_) => return html.to_string(),
+    };
+    let item_lines = task_item_lines(markdown);
+    let mut lines = (item_lines.len() == checkbox_regex.find_iter(html).count())
+        .then(|| item_lines.into_iter().map(|line| first_line + line));
+
+    checkbox_regex
+        .replace_all(html, |caps: &regex::Captures| {
+            let class = if caps[1].contains("checked") {
+                "task-done"
+            } else {
+                "task-todo"
+            };
+            match lines.as_mut().and_then(Iterator::next) {
+                Some(line) => format!(
+                    r#"<input type="checkbox" class="{}" data-line="{}" {}/>"#,
+                    class, line, &caps[1]
+                ),
+                None => format!(r#"<input type="checkbox" class="{}" {}/>"#, class, &caps[1]),
+            }
+        })
+        .into_owned()
+}
+
+/// Replaces straight quotes, dashes, and ellipses in HTML text with typographic ones.
+///
+/// `"` and `'` become opening quotes at the start of a block or after
+/// whitespace or opening punctuation and closing quotes elsewhere (so `'90s`
+/// and `it's` get apostrophes), `---` becomes an em dash, `--` an en dash, and
+/// `...` an ellipsis. Quotes pair up across inline elements like `<em>`.
+/// Tags are left alone, as is the text of `<code>`, `<kbd>`, `<pre>`,
+/// `<script>`, and `<style>` elements and of rendered KaTeX.
+///
+/// # Arguments
+/// * `html` - Rendered HTML content
+///
+/// # Returns
+/// * `String` - The HTML with typographic punctuation
+#[cfg(feature = "ssr")]
+fn smarten_typography(html: &str) -> String {
+    let tag_regex = match Regex::new(r"(?s)<!--.*?-->|<(/?)([A-Za-z][A-Za-z0-9]*)([^>]*)>") {
+        Ok(re) => re,
+        Err(_) => return html.to_string(),
+    };
+
+    let mut output = String::with_capacity(html.len());
+    // Name of the element whose text is being skipped, and how deeply it's nested
+    let mut skipped: Option<(String, usize)> = None;
+    // Last character of text in the current block, which decides quote direction
+    let mut previous: Option<char> = None;
+    let mut position = 0;
+    for caps in tag_regex.captures_iter(html) {
+        let Some(tag) = caps.get(0) else {
+            continue;
+        };
+        let text = &html[position..tag.start()];
+        if skipped.is_some() {
+            output.push_str(text);
+            previous = text.chars().last().or(previous);
+        } else {
+            smarten_text(text, &mut previous, &mut output);
+        }
+        output.push_str(tag.as_str());
+        position = tag.end();
+
+        // Comments have no name
+        let Some(name) = caps.get(2) else {
+            continue;
+        };
+        let name = name.as_str().to_ascii_lowercase();
+        let closing = !caps[1].is_empty();
+        if let Some((skipped_name, depth)) = &mut skipped {
+            if *skipped_name == name {
+                if closing {
+                    *depth -= 1;
+                } else {
+                    *depth += 1;
+                }
+            }
+            if *depth > 0 {
+                continue;
+            }
+            skipped = None;
+        } else if !closing
+            && (TYPOGRAPHY_SKIPPED_TAGS.contains(&name.as_str())
+                || (name == "span" && caps[3].contains("class=\"katex")))
+        {
+            skipped = Some((name.clone(), 1));
+        }
+
+        // Quotes don't pair across blocks
+        if !TYPOGRAPHY_INLINE_TAGS.contains(&name.as_str()) {
+            previous = None;
+        }
+    }
+
+    let rest = &html[position..];
+    if skipped.is_some() {
+        output.push_str(rest);
+    } else {
+        smarten_text(rest, &mut previous, &mut output);
+    }
+    output
+}
+
+/// Appends a run of HTML text to `output` with typographic punctuation.
+///
+/// # Arguments
+/// * `text` - Text between two tags, with `"` possibly escaped as `&quot;`
+/// * `previous` - Character preceding the text, updated to its last character
+/// * `output` - Where the converted text is appended
+#[cfg(feature = "ssr")]
+fn smarten_text(text: &str, previous: &mut Option<char>, output: &mut String) {
+    // Curly quotes need no escaping
+    let text = text.replace("&quot;", "\"");
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let opens = match *previous {
+            Some(p) => p.is_whitespace() || "([{\u{2013}\u{2014}\u{201C}\u{2018}".contains(p),
+            None => true,
+        };
+        let replacement = match c {
+            '"' if opens => '\u{201C}',
+            '"' => '\u{201D}',
+            '\'' if opens && !chars.peek().is_some_and(char::is_ascii_digit) => '\u{2018}',
+            '\'' => '\u{2019}',
+            '-' if chars.peek() == Some(&'-') => {
+                chars.next();
+                if chars.next_if_eq(&'-').is_some() {
+                    '\u{2014}'
+                } else {
+                    '\u{2013}'
+                }
+            }
+            '.' if chars.clone().take(2).eq(['.', '.']) => {
+                chars.nth(1);
+                '\u{2026}'
+            }
+            c => c,
+        };
+        output.push(replacement);
+        *previous = Some(replacement);
+    }
+}
+
+/// Returns the default title of a callout type, or None if the type isn't recognized.
+///
+/// # Arguments
+/// * `kind` - Lowercased callout type, e.g. `note` for `[!NOTE]`
+#[cfg(feature = "ssr")]
+fn callout_title(kind: &str) -> Option<&'static str> {
+    match kind {
+        "note" => Some("Note"),
+        "tip" => Some("Tip"),
+        "important" => Some("Important"),
+        "warning" => Some("Warning"),
+        "caution" => Some("Caution"),
+        "danger" => Some("Danger"),
+        _ => None,
+    }
+}
+
+/// Converts blockquotes starting with a `[!TYPE]` marker into callouts.
+///
+/// A blockquote like `> [!WARNING]` followed by more quoted lines becomes
+/// `<div class="callout callout-warning">` with a `callout-title` paragraph,
+/// followed by the rest of the blockquote's content. Text after the marker
+/// on its line replaces the default title. Blockquotes with an unknown type
+/// are left as they are.
+///
+/// # Arguments
+/// * `html` - Rendered HTML content
+///
+/// # Returns
+/// * `String` - HTML with callout blockquotes replaced
+#[cfg(feature = "ssr")]
+fn process_callouts(html: &str) -> String {
+    let (tag_regex, marker_regex) = match (
+        Regex::new(r"</?blockquote>"),
+        Regex::new(r"^<blockquote>\n<p>\[!([A-Za-z]+)\][ \t]*(.*?)(\n|</p>\n)"),
+    ) {
+        (Ok(tag), Ok(marker)) => (tag, marker),
+        _ => return html.to_string(),
+    };
+
+    let mut output = String::with_capacity(html.len());
+    // Whether each currently open blockquote was turned into a callout
+    let mut open_callouts = Vec::new();
+    let mut position = 0;
+    for tag in tag_regex.find_iter(html) {
+        output.push_str(&html[position..tag.start()]);
+        position = tag.end();
+
+        if tag.as_str() == "</blockquote>" {
+            let is_callout = open_callouts.pop().unwrap_or(false);
+            output.push_str(if is_callout {
+                "</div>"
+            } else {
+                "</blockquote>"
+            });
+            continue;
+        }
+
+        let callout = marker_regex
+            .captures(&html[tag.start()..])
+            .and_then(|caps| {
+                let kind = caps[1].to_lowercase();
+                let default_title = callout_title(&kind)?;
+                let title = match caps[2].trim() {
+                    "" => default_title,
+                    title => title,
+                };
+                // The marker either ends its paragraph or is followed by more of it
+                let rest = if &caps[3] == "\n" { "<p>" } else { "" };
+                let opening = format!(
+                    "<div class=\"callout callout-{}\">\n<p class=\"callout-title\">{}</p>\n{}",
+                    kind, title, rest
+                );
+                Some((opening, caps[0].len()))
+            });
+        match callout {
+            Some((opening, length)) => {
+                output.push_str(&opening);
+                position = tag.start() + length;
+                open_callouts.push(true);
+            }
+            None => {
+                output.push_str(tag.as_str());
+                open_callouts.push(false);
+            }
+        }
+    }
+    output.push_str(&html[position..]);
+    output
+}
+
+/// Finds the lines of a markdown body that start a task list item.
+///
+/// Lines inside fenced code blocks are skipped.
+///
+/// # Arguments
+/// * `markdown` - Markdown body (without frontmatter)
+///
+/// # Returns
+/// * `Vec<usize>` - 1-based line numbers of task list items, in order
+#[cfg(feature = "ssr")]
+fn task_item_lines(markdown: &str) -> Vec<usize> {
+    let task_regex = match Regex::new(r"^\s*(?:>\s*)*(?:[-*+]|\d{1,9}[.)])\s+\[[ xX]\](?:\s|$)") {
+        Ok(re) => re,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut lines = Vec::new();
+    // The fence marker (``` or ~~~) of the code block we're currently inside, if any
+    let mut open_fence: Option<&str> = None;
+    for (index, line) in markdown.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let fence = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| trimmed.starts_with(marker));
+        match (open_fence, fence) {
+            (None, Some(marker)) => open_fence = Some(marker),
+            (Some(open), Some(marker)) if open == marker => open_fence = None,
+            (Some(_), _) => {}
+            (None, None) => {
+                if task_regex.is_match(line) {
+                    lines.push(index + 1);
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// Rewrites relative `src`/`href` URLs that name one of the note's assets.
+///
+/// For example, with a `diagram.png` asset, `<img src="diagram.png">` becomes
+/// `<img src="/api/notes/{id}/assets/diagram.png">`. Absolute URLs (`http://`,
+/// `https://`, `/`), fragments, and URLs that don't match an asset are left alone.
+///
+/// # Arguments
+/// * `html` - Rendered HTML content
+/// * `api_prefix` - Path the API serving the assets is under (e.g. `/api`)
+/// * `note_id` - ID of the note the assets belong to
+/// * `assets` - The note's assets
+///
+/// # Returns
+/// * `String` - HTML with asset URLs pointing at the asset endpoint
+#[cfg(feature = "ssr")]
+fn rewrite_asset_links(html: &str, api_prefix: &str, note_id: &str, assets: &[Asset]) -> String {
+    if assets.is_empty() {
+        return html.to_string();
+    }
+    let url_regex = match Regex::new(r#"\b(src|href)="([^"]*)""#) {
+        Ok(re) => re,
+        Err(_) => return html.to_string(),
+    };
+
+    url_regex
+        .replace_all(html, |caps: &regex::Captures| {
+            let url = html_escape::decode_html_entities(&caps[2]);
+            if url.starts_with('/') || url.starts_with('#') || url.contains(':') {
+                return caps[0].to_string();
+            }
+
+            let name = url.strip_prefix("./").unwrap_or(&url);
+            match assets.iter().find(|asset| asset.name == name) {
+                Some(asset) => format!(r#"{}="{}""#, &caps[1], asset.url(api_prefix, note_id)),
+                None => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/// Percent-encodes a string for use as a single URL path segment.
+///
+/// Everything except unreserved characters (`A-Z a-z 0-9 - . _ ~`) is encoded,
+/// including `/`, so nested note IDs stay in one segment.
+pub fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Assigns slug `id` attributes to headings and builds a table of contents.
+///
+/// Every `<h1>`–`<h6>` produced by the markdown renderer gets an `id` derived
+/// from its text with [`slugify`]. Duplicate slugs are disambiguated by
+/// appending `-2`, `-3`, etc.
+///
+/// # Arguments
+/// * `html` - Rendered HTML content
+///
+/// # Returns
+/// * `(String, Vec<TocEntry>)` - HTML with heading IDs and the TOC entries in document order
+#[cfg(feature = "ssr")]
+fn process_headings(html: &str) -> (String, Vec<TocEntry>) {
+    let (heading_regex, tag_regex) = match (
+        Regex::new(r"(?s)<h([1-6])>(.*?)</h[1-6]>"),
+        Regex::new(r"<[^>]+>"),
+    ) {
+        (Ok(heading), Ok(tag)) => (heading, tag),
+        _ => return (html.to_string(), Vec::new()),
+    };
+
+    let mut toc = Vec::new();
+    let mut used_slugs = std::collections::HashSet::new();
+
+    let output = heading_regex.replace_all(html, |caps: &regex::Captures| {
+        let level = caps[1].parse().unwrap_or(1);
+        let inner = &caps[2];
+        let text = html_escape::decode_html_entities(&tag_regex.replace_all(inner, ""))
+            .trim()
+            .to_string();
+
+        let base = match slugify(&text) {
+            slug if slug.is_empty() => "section".to_string(),
+            slug => slug,
+        };
+        let mut slug = base.clone();
+        let mut suffix = 2;
+        while used_slugs.contains(&slug) {
+            slug = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+        used_slugs.insert(slug.clone());
+
+        let heading = format!(r#"<h{0} id="{1}">{2}</h{0}>"#, level, slug, inner);
+        toc.push(TocEntry { level, text, slug });
+        heading
+    });
+
+    (output.into_owned(), toc)
+}
+
+/// Elements whose text [`smarten_typography`] leaves as written
+#[cfg(feature = "ssr")]
+const TYPOGRAPHY_SKIPPED_TAGS: [&str; 5] = ["code", "kbd", "pre", "script", "style"];
+
+/// Inline elements that quotes pair up across in [`smarten_typography`]
+#[cfg(feature = "ssr")]
+const TYPOGRAPHY_INLINE_TAGS: [&str; 16] = [
+    "a", "abbr", "b", "code", "del", "em", "i", "kbd", "mark", "s", "small", "span", "strong",
+    "sub", "sup", "u",
+];
+
+/// Gitignore-style file at the notes root listing files that aren't notes
+#[cfg(feature = "ssr")]
+const IGNORE_FILE: &str = ".minirefignore";
+
+/// Classes given to highlighted code tokens: syntect scope names prefixed with `syn-`
+#[cfg(feature = "ssr")]
+const HIGHLIGHT_CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "syn-" };
+
+/// Directory inside a note's `.assets` directory holding generated thumbnails
+#[cfg(feature = "ssr")]
+const THUMBNAIL_DIR: &str = ".thumbnails";
+
+/// Directory at the notes root holding the templates for [`NoteStore::create_from_template`]
+#[cfg(feature = "ssr")]
+const TEMPLATES_DIR: &str = ".templates";
+
+/// Extension of the directory next to a note file holding its previous versions
+#[cfg(feature = "ssr")]
+const HISTORY_EXTENSION: &str = "history";
+
+/// Format of the UTC time naming a saved version, followed by `Z`; sorts by time
+#[cfg(feature = "ssr")]
+const HISTORY_TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%.9f";
+
+/// Checks that an ID is a relative, `/`-separated path that stays inside the
+/// notes directory and doesn't point into a note's `.assets` or `.history`
+/// directory.
+///
+/// # Arguments
+/// * `id` - The note ID to check
+///
+/// # Returns
+/// * `bool` - False for empty and absolute IDs, IDs with `\` or `.`/`..`
+///   components, and IDs inside a note data directory
+#[cfg(feature = "ssr")]
+fn is_valid_note_id(id: &str) -> bool {
+    if id.is_empty() || id.contains('\\') {
+        return false;
+    }
+    let relative = Path::new(id);
+    relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+        && !relative
+            .parent()
+            .is_some_and(|parent| parent.components().any(is_note_data_dir))
+}
+
+/// Whether a path component is a note's `.assets` or `.history` directory.
+#[cfg(feature = "ssr")]
+fn is_note_data_dir(component: Component) -> bool {
+    component
+        .as_os_str()
+        .to_str()
+        .is_some_and(|name| name.ends_with(".assets") || name.ends_with(".history"))
+}
+
+/// Reads the `.minirefignore` patterns of a notes directory.
+///
+/// # Arguments
+/// * `root` - The notes root directory
+///
+/// # Returns
+/// * `Result<Gitignore, std::io::Error>` - The patterns, empty if there's no
+///   ignore file, or an error if it can't be read or has an invalid pattern
+#[cfg(feature = "ssr")]
+fn load_ignore_file(root: &Path) -> std::io::Result<Gitignore> {
+    let path = root.join(IGNORE_FILE);
+    if !path.is_file() {
+        return Ok(Gitignore::empty());
+    }
+
+    let mut builder = GitignoreBuilder::new(root);
+    if let Some(e) = builder.add(&path) {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+    }
+    builder
+        .build()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Splits a directed graph into its strongly connected components.
+///
+/// Uses Tarjan's algorithm with an explicit stack instead of recursion, so
+/// long reference chains can't overflow the thread's stack.
+///
+/// # Arguments
+/// * `edges` - Targets of each node's outgoing edges, indexed by node
+///
+/// # Returns
+/// * `Vec<Vec<usize>>` - The nodes of each component; every node is in exactly one
+#[cfg(feature = "ssr")]
+fn strongly_connected_components(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+    let mut index = vec![UNVISITED; edges.len()];
+    let mut low_link = vec![0; edges.len()];
+    let mut on_stack = vec![false; edges.len()];
+    let mut stack = Vec::new();
+    let mut components = Vec::new();
+    let mut next_index = 0;
+
+    for root in 0..edges.len() {
+        if index[root] != UNVISITED {
+            continue;
+        }
+        index[root] = next_index;
+        low_link[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        // Each frame is a node and the position of the next edge to follow from it
+        let mut frames = vec![(root, 0)];
+        while let Some((node, edge)) = frames.pop() {
+            if let Some(&target) = edges[node].get(edge) {
+                frames.push((node, edge + 1));
+                if index[target] == UNVISITED {
+                    index[target] = next_index;
+                    low_link[target] = next_index;
+                    next_index += 1;
+                    stack.push(target);
+                    on_stack[target] = true;
+                    frames.push((target, 0));
+                } else if on_stack[target] {
+                    low_link[node] = low_link[node].min(index[target]);
+                }
+                continue;
+            }
+
+            // Every edge has been followed, so hand the low link back to the caller
+            if let Some(&(caller, _)) = frames.last() {
+                low_link[caller] = low_link[caller].min(low_link[node]);
+            }
+            if low_link[node] == index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+
+    components
+}
+
+/// Builds the nodes nested directly under a tag.
+///
+/// # Arguments
+/// * `counts` - Note counts keyed by lowercased tag path, with the tag as spelled
+/// * `parent` - Lowercased path of the parent tag; empty for top-level tags
+///
+/// # Returns
+/// * `Vec<TagNode>` - The child tags and their descendants, ordered by name
+#[cfg(feature = "ssr")]
+fn tag_children(counts: &BTreeMap<String, (String, usize)>, parent: &str) -> Vec<TagNode> {
+    let prefix = if parent.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", parent)
+    };
+
+    counts
+        .iter()
+        .filter(|(key, _)| {
+            key.strip_prefix(&prefix)
+                .is_some_and(|rest| !rest.is_empty() && !rest.contains('/'))
+        })
+        .map(|(key, (path, count))| TagNode {
+            name: path.rsplit('/').next().unwrap_or(path).to_string(),
+            path: path.clone(),
+            count: *count,
+            children: tag_children(counts, key),
+        })
+        .collect()
+}
+
+/// Splits a note into its frontmatter and markdown body.
+///
+/// The format is detected from the start of the file: a `---` fence for YAML,
+/// a `+++` fence for TOML, or a JSON object opening with `{` and closed by a
+/// `}` on its own line.
+///
+/// # Arguments
+/// * `content` - Raw note content including frontmatter
+///
+/// # Returns
+/// * `(Option<Pod>, String)` - The parsed frontmatter, if any, and the markdown body
+#[cfg(feature = "ssr")]
+fn split_frontmatter(content: &str) -> (Option<Pod>, String) {
+    let first_line = content.lines().next().unwrap_or_default().trim_end();
+
+    if first_line == "+++" {
+        let mut matter = Matter::<TOML>::new();
+        matter.delimiter = "+++".to_string();
+        let parsed = matter.parse(content);
+        return (parsed.data, parsed.content);
+    }
+
+    if first_line.starts_with('{') {
+        // JSON frontmatter has no fence; the object itself is the block
+        let mut end = 0;
+        for line in content.split_inclusive('\n') {
+            end += line.len();
+            if line.trim_end() == "}" {
+                let body = content[end..].trim_start_matches(['\r', '\n']);
+                return (Some(JSON::parse(&content[..end])), body.to_string());
+            }
+        }
+        return (None, content.to_string());
+    }
+
+    let parsed = Matter::<YAML>::new().parse(content);
+    (parsed.data, parsed.content)
+}
+
+/// Returns the length in bytes of a note's frontmatter block, closing line
+/// included, detecting its format like [`split_frontmatter`].
+///
+/// # Arguments
+/// * `content` - Raw note content including frontmatter
+///
+/// # Returns
+/// * `Option<usize>` - Where the markdown body starts, or None if the note
+///   doesn't start with a complete frontmatter block
+#[cfg(feature = "ssr")]
+fn frontmatter_len(content: &str) -> Option<usize> {
+    let first_line = content.lines().next()?.trim_end();
+    // Fenced formats close with the same fence; JSON with a `}` on its own line
+    let (skip, closing) = match first_line {
+        "---" | "+++" => (1, first_line),
+        _ if first_line.starts_with('{') => (0, "}"),
+        _ => return None,
+    };
+
+    let mut end = 0;
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        end += line.len();
+        if index >= skip && line.trim_end() == closing {
+            return Some(end);
+        }
+    }
+    None
+}
+
+/// Extracts the markdown body of a note, dropping its frontmatter.
+///
+/// # Arguments
+/// * `content` - Raw note content including frontmatter
+///
+/// # Returns
+/// * `String` - The markdown body
+#[cfg(feature = "ssr")]
+fn markdown_body(content: &str) -> String {
+    split_frontmatter(content).1
+}
+
+/// Applies `f` to the parts of a markdown body outside code.
+///
+/// Fenced code blocks and inline code spans are copied through unchanged.
+///
+/// # Arguments
+/// * `markdown` - Raw markdown body (without frontmatter)
+/// * `f` - Transformation applied to each run of prose
+///
+/// # Returns
+/// * `String` - The markdown with its prose transformed
+#[cfg(feature = "ssr")]
+fn map_markdown_prose(markdown: &str, mut f: impl FnMut(&str) -> String) -> String {
+    let mut output = String::with_capacity(markdown.len());
+    // The fence marker (``` or ~~~) of the code block we're currently inside, if any
+    let mut open_fence: Option<&str> = None;
+
+    for line in markdown.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let fence = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| trimmed.starts_with(marker));
+
+        match (open_fence, fence) {
+            // Opening a fenced block
+            (None, Some(marker)) => {
+                open_fence = Some(marker);
+                output.push_str(line);
+                continue;
+            }
+            // Closing the fenced block we're in
+            (Some(open), Some(marker)) if open == marker => {
+                open_fence = None;
+                output.push_str(line);
+                continue;
+            }
+            // Inside a fenced block, leave the line as is
+            (Some(_), _) => {
+                output.push_str(line);
+                continue;
+            }
+            (None, None) => {}
+        }
+
+        // Split on backticks so that inline code spans (odd segments) are skipped
+        for (i, segment) in line.split('`').enumerate() {
+            if i > 0 {
+                output.push('`');
+            }
+            if i % 2 == 1 {
+                output.push_str(segment);
+            } else {
+                output.push_str(&f(segment));
+            }
+        }
+    }
+
+    output
+}
+
+/// Keeps escaped dollar signs (`\$`) and bracket math delimiters (`\(`, `\)`,
+/// `\[`, `\]`) escaped through markdown rendering.
+///
+/// Markdown turns `\$` into a bare `$`, which the math renderer can't tell
+/// apart from a delimiter, and `\(` into a bare `(`. Doubling the backslash
+/// outside code makes the rendered HTML contain the escape as written, which
+/// [`process_math`] then turns into a literal dollar sign or a delimiter.
+/// Only the escapes of enabled delimiters are kept.
+///
+/// # Arguments
+/// * `markdown` - Raw markdown body (without frontmatter)
+/// * `delimiters` - The delimiters math is recognized with
+///
+/// # Returns
+/// * `String` - The markdown with the escapes protected
+#[cfg(feature = "ssr")]
+fn preserve_math_escapes(markdown: &str, delimiters: MathDelimiters) -> String {
+    let mut escapes = Vec::new();
+    if delimiters.dollars {
+        escapes.push("\\$");
+    }
+    if delimiters.brackets {
+        escapes.extend(["\\(", "\\)", "\\[", "\\]"]);
+    }
+    map_markdown_prose(markdown, |segment| {
+        escapes.iter().fold(segment.to_string(), |text, escape| {
+            text.replace(escape, &format!("\\{}", escape))
+        })
+    })
+}
+
+/// Finds the next place math processing has to look at: a `$`, or a
+/// backslash before a bracket delimiter, for the enabled delimiters.
+///
+/// # Arguments
+/// * `text` - HTML text outside code
+/// * `delimiters` - The delimiters to look for
+///
+/// # Returns
+/// * `Option<usize>` - Byte offset of the earliest candidate, if any
+#[cfg(feature = "ssr")]
+fn next_math_delimiter(text: &str, delimiters: MathDelimiters) -> Option<usize> {
+    let dollar = delimiters.dollars.then(|| text.find('$')).flatten();
+    let bracket = delimiters
+        .brackets
+        .then(|| {
+            text.match_indices('\\')
+                .map(|(i, _)| i)
+                .find(|&i| matches!(text.as_bytes().get(i + 1), Some(b'(' | b')' | b'[' | b']')))
+        })
+        .flatten();
+    match (dollar, bracket) {
+        (Some(dollar), Some(bracket)) => Some(dollar.min(bracket)),
+        (dollar, bracket) => dollar.or(bracket),
+    }
+}
+
+/// Rewrites wikilinks in a note's markdown body into standard markdown links.
+///
+/// Supports both `[[target-id]]` and `[[target-id|Display Text]]`; when no
+/// display text is given the target as written is used. A target can name a
+/// heading after `#` (`[[target-id#Some Heading]]`, or `[[#Some Heading]]` within
+/// the same note), which is turned into a fragment with [`slugify`], the same
+/// way [`process_headings`] derives heading IDs. Fenced code blocks and inline
+/// code spans are left untouched.
+///
+/// # Arguments
+/// * `markdown` - Raw markdown body (without frontmatter)
+///
+/// # Returns
+/// * `(String, Vec<String>)` - The rewritten markdown and the unique linked note
+///   IDs, without fragments, in order of first appearance
+#[cfg(feature = "ssr")]
+fn process_wikilinks(markdown: &str) -> (String, Vec<String>) {
+    let wikilink_regex = match Regex::new(WIKILINK_PATTERN) {
+        Ok(re) => re,
+        Err(_) => return (markdown.to_string(), Vec::new()),
+    };
 
-        // Process code blocks with syntax highlighting
-        let highlighted = code_block_regex.replace_all(&html_output, |caps: &regex::Captures| {
-            let language = &caps[1];
-            let content = html_escape::decode_html_entities(&caps[2]).to_string();
-
-            match self.syntax_set.find_syntax_by_token(language) {
-                Some(syntax) => {
-                    match highlighted_html_for_string(&content, &self.syntax_set, syntax, theme) {
-                        Ok(highlighted_html) => format!(
-                            r#"<pre><code class="language-{}">{}</code></pre>"#,
-                            language, highlighted_html
-                        ),
-                        Err(_) => caps[0].to_string(),
-                    }
+    let mut targets: Vec<String> = Vec::new();
+    let output = map_markdown_prose(markdown, |segment| {
+        wikilink_regex
+            .replace_all(segment, |caps: &regex::Captures| {
+                let written = caps[1].trim();
+                let display = caps
+                    .get(2)
+                    .map(|m| m.as_str().trim().to_string())
+                    .filter(|text| !text.is_empty())
+                    .unwrap_or_else(|| written.to_string());
+                let (target, heading) = written.split_once('#').unwrap_or((written, ""));
+                let target = target.trim().to_string();
+                let fragment = match slugify(heading) {
+                    slug if slug.is_empty() => String::new(),
+                    slug => format!("#{}", slug),
+                };
+                if target.is_empty() {
+                    // A link to a heading of the note itself
+                    return format!("[{}]({})", display, fragment);
                 }
-                None => caps[0].to_string(),
-            }
-        });
-
-        // Configure KaTeX options for inline math
-        let katex_opts = match Opts::builder()
-            .display_mode(false)
-            .output_type(katex::OutputType::Html)
-            .build()
-        {
-            Ok(opts) => opts,
-            Err(_) => return None,
-        };
+                let link = format!("[{}](/{}{})", display, target, fragment);
+                if !targets.contains(&target) {
+                    targets.push(target);
+                }
+                link
+            })
+            .into_owned()
+    });
 
-        // Process inline LaTeX math expressions
-        let math_processed = process_inline_math(&highlighted, &katex_opts);
+    (output, targets)
+}
 
-        // Configure KaTeX options for display math
-        let display_opts = match Opts::builder()
-            .display_mode(true)
-            .output_type(katex::OutputType::Html)
-            .build()
-        {
-            Ok(opts) => opts,
-            Err(_) => return None,
-        };
+/// Marks `![[target-id]]` embeds in a note's markdown body.
+///
+/// Each embed becomes a markdown link to the note, titled with
+/// [`EMBED_TITLE_PREFIX`] and the embed's index, which
+/// [`NoteStore::inline_embeds`] replaces with the note's content after
+/// rendering. `![[target-id|Text]]` uses the text for the link shown when the
+/// note can't be embedded. Fenced code blocks and inline code spans are left
+/// untouched.
+///
+/// # Arguments
+/// * `markdown` - Raw markdown body (without frontmatter)
+///
+/// # Returns
+/// * `(String, Vec<String>)` - The rewritten markdown and the embedded note IDs,
+///   indexed like the marks
+#[cfg(feature = "ssr")]
+fn process_embeds(markdown: &str) -> (String, Vec<String>) {
+    let embed_regex = match Regex::new(r"!\[\[([^\[\]|#]+?)(?:\|([^\[\]]+?))?\]\]") {
+        Ok(re) => re,
+        Err(_) => return (markdown.to_string(), Vec::new()),
+    };
 
-        // Process display (block) LaTeX math expressions
-        let final_content = process_display_math(&math_processed, &display_opts);
+    let mut targets: Vec<String> = Vec::new();
+    let output = map_markdown_prose(markdown, |segment| {
+        embed_regex
+            .replace_all(segment, |caps: &regex::Captures| {
+                let target = caps[1].trim().to_string();
+                if target.is_empty() {
+                    return caps[0].to_string();
+                }
+                let display = caps
+                    .get(2)
+                    .map(|m| m.as_str().trim().to_string())
+                    .filter(|text| !text.is_empty())
+                    .unwrap_or_else(|| target.clone());
+                let link = format!(
+                    "[{}](/{} \"{}{}\")",
+                    display,
+                    target,
+                    EMBED_TITLE_PREFIX,
+                    targets.len()
+                );
+                targets.push(target);
+                link
+            })
+            .into_owned()
+    });
 
-        // Scan for associated assets if we have a note path
-        let assets = note_path.map(scan_assets).unwrap_or_default();
+    (output, targets)
+}
 
-        // Construct the final note object
-        Some(Note {
-            id: parsed.data.id,
-            title: parsed.data.title,
-            content: final_content,
-            tags: parsed.data.tags,
-            references: parsed.data.references,
-            assets,
-        })
+/// Lists the IDs of the notes embedded in rendered note content, nested
+/// embeds included, in order of first appearance.
+///
+/// # Arguments
+/// * `html` - Rendered note content
+///
+/// # Returns
+/// * `Vec<String>` - The `data-embed` IDs of the content's embed sections
+#[cfg(feature = "ssr")]
+fn embedded_ids(html: &str) -> Vec<String> {
+    let Ok(embed_regex) = Regex::new(r#"<section class="embed" data-embed="([^"]*)">"#) else {
+        return Vec::new();
+    };
+    let mut ids: Vec<String> = Vec::new();
+    for caps in embed_regex.captures_iter(html) {
+        let id = html_escape::decode_html_entities(&caps[1]).into_owned();
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
     }
+    ids
 }
 
-#[cfg(feature = "ssr")]
-/// Processes inline LaTeX math expressions (surrounded by single $).
+/// Points the wikilinks to one note ID in a markdown body at another.
+///
+/// Only links whose target is exactly `old_id` are changed; a heading after
+/// `#` and display text after `|` are kept as written. Fenced code blocks and
+/// inline code spans are left untouched.
 ///
 /// # Arguments
-/// * `content` - HTML content containing math expressions
-/// * `opts` - KaTeX rendering options
+/// * `markdown` - Raw markdown body (without frontmatter)
+/// * `old_id` - Note ID the links currently point at
+/// * `new_id` - Note ID the links should point at
 ///
 /// # Returns
-/// * `String` - Processed content with rendered math
-fn process_inline_math(content: &str, opts: &Opts) -> String {
-    match Regex::new(r"\$([^\$]+?)\$") {
-        Ok(re) => re
-            .replace_all(content, |caps: &regex::Captures| {
-                match render_with_opts(&caps[1], opts) {
-                    Ok(rendered) => rendered,
-                    Err(_) => caps[0].to_string(),
+/// * `String` - The markdown with the wikilinks updated
+#[cfg(feature = "ssr")]
+fn rename_wikilinks(markdown: &str, old_id: &str, new_id: &str) -> String {
+    let wikilink_regex = match Regex::new(WIKILINK_PATTERN) {
+        Ok(re) => re,
+        Err(_) => return markdown.to_string(),
+    };
+
+    map_markdown_prose(markdown, |segment| {
+        wikilink_regex
+            .replace_all(segment, |caps: &regex::Captures| {
+                let written = &caps[1];
+                let (target, heading) = match written.find('#') {
+                    Some(index) => written.split_at(index),
+                    None => (written, ""),
+                };
+                if target.trim() != old_id {
+                    return caps[0].to_string();
                 }
+                let display = caps
+                    .get(2)
+                    .map(|m| format!("|{}", m.as_str()))
+                    .unwrap_or_default();
+                format!("[[{}{}{}]]", new_id, heading, display)
             })
-            .to_string(),
-        Err(_) => content.to_string(),
-    }
+            .into_owned()
+    })
 }
 
-#[cfg(feature = "ssr")]
-/// Processes display/block LaTeX math expressions (surrounded by double $$).
+/// Points the entries of a frontmatter `references` list naming one note ID
+/// at another, copying the rest of the frontmatter unchanged.
+///
+/// Flow lists (`[a, b]` in YAML, TOML and JSON, also spread over several
+/// lines) and YAML block lists (`- a` lines) are recognized. Entries keep
+/// their quotes and spacing.
 ///
 /// # Arguments
-/// * `content` - HTML content containing math expressions
-/// * `opts` - KaTeX rendering options
+/// * `frontmatter` - Frontmatter block of a note, fences included
+/// * `old_id` - Note ID the references currently name
+/// * `new_id` - Note ID the references should name
 ///
 /// # Returns
-/// * `String` - Processed content with rendered math
-fn process_display_math(content: &str, opts: &Opts) -> String {
-    match Regex::new(r"\$\$([^\$]+?)\$\$") {
-        Ok(re) => re
-            .replace_all(content, |caps: &regex::Captures| {
-                match render_with_opts(&caps[1], opts) {
-                    Ok(rendered) => format!("<div class=\"math-display\">{}</div>", rendered),
-                    Err(_) => caps[0].to_string(),
-                }
-            })
-            .to_string(),
-        Err(_) => content.to_string(),
+/// * `String` - The frontmatter with the references updated
+#[cfg(feature = "ssr")]
+fn rename_references(frontmatter: &str, old_id: &str, new_id: &str) -> String {
+    // Byte ranges of the list entries, surrounding spaces and quotes included
+    let mut entries = Vec::new();
+    let mut lines = frontmatter
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line))
+        })
+        .peekable();
+    while let Some((line_start, line)) = lines.next() {
+        // `references:` in YAML, `references =` in TOML, `"references":` in JSON
+        let key = line.trim_start();
+        let Some(value) = key
+            .strip_prefix("\"references\"")
+            .or_else(|| key.strip_prefix("references"))
+            .and_then(|rest| rest.trim_start().strip_prefix([':', '=']))
+        else {
+            continue;
+        };
+
+        if let Some(list) = value.trim_start().strip_prefix('[') {
+            let list_start = line_start + line.len() - list.len();
+            let Some(list_len) = frontmatter[list_start..].find(']') else {
+                break;
+            };
+            let mut entry_start = list_start;
+            for entry in frontmatter[list_start..list_start + list_len].split(',') {
+                entries.push(entry_start..entry_start + entry.len());
+                entry_start += entry.len() + 1;
+            }
+        } else if value.trim().is_empty() {
+            while let Some(&(item_start, item)) = lines.peek() {
+                let Some(entry) = item
+                    .trim_start()
+                    .strip_prefix('-')
+                    .filter(|entry| entry.starts_with([' ', '\t']))
+                else {
+                    break;
+                };
+                let entry_start = item_start + item.len() - entry.len();
+                entries.push(entry_start..entry_start + entry.trim_end().len());
+                lines.next();
+            }
+        }
     }
+
+    let mut renamed = String::with_capacity(frontmatter.len());
+    let mut copied = 0;
+    for range in entries {
+        let entry = &frontmatter[range.clone()];
+        let trimmed = entry.trim();
+        let unquoted = ['"', '\'']
+            .iter()
+            .find_map(|&quote| trimmed.strip_prefix(quote)?.strip_suffix(quote))
+            .unwrap_or(trimmed);
+        if unquoted != old_id {
+            continue;
+        }
+        let id_start = range.start + entry.len() - entry.trim_start().len()
+            + (trimmed.len() - unquoted.len()) / 2;
+        renamed.push_str(&frontmatter[copied..id_start]);
+        renamed.push_str(new_id);
+        copied = id_start + old_id.len();
+    }
+    renamed.push_str(&frontmatter[copied..]);
+    renamed
 }
 
 /// Scans for assets associated with a note.
@@ -421,7 +6036,8 @@ fn process_display_math(content: &str, opts: &Opts) -> String {
 /// * `note_path` - Path to the note's markdown file
 ///
 /// # Returns
-/// * `Vec<Asset>` - List of assets found in the note's asset directory
+/// * `Vec<Asset>` - List of assets found in the note's asset directory, sorted
+///   by name so the order doesn't depend on the file system
 #[cfg(feature = "ssr")]
 fn scan_assets(note_path: &Path) -> Vec<Asset> {
     let assets_dir = note_path.with_extension("assets");
@@ -441,6 +6057,7 @@ fn scan_assets(note_path: &Path) -> Vec<Asset> {
             }
         }
     }
+    assets.sort_by(|a, b| a.name.cmp(&b.name));
     assets
 }
 
@@ -474,3 +6091,875 @@ fn create_asset(entry: &std::fs::DirEntry) -> Option<Asset> {
         mime_type,
     })
 }
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    /// Builds a store serving the given notes from memory
+    fn store(notes: &[(&str, &str)]) -> NoteStore<MemoryBackend> {
+        NoteStore::in_memory(
+            notes
+                .iter()
+                .map(|(id, content)| (id.to_string(), content.to_string()))
+                .collect(),
+        )
+    }
+
+    /// Builds a cache entry for a note with no content
+    fn cached(id: &str) -> CachedNote {
+        let note = Note {
+            id: id.to_string(),
+            ..Default::default()
+        };
+        CachedNote::new(note, String::new(), Some(SystemTime::now()))
+    }
+
+    /// Start of every expression rendered by KaTeX
+    const KATEX: &str = "<span class=\"katex\">";
+
+    /// Renders a markdown body as the content of a note, with a store's settings
+    fn render_with(store: &NoteStore<MemoryBackend>, body: &str) -> String {
+        let content = format!("---\ntitle: Note\n---\n{}", body);
+        store.parse_note("note", &content).unwrap().content
+    }
+
+    /// Renders a markdown body as the content of a note, with the default settings
+    fn render(body: &str) -> String {
+        render_with(&store(&[]), body)
+    }
+
+    /// Creates an empty directory for a test, replacing one left by an earlier run
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("miniref-{}-{}", name, std::process::id()));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Backend that can't tell when its notes were written
+    struct NoModifiedTime(MemoryBackend);
+
+    impl NoteBackend for NoModifiedTime {
+        fn list(&self) -> std::io::Result<Vec<String>> {
+            self.0.list()
+        }
+
+        fn read(&self, id: &str) -> std::io::Result<Option<String>> {
+            self.0.read(id)
+        }
+
+        fn write(&self, id: &str, content: &str, create_new: bool) -> std::io::Result<()> {
+            self.0.write(id, content, create_new)
+        }
+
+        fn delete(&self, id: &str) -> std::io::Result<()> {
+            self.0.delete(id)
+        }
+
+        fn modified_time(&self, id: &str) -> std::io::Result<Option<SystemTime>> {
+            match self.0.read(id)? {
+                Some(_) => Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "no modification times",
+                )),
+                None => Ok(None),
+            }
+        }
+    }
+
+    /// IDs of the notes on a page
+    fn page_ids(page: &NoteListPage) -> Vec<&str> {
+        page.notes.iter().map(|note| note.id.as_str()).collect()
+    }
+
+    #[test]
+    fn wikilinks_become_markdown_links() {
+        let (markdown, targets) =
+            process_wikilinks("See [[alpha]], [[beta|the second]] and [[alpha]].");
+        assert_eq!(
+            markdown,
+            "See [alpha](/alpha), [the second](/beta) and [alpha](/alpha)."
+        );
+        assert_eq!(targets, ["alpha", "beta"]);
+    }
+
+    #[test]
+    fn wikilinks_in_code_are_left_alone() {
+        let markdown = "```\n[[alpha]]\n```\nUse `[[beta]]` to link [[gamma]].\n";
+        let (rewritten, targets) = process_wikilinks(markdown);
+        assert_eq!(
+            rewritten,
+            "```\n[[alpha]]\n```\nUse `[[beta]]` to link [gamma](/gamma).\n"
+        );
+        assert_eq!(targets, ["gamma"]);
+    }
+
+    #[test]
+    fn wikilink_targets_are_references() {
+        let store = store(&[(
+            "a",
+            "---\ntitle: A\nreferences: [c]\n---\nSee [[b]] and [[c|C]].\n",
+        )]);
+        let note = store.get_note("a").unwrap().unwrap();
+        assert_eq!(note.references, ["c", "b"]);
+        assert!(note.content.contains("<a href=\"/b\">b</a>"));
+    }
+
+    #[test]
+    fn cache_evicts_least_recently_used() {
+        let mut cache = NoteCache::new(Some(2));
+        cache.insert("a".to_string(), cached("a"));
+        cache.insert("b".to_string(), cached("b"));
+        assert!(cache.get("a").is_some());
+        cache.insert("c".to_string(), cached("c"));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.peek("a").is_some());
+        assert!(cache.peek("b").is_none());
+        assert!(cache.peek("c").is_some());
+    }
+
+    #[test]
+    fn cache_peek_keeps_recency() {
+        let mut cache = NoteCache::new(Some(2));
+        cache.insert("a".to_string(), cached("a"));
+        cache.insert("b".to_string(), cached("b"));
+        assert!(cache.peek("a").is_some());
+        cache.insert("c".to_string(), cached("c"));
+
+        assert!(cache.peek("a").is_none());
+        assert!(cache.peek("b").is_some());
+    }
+
+    #[test]
+    fn cache_shrinks_to_new_capacity() {
+        let mut cache = NoteCache::new(None);
+        for id in ["a", "b", "c"] {
+            cache.insert(id.to_string(), cached(id));
+        }
+        assert!(cache.get("a").is_some());
+        cache.set_capacity(Some(1));
+
+        assert_eq!(cache.len(), 1);
+        assert!(cache.peek("a").is_some());
+    }
+
+    #[test]
+    fn cache_capacity_bounds_warmed_notes() {
+        let note = "---\ntitle: Note\n---\nBody\n";
+        let store = store(&[("a", note), ("b", note), ("c", note)]).with_cache_capacity(Some(2));
+        store.warm_cache().unwrap();
+        assert_eq!(store.cache_stats().entries, 2);
+    }
+
+    #[test]
+    fn inline_math_end_skips_currency() {
+        assert_eq!(find_inline_math_end("x$"), Some(1));
+        assert_eq!(find_inline_math_end("5 and then $10"), None);
+        assert_eq!(find_inline_math_end(" x$"), None);
+        assert_eq!(find_inline_math_end("x $ y$"), Some(5));
+        assert_eq!(find_inline_math_end("a\\$b$"), Some(4));
+        assert_eq!(find_inline_math_end("x$5"), None);
+    }
+
+    #[test]
+    fn currency_amounts_are_not_math() {
+        let content = render("It cost $5 and then $10.");
+        assert!(content.contains("It cost $5 and then $10."));
+        assert!(!content.contains(KATEX));
+    }
+
+    #[test]
+    fn escaped_dollars_are_literal() {
+        let content = render("Costs \\$x$ and $y$");
+        assert!(content.contains("Costs $x$ and "));
+        assert_eq!(content.matches(KATEX).count(), 1);
+    }
+
+    #[test]
+    fn math_in_inline_code_is_left_alone() {
+        let content = render("Type `$x$` for math");
+        assert!(content.contains("<code>$x$</code>"));
+        assert!(!content.contains(KATEX));
+    }
+
+    #[test]
+    fn display_math_end_needs_content() {
+        assert_eq!(find_display_math_end("\nx^2\n$$ and $y$"), Some(5));
+        assert_eq!(find_display_math_end(" \n$$"), None);
+        assert_eq!(find_display_math_end("x^2"), None);
+    }
+
+    #[test]
+    fn display_math_is_not_split_into_inline_math() {
+        let content = render("$$\na^2 + b^2 = c^2\n$$\nwhere $a$ is a side.\n");
+        assert_eq!(content.matches("<div class=\"math-display\">").count(), 1);
+        assert_eq!(content.matches(KATEX).count(), 2);
+        assert!(!content.contains('$'));
+    }
+
+    #[test]
+    fn footnotes_link_back_to_each_reference() {
+        let content =
+            render("One[^a], two[^b], and one again[^a].\n\n[^a]: First.\n[^b]: Second.\n");
+        for id in [
+            "user-content-fnref-a",
+            "user-content-fnref-a-2",
+            "user-content-fnref-b",
+        ] {
+            assert!(
+                content.contains(&format!("id=\"{}\"", id)),
+                "no reference {}",
+                id
+            );
+            assert!(
+                content.contains(&format!("href=\"#{}\"", id)),
+                "no back-reference to {}",
+                id
+            );
+        }
+        for id in ["user-content-fn-a", "user-content-fn-b"] {
+            assert!(
+                content.contains(&format!("<li id=\"{}\">", id)),
+                "no footnote {}",
+                id
+            );
+        }
+    }
+
+    #[test]
+    fn highlight_code_block_knows_languages() {
+        let store = store(&[]);
+        for (language, code) in [
+            ("rust", "fn main() {}\n"),
+            ("python", "def main():\n    pass\n"),
+        ] {
+            let html = store.highlight_code_block(language, code).unwrap();
+            assert!(html.starts_with(&format!(
+                "<pre class=\"syn-code\"><code class=\"language-{}\">",
+                language
+            )));
+            assert!(html.contains("<span class=\"syn-"));
+        }
+        assert_eq!(store.highlight_code_block("no-such-language", "x\n"), None);
+    }
+
+    #[test]
+    fn unknown_languages_keep_escaped_code() {
+        let content = render("```no-such-language\n<b>&</b>\n```\n");
+        assert!(content.contains(
+            "<pre><code class=\"language-no-such-language\">&lt;b&gt;&amp;&lt;/b&gt;\n</code></pre>"
+        ));
+    }
+
+    #[test]
+    fn backend_id_wins_over_frontmatter_id() {
+        let store = store(&[("foo", "---\nid: bar\ntitle: Foo\n---\nBody\n")]);
+        assert_eq!(store.get_note("foo").unwrap().unwrap().id, "foo");
+        assert!(store.get_note("bar").unwrap().is_none());
+
+        let ids: Vec<String> = store
+            .list_metadata(false)
+            .unwrap()
+            .into_iter()
+            .map(|note| note.id)
+            .collect();
+        assert_eq!(ids, ["foo"]);
+    }
+
+    #[test]
+    fn math_can_be_turned_off_per_note() {
+        let store = store(&[
+            (
+                "plain",
+                "---\ntitle: Plain\nmath: false\n---\nSet $x$ to \\$HOME.\n",
+            ),
+            ("math", "---\ntitle: Math\n---\nSet $x$.\n"),
+        ]);
+        let plain = store.get_note("plain").unwrap().unwrap();
+        assert!(!plain.math);
+        assert!(plain.content.contains("Set $x$ to $HOME."));
+        assert!(!plain.content.contains(KATEX));
+
+        let math = store.get_note("math").unwrap().unwrap();
+        assert!(math.content.contains(KATEX));
+    }
+
+    #[test]
+    fn frontmatter_formats_parse_alike() {
+        let yaml = "---\nid: alpha\ntitle: Alpha\ntags: [one, two]\nreferences: [beta]\nmath: false\n---\nBody\n";
+        let toml = "+++\nid = \"alpha\"\ntitle = \"Alpha\"\ntags = [\"one\", \"two\"]\nreferences = [\"beta\"]\nmath = false\n+++\nBody\n";
+        let json = "{\n  \"id\": \"alpha\",\n  \"title\": \"Alpha\",\n  \"tags\": [\"one\", \"two\"],\n  \"references\": [\"beta\"],\n  \"math\": false\n}\nBody\n";
+
+        let (note, body) = parse_frontmatter(yaml).unwrap();
+        assert_eq!(note.title, "Alpha");
+        assert_eq!(note.tags, ["one", "two"]);
+        assert_eq!(note.references, ["beta"]);
+        assert!(!note.math);
+        assert_eq!(body.trim(), "Body");
+
+        for content in [toml, json] {
+            let (other, other_body) = parse_frontmatter(content).unwrap();
+            assert_eq!(other, note);
+            assert_eq!(other_body.trim(), "Body");
+        }
+    }
+
+    #[test]
+    fn frontmatter_len_covers_each_format() {
+        assert_eq!(frontmatter_len("---\nid: a\n---\nBody\n"), Some(14));
+        assert_eq!(frontmatter_len("+++\nid = \"a\"\n+++\nBody\n"), Some(17));
+        assert_eq!(frontmatter_len("{\n  \"id\": \"a\"\n}\nBody\n"), Some(16));
+        assert_eq!(frontmatter_len("---\nid: a\n"), None);
+        assert_eq!(frontmatter_len("# Just a heading\n"), None);
+    }
+
+    #[test]
+    fn note_exists_checks_ids_only() {
+        let store = store(&[(
+            "note",
+            "---\ntitle: Note\naliases: [other-name]\n---\nBody\n",
+        )]);
+        assert!(store.note_exists("note"));
+        assert!(!store.note_exists("missing"));
+
+        // Aliases resolve when fetching the note, but they aren't note IDs
+        assert!(!store.note_exists("other-name"));
+        assert!(store.get_note("other-name").unwrap().is_some());
+    }
+
+    #[test]
+    fn notes_without_title_or_id_are_named_after_their_id() {
+        let store = store(&[("meeting-notes", "---\ntags: [work]\n---\nJust text.\n")]);
+        let note = store.get_note("meeting-notes").unwrap().unwrap();
+        assert_eq!(note.id, "meeting-notes");
+        assert_eq!(note.title, "Meeting notes");
+    }
+
+    #[test]
+    fn notes_without_frontmatter_are_rejected() {
+        assert!(matches!(
+            parse_frontmatter("Just text.\n"),
+            Err(ParseError::MissingFrontmatter)
+        ));
+
+        let store = store(&[("loose", "Just text.\n")]);
+        let error = store.get_note("loose").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn ids_resolve_case_insensitively_unless_ambiguous() {
+        let note = "---\ntitle: Note\n---\nBody\n";
+        let store = store(&[("MyNote", note), ("Other", note), ("other", note)]);
+        assert_eq!(store.get_note("mynote").unwrap().unwrap().id, "MyNote");
+
+        // An exact match wins over other spellings
+        assert_eq!(store.get_note("other").unwrap().unwrap().id, "other");
+        // Either note could be meant
+        assert!(store.get_note("OTHER").unwrap().is_none());
+    }
+
+    #[test]
+    fn task_checkboxes_get_state_and_line() {
+        let markdown = "- [ ] todo\n- [x] done\n";
+        let html = "<ul>\n<li><input type=\"checkbox\" disabled=\"\" /> todo</li>\n\
+                    <li><input type=\"checkbox\" disabled=\"\" checked=\"\" /> done</li>\n</ul>\n";
+        assert_eq!(
+            process_task_lists(html, markdown, 3),
+            "<ul>\n<li><input type=\"checkbox\" class=\"task-todo\" data-line=\"4\" disabled=\"\" /> todo</li>\n\
+             <li><input type=\"checkbox\" class=\"task-done\" data-line=\"5\" disabled=\"\" checked=\"\" /> done</li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn task_item_lines_skip_code_blocks() {
+        let markdown =
+            "- [ ] first\n```\n- [ ] not a task\n```\n1. [x] second\n> - [X] quoted\n- [] no\n";
+        assert_eq!(task_item_lines(markdown), [1, 5, 6]);
+    }
+
+    #[test]
+    fn task_lists_render_with_state_classes() {
+        let content = render("- [ ] todo\n- [x] done\n");
+        assert!(content.contains("class=\"task-todo\""));
+        assert!(content.contains("class=\"task-done\""));
+    }
+
+    #[test]
+    fn notes_can_be_read_while_listing() {
+        let notes: Vec<(String, String)> = (0..40)
+            .map(|i| {
+                let content = format!("---\ntitle: Note {}\n---\nBody of note {}.\n", i, i);
+                (format!("note-{}", i), content)
+            })
+            .collect();
+        let store = NoteStore::in_memory(notes.into_iter().collect());
+
+        std::thread::scope(|scope| {
+            let listing = scope.spawn(|| store.list_notes());
+            for reader in 0..4 {
+                let store = &store;
+                scope.spawn(move || {
+                    for i in (reader..40).step_by(4) {
+                        let id = format!("note-{}", i);
+                        assert_eq!(store.get_note(&id).unwrap().unwrap().id, id);
+                    }
+                });
+            }
+            assert_eq!(listing.join().unwrap().unwrap().len(), 40);
+        });
+    }
+
+    #[test]
+    fn ignored_files_are_not_notes() {
+        let dir = temp_dir("ignore");
+        std::fs::create_dir_all(dir.join("_templates")).unwrap();
+        for file in ["kept.md", "scratch.md", "_templates/daily.md"] {
+            std::fs::write(dir.join(file), "---\ntitle: Note\n---\nBody\n").unwrap();
+        }
+        std::fs::write(dir.join(IGNORE_FILE), "_templates/*\nscratch.md\n").unwrap();
+
+        let store = NoteStore::new(&dir).unwrap();
+        let ids: Vec<String> = store
+            .list_notes()
+            .unwrap()
+            .into_iter()
+            .map(|note| note.id)
+            .collect();
+        assert_eq!(ids, ["kept"]);
+        assert!(store.get_note("scratch").unwrap().is_none());
+        assert!(store.get_note("_templates/daily").unwrap().is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn strongly_connected_components_find_cycles() {
+        // 0 -> 1 -> 2 -> 0 is a cycle, 3 links to itself, and 4 only links into the cycle
+        let edges = [vec![1], vec![2], vec![0], vec![3], vec![0]];
+        let mut components: Vec<Vec<usize>> = strongly_connected_components(&edges)
+            .into_iter()
+            .map(|mut component| {
+                component.sort();
+                component
+            })
+            .collect();
+        components.sort();
+        assert_eq!(components, [vec![0, 1, 2], vec![3], vec![4]]);
+    }
+
+    #[test]
+    fn strongly_connected_components_handle_long_chains() {
+        // Deep enough to overflow the stack if the traversal recursed
+        let length = 100_000;
+        let edges: Vec<Vec<usize>> = (0..length).map(|node| vec![(node + 1) % length]).collect();
+        let components = strongly_connected_components(&edges);
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0].len(), length);
+    }
+
+    #[test]
+    fn reference_cycles_are_grouped() {
+        let store = store(&[
+            ("a", "---\ntitle: A\n---\nSee [[b]].\n"),
+            ("b", "---\ntitle: B\nreferences: [a]\n---\nBody\n"),
+            ("c", "---\ntitle: C\n---\nSee [[c]] and [[missing]].\n"),
+            ("d", "---\ntitle: D\n---\nSee [[a]].\n"),
+        ]);
+        assert_eq!(
+            store.reference_cycles().unwrap(),
+            [vec!["a", "b"], vec!["c"]]
+        );
+    }
+
+    #[test]
+    fn callouts_replace_marked_blockquotes() {
+        for (marker, kind, title) in [
+            ("NOTE", "note", "Note"),
+            ("TIP", "tip", "Tip"),
+            ("IMPORTANT", "important", "Important"),
+            ("WARNING", "warning", "Warning"),
+            ("CAUTION", "caution", "Caution"),
+            ("DANGER", "danger", "Danger"),
+        ] {
+            let html = format!(
+                "<blockquote>\n<p>[!{}]\nBody text.</p>\n</blockquote>\n",
+                marker
+            );
+            assert_eq!(
+                process_callouts(&html),
+                format!(
+                    "<div class=\"callout callout-{}\">\n<p class=\"callout-title\">{}</p>\n<p>Body text.</p>\n</div>\n",
+                    kind, title
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn callouts_keep_custom_titles_and_unknown_types() {
+        let html = "<blockquote>\n<p>[!WARNING] Careful now</p>\n<p>Body.</p>\n</blockquote>\n";
+        assert_eq!(
+            process_callouts(html),
+            "<div class=\"callout callout-warning\">\n<p class=\"callout-title\">Careful now</p>\n<p>Body.</p>\n</div>\n"
+        );
+
+        let unknown = "<blockquote>\n<p>[!FOO]\nBody text.</p>\n</blockquote>\n";
+        assert_eq!(process_callouts(unknown), unknown);
+    }
+
+    #[test]
+    fn callouts_render_from_markdown() {
+        let content = render("> [!TIP]\n> Use **bold** text.\n");
+        assert!(content.contains(
+            "<div class=\"callout callout-tip\">\n<p class=\"callout-title\">Tip</p>\n<p>Use <strong>bold</strong> text.</p>\n</div>"
+        ));
+    }
+
+    #[test]
+    fn assets_are_sorted_by_name() {
+        let dir = temp_dir("assets");
+        let assets_dir = dir.join("note.assets");
+        std::fs::create_dir_all(&assets_dir).unwrap();
+        for name in ["c.txt", "a.png", "b.pdf"] {
+            std::fs::write(assets_dir.join(name), name).unwrap();
+        }
+
+        let names: Vec<String> = scan_assets(&dir.join("note.md"))
+            .into_iter()
+            .map(|asset| asset.name)
+            .collect();
+        assert_eq!(names, ["a.png", "b.pdf", "c.txt"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn note_title_falls_back_in_order() {
+        assert_eq!(note_title("Title", Some("Heading"), "my-note"), "Title");
+        assert_eq!(note_title("  ", Some("Heading"), "my-note"), "Heading");
+        assert_eq!(
+            note_title("", None, "projects/my-first_note"),
+            "My first note"
+        );
+    }
+
+    #[test]
+    fn first_markdown_heading_skips_code_and_lower_levels() {
+        let markdown = "```\n# Not this\n```\n## Nor this\n#hashtag\n# The *Heading* #\n";
+        assert_eq!(
+            first_markdown_heading(markdown).as_deref(),
+            Some("The *Heading*")
+        );
+    }
+
+    #[test]
+    fn missing_titles_fall_back_to_heading_then_id() {
+        let store = store(&[
+            ("titled", "---\ntitle: From Frontmatter\n---\n# Heading\n"),
+            ("headed", "---\ntags: [x]\n---\nIntro\n\n# The Heading\n"),
+            ("bare-note", "---\ntags: [x]\n---\nNo heading here.\n"),
+        ]);
+        let expected = [
+            ("bare-note", "Bare note"),
+            ("headed", "The Heading"),
+            ("titled", "From Frontmatter"),
+        ];
+
+        let metadata = store.list_metadata(false).unwrap();
+        assert_eq!(metadata.len(), expected.len());
+        for ((id, title), listed) in expected.into_iter().zip(&metadata) {
+            assert_eq!(listed.id, id);
+            assert_eq!(listed.title, title);
+            assert_eq!(store.get_note(id).unwrap().unwrap().title, title);
+        }
+    }
+
+    #[test]
+    fn math_in_code_blocks_is_left_alone() {
+        let content = render("```latex\n\\frac{1}{2} $x$\n```\n\n```\n$$y$$\n```\n");
+        assert!(!content.contains(KATEX));
+        assert!(strip_tags(&content).contains("\\frac{1}{2} $x$"));
+        assert!(content.contains("$$y$$"));
+    }
+
+    #[test]
+    fn bracket_delimiters_render_when_enabled() {
+        let brackets = store(&[]).with_math_delimiters(MathDelimiters {
+            dollars: false,
+            brackets: true,
+        });
+        let content = render_with(&brackets, "Inline \\(x^2\\) and $y$\n\n\\[\nz^2\n\\]\n");
+        assert_eq!(content.matches(KATEX).count(), 2);
+        assert_eq!(content.matches("<div class=\"math-display\">").count(), 1);
+        assert!(content.contains("$y$"));
+    }
+
+    #[test]
+    fn bracket_delimiters_are_escapes_by_default() {
+        let content = render("Inline \\(x^2\\) and $y$");
+        assert!(content.contains("Inline (x^2) and "));
+        assert_eq!(content.matches(KATEX).count(), 1);
+    }
+
+    #[test]
+    fn rename_wikilinks_keeps_headings_and_text() {
+        let markdown = "[[old]], [[old#Intro|the intro]], [[older]] and [[other]]\n";
+        assert_eq!(
+            rename_wikilinks(markdown, "old", "new"),
+            "[[new]], [[new#Intro|the intro]], [[older]] and [[other]]\n"
+        );
+    }
+
+    #[test]
+    fn rename_wikilinks_skips_code() {
+        let markdown = "~~~\n[[old]]\n~~~\n`[[old]]` is [[old]]\n";
+        assert_eq!(
+            rename_wikilinks(markdown, "old", "new"),
+            "~~~\n[[old]]\n~~~\n`[[old]]` is [[new]]\n"
+        );
+    }
+
+    #[test]
+    fn rename_note_updates_links_to_it() {
+        let store = store(&[
+            ("old", "---\ntitle: Old\n---\nBody\n"),
+            (
+                "linking",
+                "---\ntitle: Linking\nreferences: [old]\n---\nSee [[old#Intro|the intro]] and `[[old]]`.\n",
+            ),
+            ("unrelated", "---\ntitle: Unrelated\n---\nSee [[other]].\n"),
+        ]);
+        assert_eq!(store.rename_note("old", "new").unwrap(), ["linking"]);
+
+        assert!(store.get_note("old").unwrap().is_none());
+        assert_eq!(store.get_note("new").unwrap().unwrap().title, "Old");
+        // Links and references change; the rest of the note is kept as written
+        assert_eq!(
+            store.get_note_raw("linking").unwrap().unwrap(),
+            "---\ntitle: Linking\nreferences: [new]\n---\nSee [[new#Intro|the intro]] and `[[old]]`.\n"
+        );
+        let linking = store.get_note("linking").unwrap().unwrap();
+        assert_eq!(linking.references, ["new"]);
+    }
+
+    #[test]
+    fn rename_references_covers_each_format() {
+        let rename = |frontmatter: &str| rename_references(frontmatter, "old", "new");
+        assert_eq!(
+            rename("---\nreferences: [old, 'old', older]\nnote: old\n---\n"),
+            "---\nreferences: [new, 'new', older]\nnote: old\n---\n"
+        );
+        assert_eq!(
+            rename("---\nreferences:\n  - other\n  - \"old\"\ntags: [old]\n---\n"),
+            "---\nreferences:\n  - other\n  - \"new\"\ntags: [old]\n---\n"
+        );
+        assert_eq!(
+            rename("+++\nreferences = [\n  \"old\",\n  \"other\",\n]\n+++\n"),
+            "+++\nreferences = [\n  \"new\",\n  \"other\",\n]\n+++\n"
+        );
+        assert_eq!(
+            rename("{\n  \"title\": \"old\",\n  \"references\": [\"old\"]\n}\n"),
+            "{\n  \"title\": \"old\",\n  \"references\": [\"new\"]\n}\n"
+        );
+    }
+
+    #[test]
+    fn rename_note_refuses_taken_and_missing_ids() {
+        let note = "---\ntitle: Note\n---\nBody\n";
+        let store = store(&[("a", note), ("b", note)]);
+        let taken = store.rename_note("a", "b").unwrap_err();
+        assert_eq!(taken.kind(), std::io::ErrorKind::AlreadyExists);
+        let missing = store.rename_note("missing", "c").unwrap_err();
+        assert_eq!(missing.kind(), std::io::ErrorKind::NotFound);
+        assert!(store.note_exists("a"));
+    }
+
+    #[test]
+    fn embeds_are_marked_with_their_index() {
+        let markdown = "![[alpha]]\n\n```\n![[beta]]\n```\nSee ![[gamma|Gamma]] and [[delta]].\n";
+        let (rewritten, targets) = process_embeds(markdown);
+        assert_eq!(
+            rewritten,
+            "[alpha](/alpha \"miniref-embed-0\")\n\n```\n![[beta]]\n```\n\
+             See [Gamma](/gamma \"miniref-embed-1\") and [[delta]].\n"
+        );
+        assert_eq!(targets, ["alpha", "gamma"]);
+    }
+
+    #[test]
+    fn embeds_inline_the_embedded_note() {
+        let store = store(&[
+            (
+                "host",
+                "---\ntitle: Host\n---\n![[part]]\n\nAnd ![[host]] and ![[missing]].\n",
+            ),
+            ("part", "---\ntitle: Part\n---\nPart body.\n"),
+        ]);
+        let note = store.get_note("host").unwrap().unwrap();
+        assert!(note
+            .content
+            .contains("<section class=\"embed\" data-embed=\"part\"><p>Part body.</p>"));
+        // A note can't embed itself, and missing notes stay links
+        assert!(note.content.contains("<a href=\"/host\">host</a>"));
+        assert!(note.content.contains("<a href=\"/missing\">missing</a>"));
+        assert_eq!(note.references, ["part", "host", "missing"]);
+    }
+
+    #[test]
+    fn in_memory_notes_are_rendered_and_cached() {
+        let store = store(&[(
+            "note",
+            "---\ntitle: Note\ntags: [Rust]\n---\n# Hello\n\nSome *text*.\n",
+        )]);
+        let note = store.get_note("note").unwrap().unwrap();
+        assert_eq!(note.tags, ["rust"]);
+        assert!(note.content.contains("<em>text</em>"));
+
+        assert_eq!(store.get_note("note").unwrap(), Some(note));
+        assert_eq!(
+            store.cache_stats(),
+            CacheStats {
+                entries: 1,
+                hits: 1,
+                misses: 1
+            }
+        );
+    }
+
+    #[test]
+    fn in_memory_notes_can_be_written() {
+        let store = store(&[]);
+        let note = Note {
+            id: "new".to_string(),
+            title: "New".to_string(),
+            ..Default::default()
+        };
+        store.create_note(&note, "Written *here*.\n").unwrap();
+
+        let created = store.get_note("new").unwrap().unwrap();
+        assert_eq!(created.title, "New");
+        assert!(created.content.contains("<em>here</em>"));
+        assert_eq!(
+            store.create_note(&note, "Again\n").unwrap_err().kind(),
+            std::io::ErrorKind::AlreadyExists
+        );
+    }
+
+    #[test]
+    fn smart_typography_pairs_quotes_across_emphasis() {
+        let html = "<p>He said &quot;<em>hi</em>&quot; -- 'twas the '90s --- and so on...</p>";
+        assert_eq!(
+            smarten_typography(html),
+            "<p>He said \u{201C}<em>hi</em>\u{201D} \u{2013} \u{2018}twas the \u{2019}90s \u{2014} and so on\u{2026}</p>"
+        );
+    }
+
+    #[test]
+    fn smart_typography_skips_code() {
+        let html =
+            "<p>Run <code>a -- &quot;b&quot;...</code></p>\n<pre><code>x = 'y' -- z\n</code></pre>";
+        assert_eq!(smarten_typography(html), html);
+    }
+
+    #[test]
+    fn smart_typography_is_opt_in() {
+        let body = "\"Quoted\" -- text...\n";
+        assert!(render(body).contains("<p>&quot;Quoted&quot; -- text...</p>"));
+
+        let smart = store(&[]).with_smart_typography(true);
+        assert!(render_with(&smart, body)
+            .contains("<p>\u{201C}Quoted\u{201D} \u{2013} text\u{2026}</p>"));
+    }
+
+    #[test]
+    fn notes_without_modified_time_are_read_uncached() {
+        let notes = HashMap::from([(
+            "note".to_string(),
+            "---\ntitle: Note\n---\nBody\n".to_string(),
+        )]);
+        let store = NoteStore::with_backend(NoModifiedTime(MemoryBackend::new(notes)));
+        assert!(store.note_exists("note"));
+        assert!(!store.note_exists("missing"));
+
+        for _ in 0..2 {
+            assert_eq!(store.get_note("note").unwrap().unwrap().title, "Note");
+        }
+        assert!(store.get_note("missing").unwrap().is_none());
+        // The cache entry can't be validated, so every read parses the note again
+        let stats = store.cache_stats();
+        assert_eq!((stats.hits, stats.misses), (0, 2));
+
+        // No time is made up where one is shown
+        assert_eq!(
+            store.sitemap_entries().unwrap(),
+            [("note".to_string(), None)]
+        );
+        assert_eq!(store.get_note("note").unwrap().unwrap().modified, None);
+    }
+
+    #[test]
+    fn cursor_pages_cover_every_published_note_once() {
+        let note = "---\ntitle: Note\n---\nBody\n";
+        let draft = "---\ntitle: Draft\ndraft: true\n---\nBody\n";
+        let store = store(&[
+            ("a", note),
+            ("b", note),
+            ("c", draft),
+            ("d", note),
+            ("e", note),
+        ]);
+
+        let first = store.list_notes_paged(None, 2).unwrap();
+        assert_eq!(page_ids(&first), ["a", "b"]);
+        assert_eq!(first.next_cursor.as_deref(), Some("b"));
+
+        // The last page has no cursor, even when it's full
+        let last = store.list_notes_paged(first.next_cursor, 2).unwrap();
+        assert_eq!(page_ids(&last), ["d", "e"]);
+        assert_eq!(last.next_cursor, None);
+    }
+
+    #[test]
+    fn cursor_paging_edge_cases() {
+        assert_eq!(
+            store(&[]).list_notes_paged(None, 10).unwrap(),
+            NoteListPage::default()
+        );
+
+        let note = "---\ntitle: Note\n---\nBody\n";
+        let store = store(&[("a", note), ("c", note)]);
+
+        // A limit of zero still makes progress
+        let page = store.list_notes_paged(None, 0).unwrap();
+        assert_eq!(page_ids(&page), ["a"]);
+        assert_eq!(page.next_cursor.as_deref(), Some("a"));
+
+        // A cursor whose note is gone continues after where it was
+        let page = store.list_notes_paged(Some("b".to_string()), 10).unwrap();
+        assert_eq!(page_ids(&page), ["c"]);
+        assert_eq!(page.next_cursor, None);
+
+        let page = store.list_notes_paged(Some("z".to_string()), 10).unwrap();
+        assert_eq!(page, NoteListPage::default());
+    }
+
+    #[test]
+    fn sitemap_entries_leave_out_drafts() {
+        let store = store(&[
+            ("published", "---\ntitle: Published\n---\nBody\n"),
+            ("draft", "---\ntitle: Draft\ndraft: true\n---\nBody\n"),
+        ]);
+        let ids: Vec<String> = store
+            .sitemap_entries()
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(ids, ["published"]);
+    }
+}